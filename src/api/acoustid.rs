@@ -0,0 +1,137 @@
+use super::MetadataResult;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Recordings below this AcoustID score are treated as no match.
+const MIN_SCORE: f64 = 0.8;
+
+#[derive(Debug, Deserialize)]
+struct Fingerprint {
+    duration: f64,
+    fingerprint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupResponse {
+    status: String,
+    #[serde(default)]
+    results: Vec<LookupResult>,
+    error: Option<LookupError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LookupResult {
+    score: f64,
+    #[serde(default)]
+    recordings: Vec<Recording>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Recording {
+    /// MusicBrainz recording ID
+    id: Option<String>,
+    title: Option<String>,
+    #[serde(default)]
+    artists: Vec<Artist>,
+    #[serde(default)]
+    releasegroups: Vec<ReleaseGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Artist {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseGroup {
+    id: String,
+    title: Option<String>,
+}
+
+/// Runs Chromaprint's `fpcalc` on the file.
+async fn fingerprint(path: &Path) -> Result<Fingerprint, String> {
+    let output = tokio::process::Command::new("fpcalc")
+        .arg("-json")
+        .arg(path)
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                "fpcalc was not found. Install Chromaprint and make sure fpcalc is on your PATH.".to_string()
+            }
+            _ => format!("Could not run fpcalc: {}", e),
+        })?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "fpcalc failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Could not read fpcalc output: {}", e))
+}
+
+/// Identifies a file by its audio fingerprint and returns the best-scoring recording.
+pub async fn identify(path: &Path, api_key: &str) -> Result<MetadataResult, String> {
+    if api_key.is_empty() {
+        return Err("AcoustID API Key is missing".to_string());
+    }
+    if super::is_offline() {
+        return Err(super::OFFLINE_ERROR.to_string());
+    }
+
+    let fp = fingerprint(path).await?;
+    let params = [
+        ("client", api_key.to_string()),
+        ("meta", "recordings releasegroups".to_string()),
+        ("duration", (fp.duration.round() as u64).to_string()),
+        ("fingerprint", fp.fingerprint),
+    ];
+
+    // Fingerprints are too long for a query string, so post them as a form
+    let response = super::http_client()
+        .post("https://api.acoustid.org/v2/lookup")
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| format!("AcoustID request failed: {}", e))?
+        .json::<LookupResponse>()
+        .await
+        .map_err(|e| format!("AcoustID parse failed: {}", e))?;
+
+    if response.status != "ok" {
+        let message = response.error.map(|e| e.message).unwrap_or(response.status);
+        return Err(format!("AcoustID error: {}", message));
+    }
+
+    let best = response
+        .results
+        .into_iter()
+        .filter(|r| r.score >= MIN_SCORE)
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+        .and_then(|r| r.recordings.into_iter().find(|rec| rec.title.is_some()))
+        .ok_or("No confident match for this recording")?;
+
+    let release_group = best.releasegroups.first();
+    Ok(MetadataResult {
+        title: best.title.unwrap_or_default(),
+        artist: best.artists.iter().map(|a| a.name.as_str()).collect::<Vec<_>>().join(", "),
+        album: release_group.and_then(|g| g.title.clone()).unwrap_or_default(),
+        year: None,
+        track: None,
+        cover_urls: release_group
+            .map(|g| ["500", "1200"].iter().map(|size| format!("https://coverartarchive.org/release-group/{}/front-{}", g.id, size)).collect())
+            .unwrap_or_default(),
+        source: "AcoustID".to_string(),
+        musicbrainz_id: best.id,
+        spotify_id: None,
+        isrc: None,
+        explicit: None,
+    })
+}
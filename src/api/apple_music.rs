@@ -1,4 +1,4 @@
-use super::MetadataResult;
+use super::{AlbumResult, MetadataResult, SearchOptions};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -14,30 +14,203 @@ struct ItunesTrack {
     artist_name: Option<String>,
     #[serde(rename = "collectionName")]
     collection_name: Option<String>,
+    #[serde(rename = "trackNumber")]
+    track_number: Option<u32>,
+    #[serde(rename = "releaseDate")]
+    release_date: Option<String>,
     #[serde(rename = "artworkUrl100")]
     artwork_url: Option<String>,
+    #[serde(rename = "trackExplicitness")]
+    track_explicitness: Option<String>,
 }
 
-pub async fn search(term: &str) -> Result<Vec<MetadataResult>, String> {
-    let url = format!(
-        "https://itunes.apple.com/search?term={}&media=music&entity=song&limit=10",
-        urlencoding::encode(term)
+/// "explicit", "cleaned" or "notExplicit"; anything else is treated as unknown.
+fn explicitness(value: Option<&str>) -> Option<bool> {
+    match value? {
+        "explicit" => Some(true),
+        "cleaned" | "notExplicit" => Some(false),
+        _ => None,
+    }
+}
+
+/// The artwork URL comes at 100x100; the same path serves larger renditions.
+fn artwork_candidates(url: Option<&str>) -> Vec<String> {
+    url.map(|u| ["600x600", "1200x1200"].iter().map(|size| u.replace("100x100", size)).collect())
+        .unwrap_or_default()
+}
+
+/// Maps a song search response to results, with artwork upscaled from the 100x100 URL.
+fn parse_itunes(response: ItunesResponse) -> Vec<MetadataResult> {
+    response.results.into_iter().map(|t| MetadataResult {
+        title: t.track_name.unwrap_or_default(),
+        artist: t.artist_name.unwrap_or_default(),
+        album: t.collection_name.unwrap_or_default(),
+        year: t.release_date.as_deref().and_then(super::parse_year),
+        track: t.track_number,
+        cover_urls: artwork_candidates(t.artwork_url.as_deref()),
+        source: "Apple Music".to_string(),
+        musicbrainz_id: None,
+        spotify_id: None,
+        isrc: None,
+        explicit: explicitness(t.track_explicitness.as_deref()),
+    }).collect()
+}
+
+pub async fn search(term: &str, options: &SearchOptions) -> Result<Vec<MetadataResult>, String> {
+    let mut url = format!(
+        "https://itunes.apple.com/search?term={}&media=music&entity=song&limit={}",
+        urlencoding::encode(term),
+        options.limit
     );
+    if !options.country.is_empty() {
+        url.push_str(&format!("&country={}", options.country));
+    }
 
-    let response = reqwest::get(&url)
+    let response = super::http_client()
+        .get(&url)
+        .send()
         .await
         .map_err(|e| format!("Request failed: {}", e))?
         .json::<ItunesResponse>()
         .await
         .map_err(|e| format!("Parse failed: {}", e))?;
 
-    let results = response.results.into_iter().map(|t| MetadataResult {
-        title: t.track_name.unwrap_or_default(),
-        artist: t.artist_name.unwrap_or_default(),
-        album: t.collection_name.unwrap_or_default(),
-        cover_url: t.artwork_url.map(|u| u.replace("100x100", "600x600")),
-        source: "Apple Music".to_string(),
-    }).collect();
+    Ok(parse_itunes(response))
+}
+
+#[derive(Debug, Deserialize)]
+struct ItunesAlbumResponse {
+    results: Vec<ItunesAlbum>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItunesAlbum {
+    #[serde(rename = "collectionId")]
+    collection_id: u64,
+    #[serde(rename = "collectionName")]
+    collection_name: Option<String>,
+    #[serde(rename = "artistName")]
+    artist_name: Option<String>,
+    #[serde(rename = "releaseDate")]
+    release_date: Option<String>,
+    #[serde(rename = "artworkUrl100")]
+    artwork_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItunesLookupResponse {
+    results: Vec<ItunesLookupItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItunesLookupItem {
+    #[serde(rename = "wrapperType")]
+    wrapper_type: String,
+    #[serde(rename = "trackName")]
+    track_name: Option<String>,
+    #[serde(rename = "artistName")]
+    artist_name: Option<String>,
+    #[serde(rename = "trackNumber")]
+    track_number: Option<u32>,
+    #[serde(rename = "trackExplicitness")]
+    track_explicitness: Option<String>,
+}
+
+pub async fn search_album(term: &str, options: &SearchOptions) -> Result<Vec<AlbumResult>, String> {
+    let mut url = format!(
+        "https://itunes.apple.com/search?term={}&media=music&entity=album&limit={}",
+        urlencoding::encode(term),
+        options.limit
+    );
+    if !options.country.is_empty() {
+        url.push_str(&format!("&country={}", options.country));
+    }
+
+    let response = super::http_client()
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?
+        .json::<ItunesAlbumResponse>()
+        .await
+        .map_err(|e| format!("Parse failed: {}", e))?;
+
+    let mut albums = Vec::new();
+    for a in response.results {
+        let title = a.collection_name.unwrap_or_default();
+        let artist = a.artist_name.unwrap_or_default();
+        let year = a.release_date.as_deref().and_then(super::parse_year);
+        let cover_urls = artwork_candidates(a.artwork_url.as_deref());
+
+        let tracks = album_tracks(a.collection_id, options)
+            .await?
+            .into_iter()
+            .map(|t| MetadataResult {
+                title: t.track_name.unwrap_or_default(),
+                artist: t.artist_name.unwrap_or_else(|| artist.clone()),
+                album: title.clone(),
+                year,
+                track: t.track_number,
+                cover_urls: cover_urls.clone(),
+                source: "Apple Music".to_string(),
+                musicbrainz_id: None,
+                spotify_id: None,
+                isrc: None,
+                explicit: explicitness(t.track_explicitness.as_deref()),
+            })
+            .collect();
+
+        albums.push(AlbumResult {
+            title,
+            artist,
+            year,
+            source: "Apple Music".to_string(),
+            tracks,
+        });
+    }
+
+    Ok(albums)
+}
+
+async fn album_tracks(collection_id: u64, options: &SearchOptions) -> Result<Vec<ItunesLookupItem>, String> {
+    let mut url = format!("https://itunes.apple.com/lookup?id={}&entity=song", collection_id);
+    if !options.country.is_empty() {
+        url.push_str(&format!("&country={}", options.country));
+    }
+
+    let response = super::http_client()
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?
+        .json::<ItunesLookupResponse>()
+        .await
+        .map_err(|e| format!("Parse failed: {}", e))?;
+
+    // The first item describes the album itself
+    Ok(response.results.into_iter().filter(|i| i.wrapper_type == "track").collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_song_search() {
+        let response: ItunesResponse = serde_json::from_str(include_str!("testdata/apple_music.json")).unwrap();
+        let results = parse_itunes(response);
 
-    Ok(results)
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.title, "One More Time");
+        assert_eq!(result.artist, "Daft Punk");
+        assert_eq!(result.album, "Discovery");
+        assert_eq!(result.year, Some(2001));
+        assert_eq!(result.track, Some(1));
+        assert_eq!(result.explicit, Some(false));
+        assert_eq!(result.cover_urls, vec![
+            "https://is1-ssl.mzstatic.com/image/thumb/Music/v4/discovery/600x600bb.jpg",
+            "https://is1-ssl.mzstatic.com/image/thumb/Music/v4/discovery/1200x1200bb.jpg",
+        ]);
+    }
 }
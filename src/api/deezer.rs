@@ -0,0 +1,107 @@
+use super::{MetadataResult, SearchOptions};
+use serde::Deserialize;
+
+/// Deezer answers errors with HTTP 200 and an `error` object instead of `data`.
+#[derive(Debug, Deserialize)]
+struct DeezerResponse {
+    data: Option<Vec<DeezerTrack>>,
+    error: Option<DeezerError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeezerError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeezerTrack {
+    title: String,
+    artist: DeezerArtist,
+    album: DeezerAlbum,
+    explicit_lyrics: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeezerArtist {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeezerAlbum {
+    title: String,
+    cover_xl: Option<String>,
+    cover_big: Option<String>,
+}
+
+/// Maps a search response to results, or to the error Deezer reported in its body.
+fn parse_deezer(response: DeezerResponse) -> Result<Vec<MetadataResult>, String> {
+    if let Some(error) = response.error {
+        return Err(error.message);
+    }
+
+    Ok(response.data.unwrap_or_default().into_iter().map(|t| MetadataResult {
+        title: t.title,
+        artist: t.artist.name,
+        album: t.album.title,
+        year: None,
+        track: None,
+        // 1000x1000, then 500x500
+        cover_urls: [t.album.cover_xl, t.album.cover_big].into_iter().flatten().filter(|u| !u.is_empty()).collect(),
+        source: "Deezer".to_string(),
+        musicbrainz_id: None,
+        spotify_id: None,
+        isrc: None,
+        explicit: t.explicit_lyrics,
+    }).collect())
+}
+
+/// Keyless track search. Deezer's search results carry no release date or track
+/// number, and it has no country parameter, so only the limit applies.
+pub async fn search(term: &str, options: &SearchOptions) -> Result<Vec<MetadataResult>, String> {
+    let url = format!(
+        "https://api.deezer.com/search?q={}&limit={}",
+        urlencoding::encode(term),
+        options.limit
+    );
+
+    let response = super::http_client()
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?
+        .json::<DeezerResponse>()
+        .await
+        .map_err(|e| format!("Parse failed: {}", e))?;
+
+    parse_deezer(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_track_search() {
+        let response: DeezerResponse = serde_json::from_str(include_str!("testdata/deezer.json")).unwrap();
+        let results = parse_deezer(response).unwrap();
+
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.title, "One More Time");
+        assert_eq!(result.artist, "Daft Punk");
+        assert_eq!(result.album, "Discovery");
+        assert_eq!(result.year, None);
+        assert_eq!(result.track, None);
+        assert_eq!(result.cover_urls, vec![
+            "https://e-cdns-images.dzcdn.net/images/cover/discovery/1000x1000-000000-80-0-0.jpg",
+            "https://e-cdns-images.dzcdn.net/images/cover/discovery/500x500-000000-80-0-0.jpg",
+        ]);
+    }
+
+    #[test]
+    fn reports_error_body() {
+        let response: DeezerResponse =
+            serde_json::from_str(r#"{"error":{"type":"Exception","message":"Quota limit exceeded","code":4}}"#).unwrap();
+        assert_eq!(parse_deezer(response).unwrap_err(), "Quota limit exceeded");
+    }
+}
@@ -1,4 +1,4 @@
-use super::MetadataResult;
+use super::{MetadataResult, SearchOptions};
 use serde::Deserialize;
 use reqwest::header::AUTHORIZATION;
 
@@ -22,6 +22,38 @@ struct GeniusSong {
     title: String,
     artist_names: String,
     song_art_image_url: Option<String>,
+    header_image_url: Option<String>,
+    release_date_components: Option<GeniusDate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeniusDate {
+    year: Option<u32>,
+}
+
+/// Maps search hits to results. Genius has no album data; the song art comes first,
+/// then the header image when it differs.
+fn parse_genius(response: GeniusSearchResponse) -> Vec<MetadataResult> {
+    response.response.hits.into_iter().map(|hit| {
+        let mut cover_urls: Vec<String> = hit.result.song_art_image_url.into_iter().collect();
+        if let Some(header) = hit.result.header_image_url.filter(|h| !cover_urls.contains(h)) {
+            cover_urls.push(header);
+        }
+
+        MetadataResult {
+            title: hit.result.title,
+            artist: hit.result.artist_names,
+            album: "Unknown (Genius)".to_string(),
+            year: hit.result.release_date_components.and_then(|d| d.year),
+            track: None,
+            cover_urls,
+            source: "Genius".to_string(),
+            musicbrainz_id: None,
+            spotify_id: None,
+            isrc: None,
+            explicit: None,
+        }
+    }).collect()
 }
 
 pub struct GeniusClient {
@@ -33,15 +65,16 @@ impl GeniusClient {
         Self { access_token }
     }
 
-    pub async fn search(&self, term: &str) -> Result<Vec<MetadataResult>, String> {
+    pub async fn search(&self, term: &str, options: &SearchOptions) -> Result<Vec<MetadataResult>, String> {
         if self.access_token.is_empty() {
             return Err("Genius Access Token is missing".to_string());
         }
 
-        let client = reqwest::Client::new();
+        let client = super::http_client();
         let url = format!(
-            "https://api.genius.com/search?q={}",
-            urlencoding::encode(term)
+            "https://api.genius.com/search?q={}&per_page={}",
+            urlencoding::encode(term),
+            options.limit
         );
 
         let response = client
@@ -60,16 +93,29 @@ impl GeniusClient {
             .await
             .map_err(|e| format!("Genius parse failed: {}", e))?;
 
-        let results = genius_res.response.hits.into_iter().map(|hit| {
-            MetadataResult {
-                title: hit.result.title,
-                artist: hit.result.artist_names,
-                album: "Unknown (Genius)".to_string(),
-                cover_url: hit.result.song_art_image_url,
-                source: "Genius".to_string(),
-            }
-        }).collect();
-
-        Ok(results)
+        Ok(parse_genius(genius_res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_search_hits() {
+        let response: GeniusSearchResponse = serde_json::from_str(include_str!("testdata/genius.json")).unwrap();
+        let results = parse_genius(response);
+
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.title, "One More Time");
+        assert_eq!(result.artist, "Daft Punk");
+        assert_eq!(result.album, "Unknown (Genius)");
+        assert_eq!(result.year, Some(2000));
+        assert_eq!(result.track, None);
+        assert_eq!(result.cover_urls, vec![
+            "https://images.genius.com/discovery.1000x1000x1.jpg",
+            "https://images.genius.com/discovery-header.1000x1000x1.jpg",
+        ]);
     }
 }
@@ -1,4 +1,4 @@
-use super::MetadataResult;
+use super::{MetadataResult, SearchOptions};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -20,6 +20,8 @@ struct LastFmTrackMatches {
 struct LastFmTrack {
     name: String,
     artist: String,
+    /// MusicBrainz recording ID; often empty
+    mbid: Option<String>,
     image: Option<Vec<LastFmImage>>,
 }
 
@@ -30,6 +32,44 @@ struct LastFmImage {
     size: String,
 }
 
+// Last.fm serves this grey star image when it has no real artwork
+const PLACEHOLDER_IMAGE_IDS: [&str; 1] = ["2a96cbd8b46e442fc41c2b86b821562f"];
+
+fn is_placeholder(url: &str) -> bool {
+    PLACEHOLDER_IMAGE_IDS.iter().any(|id| url.contains(id))
+}
+
+/// Real (non-placeholder) images, largest first.
+fn candidate_images(images: &[LastFmImage]) -> Vec<String> {
+    ["extralarge", "large"].iter().filter_map(|size| {
+        images
+            .iter()
+            .find(|i| i.size == *size && !i.url.is_empty() && !is_placeholder(&i.url))
+            .map(|i| i.url.clone())
+    }).collect()
+}
+
+/// Maps track matches to results. Last.fm search has no album, year or track number.
+fn parse_lastfm(response: LastFmSearchResponse) -> Vec<MetadataResult> {
+    response.results.trackmatches.track.into_iter().map(|track| {
+        let cover_urls = track.image.as_deref().map(candidate_images).unwrap_or_default();
+
+        MetadataResult {
+            title: track.name,
+            artist: track.artist,
+            album: "Unknown (Last.fm)".to_string(),
+            year: None,
+            track: None,
+            cover_urls,
+            source: "Last.fm".to_string(),
+            musicbrainz_id: track.mbid.filter(|id| !id.is_empty()),
+            spotify_id: None,
+            isrc: None,
+            explicit: None,
+        }
+    }).collect()
+}
+
 pub struct LastFmClient {
     api_key: String,
 }
@@ -39,18 +79,22 @@ impl LastFmClient {
         Self { api_key }
     }
 
-    pub async fn search(&self, term: &str) -> Result<Vec<MetadataResult>, String> {
+    /// `track.search` has no locale or country parameter, so only the limit applies here.
+    pub async fn search(&self, term: &str, options: &SearchOptions) -> Result<Vec<MetadataResult>, String> {
         if self.api_key.is_empty() {
             return Err("Last.fm API Key is missing".to_string());
         }
 
         let url = format!(
-            "http://ws.audioscrobbler.com/2.0/?method=track.search&track={}&api_key={}&format=json",
+            "http://ws.audioscrobbler.com/2.0/?method=track.search&track={}&api_key={}&limit={}&format=json",
             urlencoding::encode(term),
-            self.api_key
+            self.api_key,
+            options.limit
         );
 
-        let response = reqwest::get(&url)
+        let response = super::http_client()
+            .get(&url)
+            .send()
             .await
             .map_err(|e| format!("Last.fm request failed: {}", e))?;
 
@@ -63,28 +107,56 @@ impl LastFmClient {
             .await
             .map_err(|e| format!("Last.fm parse failed: {}", e))?;
 
-        let results = lastfm_res.results.trackmatches.track.into_iter().map(|track| {
-            let mut best_image = None;
-            if let Some(images) = track.image {
-                if let Some(img) = images.iter().find(|i| i.size == "extralarge") {
-                    if !img.url.is_empty() { best_image = Some(img.url.clone()); }
-                }
-                if best_image.is_none() {
-                     if let Some(img) = images.iter().find(|i| i.size == "large") {
-                        if !img.url.is_empty() { best_image = Some(img.url.clone()); }
-                    }
-                }
-            }
-
-            MetadataResult {
-                title: track.name,
-                artist: track.artist,
-                album: "Unknown (Last.fm)".to_string(),
-                cover_url: best_image,
-                source: "Last.fm".to_string(),
-            }
-        }).collect();
-
-        Ok(results)
+        Ok(parse_lastfm(lastfm_res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_track_matches() {
+        let response: LastFmSearchResponse = serde_json::from_str(include_str!("testdata/lastfm.json")).unwrap();
+        let results = parse_lastfm(response);
+
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.title, "One More Time");
+        assert_eq!(result.artist, "Daft Punk");
+        assert_eq!(result.album, "Unknown (Last.fm)");
+        assert_eq!(result.year, None);
+        assert_eq!(result.track, None);
+        assert_eq!(result.musicbrainz_id.as_deref(), Some("7a3ee6f6-2b4e-4c8a-9a3e-7e5c3c3a7c0e"));
+        assert_eq!(result.cover_urls, vec![
+            "https://lastfm.freetls.fastly.net/i/u/300x300/discovery.png",
+            "https://lastfm.freetls.fastly.net/i/u/174s/discovery.png",
+        ]);
+    }
+
+    fn image(url: &str, size: &str) -> LastFmImage {
+        LastFmImage { url: url.to_string(), size: size.to_string() }
+    }
+
+    #[test]
+    fn candidate_images_are_largest_first() {
+        let images = [
+            image("https://example.com/medium.png", "medium"),
+            image("https://example.com/large.png", "large"),
+            image("https://example.com/extralarge.png", "extralarge"),
+        ];
+        assert_eq!(candidate_images(&images), vec!["https://example.com/extralarge.png", "https://example.com/large.png"]);
+    }
+
+    #[test]
+    fn candidate_images_skip_empty_and_placeholder_urls() {
+        let images = [
+            image("", "extralarge"),
+            image("https://example.com/2a96cbd8b46e442fc41c2b86b821562f.png", "large"),
+        ];
+        assert!(candidate_images(&images).is_empty());
+
+        let images = [image("", "extralarge"), image("https://example.com/large.png", "large")];
+        assert_eq!(candidate_images(&images), vec!["https://example.com/large.png"]);
     }
 }
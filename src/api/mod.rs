@@ -1,4 +1,6 @@
+pub mod acoustid;
 pub mod apple_music;
+pub mod deezer;
 pub mod spotify;
 pub mod genius;
 pub mod lastfm;
@@ -8,56 +10,431 @@ pub struct MetadataResult {
     pub title: String,
     pub artist: String,
     pub album: String,
-    pub cover_url: Option<String>,
+    pub year: Option<u32>,
+    pub track: Option<u32>,
+    /// Candidate covers, e.g. different sizes, in order of preference
+    pub cover_urls: Vec<String>,
     pub source: String,
+    /// MusicBrainz recording ID
+    pub musicbrainz_id: Option<String>,
+    pub spotify_id: Option<String>,
+    pub isrc: Option<String>,
+    /// None when the provider doesn't say whether the track is explicit
+    pub explicit: Option<bool>,
+}
+
+impl MetadataResult {
+    /// The cover used unless the user picks another candidate.
+    pub fn cover_url(&self) -> Option<&str> {
+        self.cover_urls.first().map(String::as_str)
+    }
+}
+
+/// The metadata sources NaviTag can search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Provider {
+    AppleMusic,
+    Spotify,
+    Genius,
+    LastFm,
+    Deezer,
+}
+
+impl std::fmt::Display for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Provider::AppleMusic => "Apple Music",
+            Provider::Spotify => "Spotify",
+            Provider::Genius => "Genius",
+            Provider::LastFm => "Last.fm",
+            Provider::Deezer => "Deezer",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl Provider {
+    pub const ALL: [Provider; 5] = [Provider::AppleMusic, Provider::Spotify, Provider::Genius, Provider::LastFm, Provider::Deezer];
+
+    pub fn is_enabled(&self, settings: &UserSettings) -> bool {
+        match self {
+            Provider::AppleMusic => settings.enable_apple_music,
+            Provider::Spotify => settings.enable_spotify,
+            Provider::Genius => settings.enable_genius,
+            Provider::LastFm => settings.enable_lastfm,
+            Provider::Deezer => settings.enable_deezer,
+        }
+    }
+
+    /// Names of the credential fields this provider needs that are blank in `settings`.
+    pub fn missing_credentials(&self, settings: &UserSettings) -> Vec<&'static str> {
+        let fields = match self {
+            Provider::Spotify => vec![("Client ID", &settings.spotify_id), ("Client Secret", &settings.spotify_secret)],
+            Provider::Genius => vec![("Access Token", &settings.genius_token)],
+            Provider::LastFm => vec![("API Key", &settings.lastfm_api_key)],
+            Provider::AppleMusic | Provider::Deezer => Vec::new(),
+        };
+        fields.into_iter().filter(|(_, value)| value.trim().is_empty()).map(|(name, _)| name).collect()
+    }
+
+    /// Enabled and with every credential it needs, so searching it can return anything.
+    pub fn is_usable(&self, settings: &UserSettings) -> bool {
+        self.is_enabled(settings) && self.missing_credentials(settings).is_empty()
+    }
+}
+
+/// One line per provider that is enabled but skipped by searches because a credential
+/// is blank, e.g. "Spotify enabled but missing Client ID".
+pub fn unconfigured_providers(settings: &UserSettings) -> Vec<String> {
+    Provider::ALL
+        .iter()
+        .filter(|provider| provider.is_enabled(settings))
+        .filter_map(|provider| {
+            let missing = provider.missing_credentials(settings);
+            (!missing.is_empty()).then(|| format!("{} enabled but missing {}", provider, missing.join(" and ")))
+        })
+        .collect()
+}
+
+/// Everything one search produced: the combined results plus the providers that failed.
+/// Track searches yield `MetadataResult`s, album searches `AlbumResult`s.
+#[derive(Debug, Clone)]
+pub struct SearchOutcome<T = MetadataResult> {
+    pub results: Vec<T>,
+    pub errors: Vec<(Provider, String)>,
+}
+
+impl<T> Default for SearchOutcome<T> {
+    fn default() -> Self {
+        Self { results: Vec::new(), errors: Vec::new() }
+    }
+}
+
+impl<T> SearchOutcome<T> {
+    /// One line per failed provider, e.g. "Spotify: authentication failed".
+    pub fn error_summary(&self) -> String {
+        self.errors
+            .iter()
+            .map(|(provider, error)| format!("{}: {}", provider, error))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// An album and its tracklist, for tagging a whole folder in one go.
+#[derive(Debug, Clone)]
+pub struct AlbumResult {
+    pub title: String,
+    pub artist: String,
+    pub year: Option<u32>,
+    pub source: String,
+    /// Each track carries the album's title, year and cover as well
+    pub tracks: Vec<MetadataResult>,
+}
+
+/// Album search makes one extra request per album for its tracks, so keep the list short.
+pub const MAX_ALBUM_RESULTS: u32 = 5;
+
+/// Per-search parameters shared by every provider.
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub limit: u32,
+    /// ISO 3166-1 alpha-2 code, or empty to let the provider decide
+    pub country: String,
+}
+
+impl SearchOptions {
+    pub fn from_settings(settings: &UserSettings) -> Self {
+        Self {
+            limit: settings.result_limit(),
+            country: settings.country_code().unwrap_or_default(),
+        }
+    }
+}
+
+/// Year from a provider date such as "2019-05-10T07:00:00Z", "1981-12" or "1981".
+pub(crate) fn parse_year(date: &str) -> Option<u32> {
+    date.get(..4)
+        .and_then(|y| y.parse().ok())
+        .filter(|y| *y > 0)
 }
 
 use crate::settings::UserSettings;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Returned instead of making a request while offline mode is on.
+pub const OFFLINE_ERROR: &str = "Offline mode is on";
 
-pub async fn search_all(term: String, settings: UserSettings) -> Vec<MetadataResult> {
-    let mut results = Vec::new();
+/// Set from the offline setting by `set_offline`.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Stops (true) or allows every provider request and cover download. Covers already in
+/// the cover cache are still served while offline.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Rebuilt by `configure_proxy` when the proxy settings change.
+static CLIENT: RwLock<Option<reqwest::Client>> = RwLock::new(None);
+
+/// One client for every request the app makes, so connections and TLS sessions are reused.
+/// Until `configure_proxy` runs, proxies come from the environment.
+pub fn http_client() -> reqwest::Client {
+    if let Some(client) = CLIENT.read().ok().and_then(|client| client.clone()) {
+        return client;
+    }
+    let client = build_client("", "").unwrap_or_default();
+    if let Ok(mut slot) = CLIENT.write() {
+        slot.get_or_insert_with(|| client.clone());
+    }
+    client
+}
+
+/// Routes every provider request and cover download through the given proxies. Empty
+/// values fall back to the http_proxy / https_proxy environment variables. On a malformed
+/// URL the current client is kept and the error is returned.
+pub fn configure_proxy(http_proxy: &str, https_proxy: &str) -> Result<(), String> {
+    let client = build_client(http_proxy, https_proxy)?;
+    if let Ok(mut slot) = CLIENT.write() {
+        *slot = Some(client);
+    }
+    Ok(())
+}
+
+/// Checks that a proxy setting is empty or a full http(s) URL such as "http://proxy:8080".
+pub fn validate_proxy_url(value: &str) -> Result<(), String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(());
+    }
+    let url = reqwest::Url::parse(value).map_err(|e| format!("\"{}\" is not a valid proxy URL: {}", value, e))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(format!("\"{}\" is not a valid proxy URL; use the form http://host:port", value));
+    }
+    Ok(())
+}
+
+fn build_client(http_proxy: &str, https_proxy: &str) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .user_agent(concat!("NaviTag/", env!("CARGO_PKG_VERSION")));
+
+    let (http_proxy, https_proxy) = (http_proxy.trim(), https_proxy.trim());
+    // reqwest reads the environment on its own, but only while no proxy is set explicitly
+    if !http_proxy.is_empty() || !https_proxy.is_empty() {
+        validate_proxy_url(http_proxy)?;
+        validate_proxy_url(https_proxy)?;
+        let no_proxy = reqwest::NoProxy::from_env();
+        if let Some(url) = proxy_or_env(http_proxy, "http_proxy") {
+            let proxy = reqwest::Proxy::http(&url).map_err(|e| format!("Invalid HTTP proxy: {}", e))?;
+            builder = builder.proxy(proxy.no_proxy(no_proxy.clone()));
+        }
+        if let Some(url) = proxy_or_env(https_proxy, "https_proxy") {
+            let proxy = reqwest::Proxy::https(&url).map_err(|e| format!("Invalid HTTPS proxy: {}", e))?;
+            builder = builder.proxy(proxy.no_proxy(no_proxy));
+        }
+    }
+
+    builder.build().map_err(|e| format!("Could not set up the HTTP client: {}", e))
+}
+
+/// The configured proxy, or the environment variable in either case when it's empty.
+fn proxy_or_env(configured: &str, variable: &str) -> Option<String> {
+    if !configured.is_empty() {
+        return Some(configured.to_string());
+    }
+    [variable.to_string(), variable.to_uppercase()]
+        .iter()
+        .find_map(|name| std::env::var(name).ok())
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// How long to wait before retry number `attempt` (starting at 0): 500ms, 1s, 2s and so on.
+pub fn backoff_delay(attempt: u32) -> Duration {
+    Duration::from_millis(500) * 2u32.saturating_pow(attempt.min(6))
+}
+
+/// Runs one provider's search, failing if it takes longer than `timeout`.
+async fn with_timeout<T, F>(timeout: Duration, search: F) -> Result<Vec<T>, String>
+where
+    F: std::future::Future<Output = Result<Vec<T>, String>>,
+{
+    match tokio::time::timeout(timeout, search).await {
+        Ok(results) => results,
+        Err(_) => Err(format!("timed out after {}s", timeout.as_secs())),
+    }
+}
+
+/// Searches every enabled provider at once. A provider that fails doesn't fail the
+/// search; its error is reported alongside the other providers' results.
+pub async fn search_all(term: String, settings: UserSettings) -> SearchOutcome {
+    if is_offline() {
+        return SearchOutcome::default();
+    }
+    let options = SearchOptions::from_settings(&settings);
+    let timeout = settings.provider_timeout();
 
     let apple_future = async {
         if settings.enable_apple_music {
-            apple_music::search(&term).await.unwrap_or_default()
+            apple_music::search(&term, &options).await
         } else {
-            Vec::new()
+            Ok(Vec::new())
         }
     };
 
     let spotify_future = async {
-        if settings.enable_spotify && !settings.spotify_id.is_empty() {
+        if Provider::Spotify.is_usable(&settings) {
              let mut client = spotify::SpotifyClient::new(settings.spotify_id.clone(), settings.spotify_secret.clone());
-             client.search(&term).await.unwrap_or_default()
+             client.search(&term, &options).await
         } else {
-             Vec::new()
+             Ok(Vec::new())
         }
     };
 
     let genius_future = async {
-        if settings.enable_genius && !settings.genius_token.is_empty() {
+        if Provider::Genius.is_usable(&settings) {
             let client = genius::GeniusClient::new(settings.genius_token.clone());
-            client.search(&term).await.unwrap_or_default()
+            client.search(&term, &options).await
         } else {
-             Vec::new()
+             Ok(Vec::new())
         }
     };
 
     let lastfm_future = async {
-        if settings.enable_lastfm && !settings.lastfm_api_key.is_empty() {
+        if Provider::LastFm.is_usable(&settings) {
             let client = lastfm::LastFmClient::new(settings.lastfm_api_key.clone());
-            client.search(&term).await.unwrap_or_default()
+            client.search(&term, &options).await
         } else {
-             Vec::new()
+             Ok(Vec::new())
         }
     };
 
-    let (r1, r2, r3, r4) = tokio::join!(apple_future, spotify_future, genius_future, lastfm_future);
+    let deezer_future = async {
+        if settings.enable_deezer {
+            deezer::search(&term, &options).await
+        } else {
+            Ok(Vec::new())
+        }
+    };
+
+    let (r1, r2, r3, r4, r5) = tokio::join!(
+        with_timeout(timeout, apple_future),
+        with_timeout(timeout, spotify_future),
+        with_timeout(timeout, genius_future),
+        with_timeout(timeout, lastfm_future),
+        with_timeout(timeout, deezer_future)
+    );
+
+    let mut outcome = SearchOutcome::default();
+    for (provider, result) in [
+        (Provider::AppleMusic, r1),
+        (Provider::Spotify, r2),
+        (Provider::Genius, r3),
+        (Provider::LastFm, r4),
+        (Provider::Deezer, r5),
+    ] {
+        match result {
+            Ok(results) => outcome.results.extend(results),
+            Err(e) => outcome.errors.push((provider, e)),
+        }
+    }
+    // Stable, so each provider's own relevance order is kept
+    outcome.results.sort_by_key(|r| settings.provider_rank(&r.source));
+    outcome
+}
 
-    results.extend(r1);
-    results.extend(r2);
-    results.extend(r3);
-    results.extend(r4);
-    
-    results
+/// Album search across the providers that expose tracklists (Apple Music and Spotify).
+/// Like `search_all`, a provider that fails or times out is reported in the errors.
+pub async fn search_albums(term: String, settings: UserSettings) -> SearchOutcome<AlbumResult> {
+    if is_offline() {
+        return SearchOutcome::default();
+    }
+    let mut options = SearchOptions::from_settings(&settings);
+    options.limit = options.limit.min(MAX_ALBUM_RESULTS);
+    let timeout = settings.provider_timeout() * 2;
+
+    let apple_future = async {
+        if settings.enable_apple_music {
+            apple_music::search_album(&term, &options).await
+        } else {
+            Ok(Vec::new())
+        }
+    };
+
+    let spotify_future = async {
+        if Provider::Spotify.is_usable(&settings) {
+            let mut client = spotify::SpotifyClient::new(settings.spotify_id.clone(), settings.spotify_secret.clone());
+            client.search_album(&term, &options).await
+        } else {
+            Ok(Vec::new())
+        }
+    };
+
+    let (apple, spotify) = tokio::join!(
+        with_timeout(timeout, apple_future),
+        with_timeout(timeout, spotify_future)
+    );
+
+    let mut outcome = SearchOutcome::default();
+    for (provider, result) in [(Provider::AppleMusic, apple), (Provider::Spotify, spotify)] {
+        match result {
+            Ok(albums) => outcome.results.extend(albums),
+            Err(e) => outcome.errors.push((provider, e)),
+        }
+    }
+    outcome
+}
+
+/// Drops whatever a provider keeps between searches (today only Spotify's access
+/// token), so the next search starts from the current credentials.
+pub fn reset_provider_state(provider: Provider) {
+    match provider {
+        Provider::Spotify => spotify::clear_token_cache(),
+        Provider::AppleMusic | Provider::Genius | Provider::LastFm | Provider::Deezer => {}
+    }
+}
+
+/// Checks a provider's credentials with the cheapest request it supports.
+pub async fn test_connection(provider: Provider, settings: UserSettings) -> Result<(), String> {
+    if is_offline() {
+        return Err(OFFLINE_ERROR.to_string());
+    }
+    let options = SearchOptions {
+        limit: 1,
+        country: settings.country_code().unwrap_or_default(),
+    };
+
+    match provider {
+        Provider::AppleMusic => apple_music::search("test", &options).await.map(|_| ()),
+        Provider::Spotify => {
+            if settings.spotify_id.is_empty() || settings.spotify_secret.is_empty() {
+                return Err("Enter both a Client ID and a Client Secret".to_string());
+            }
+            spotify::SpotifyClient::new(settings.spotify_id, settings.spotify_secret)
+                .authenticate()
+                .await
+        }
+        Provider::Genius => genius::GeniusClient::new(settings.genius_token)
+            .search("test", &options)
+            .await
+            .map(|_| ()),
+        Provider::LastFm => {
+            if settings.lastfm_api_key.is_empty() {
+                return Err("Last.fm API Key is missing".to_string());
+            }
+            lastfm::LastFmClient::new(settings.lastfm_api_key)
+                .search("test", &options)
+                .await
+                .map(|_| ())
+        }
+        Provider::Deezer => deezer::search("test", &options).await.map(|_| ()),
+    }
 }
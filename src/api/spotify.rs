@@ -1,10 +1,43 @@
-use super::MetadataResult;
+use super::{AlbumResult, MetadataResult, SearchOptions};
 use serde::Deserialize;
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Tokens are renewed this long before Spotify says they expire.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Deserialize)]
 struct SpotifyTokenResponse {
     access_token: String,
+    /// Seconds; Spotify issues tokens for an hour
+    expires_in: Option<u64>,
+}
+
+/// The last token issued, shared by every `SpotifyClient` so each search doesn't
+/// authenticate again. Tied to the credentials it was issued for.
+struct CachedToken {
+    client_id: String,
+    client_secret: String,
+    access_token: String,
+    expires_at: Instant,
+}
+
+static TOKEN_CACHE: Mutex<Option<CachedToken>> = Mutex::new(None);
+
+fn cached_token(client_id: &str, client_secret: &str) -> Option<String> {
+    let cache = TOKEN_CACHE.lock().ok()?;
+    cache
+        .as_ref()
+        .filter(|t| t.client_id == client_id && t.client_secret == client_secret && Instant::now() < t.expires_at)
+        .map(|t| t.access_token.clone())
+}
+
+/// Forgets the cached token, so the next search authenticates from scratch.
+pub fn clear_token_cache() {
+    if let Ok(mut cache) = TOKEN_CACHE.lock() {
+        *cache = None;
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -19,17 +52,60 @@ struct Tracks {
 
 #[derive(Debug, Deserialize)]
 struct Track {
+    id: Option<String>,
     name: String,
+    track_number: Option<u32>,
     album: Album,
     artists: Vec<Artist>,
+    external_ids: Option<ExternalIds>,
+    explicit: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalIds {
+    isrc: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Album {
     name: String,
+    release_date: Option<String>,
     images: Vec<Image>,
 }
 
+#[derive(Debug, Deserialize)]
+struct AlbumSearchResponse {
+    albums: Albums,
+}
+
+#[derive(Debug, Deserialize)]
+struct Albums {
+    items: Vec<SimplifiedAlbum>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SimplifiedAlbum {
+    id: String,
+    name: String,
+    artists: Vec<Artist>,
+    images: Vec<Image>,
+    release_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlbumTracks {
+    items: Vec<SimplifiedTrack>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SimplifiedTrack {
+    id: Option<String>,
+    name: String,
+    track_number: Option<u32>,
+    artists: Vec<Artist>,
+    explicit: Option<bool>,
+}
+
 #[derive(Debug, Deserialize)]
 struct Artist {
     name: String,
@@ -42,6 +118,50 @@ struct Image {
     width: Option<u32>,
 }
 
+/// Spotify doesn't guarantee image order, so sort by pixel area, largest first.
+fn images_by_size(images: &[Image]) -> Vec<String> {
+    let mut images: Vec<&Image> = images.iter().collect();
+    images.sort_by_key(|i| std::cmp::Reverse(i.width.unwrap_or(0) as u64 * i.height.unwrap_or(0) as u64));
+    images.into_iter().map(|i| i.url.clone()).collect()
+}
+
+fn to_result(t: Track) -> MetadataResult {
+    let artist = t.artists.first().map(|a| a.name.clone()).unwrap_or_default();
+    let cover_urls = images_by_size(&t.album.images);
+    let year = t.album.release_date.as_deref().and_then(super::parse_year);
+
+    MetadataResult {
+        title: t.name,
+        artist,
+        album: t.album.name,
+        year,
+        track: t.track_number,
+        cover_urls,
+        source: "Spotify".to_string(),
+        musicbrainz_id: None,
+        spotify_id: t.id,
+        isrc: t.external_ids.and_then(|ids| ids.isrc),
+        explicit: t.explicit,
+    }
+}
+
+/// Maps a track search response to results, covers largest first.
+fn parse_spotify(response: SpotifySearchResponse) -> Vec<MetadataResult> {
+    response.tracks.items.into_iter().map(to_result).collect()
+}
+
+fn search_url(term: &str, options: &SearchOptions) -> String {
+    let mut url = format!(
+        "https://api.spotify.com/v1/search?q={}&type=track&limit={}",
+        urlencoding::encode(term),
+        options.limit
+    );
+    if !options.country.is_empty() {
+        url.push_str(&format!("&market={}", options.country));
+    }
+    url
+}
+
 pub struct SpotifyClient {
     client_id: String,
     client_secret: String,
@@ -50,15 +170,16 @@ pub struct SpotifyClient {
 
 impl SpotifyClient {
     pub fn new(client_id: String, client_secret: String) -> Self {
+        let access_token = cached_token(&client_id, &client_secret);
         Self {
             client_id,
             client_secret,
-            access_token: None,
+            access_token,
         }
     }
 
     pub async fn authenticate(&mut self) -> Result<(), String> {
-        let client = reqwest::Client::new();
+        let client = super::http_client();
         let params = [("grant_type", "client_credentials")];
         
         let response = client
@@ -79,22 +200,28 @@ impl SpotifyClient {
             .await
             .map_err(|e| format!("Auth parse failed: {}", e))?;
 
+        let lifetime = Duration::from_secs(token_res.expires_in.unwrap_or(3600)).saturating_sub(TOKEN_EXPIRY_MARGIN);
+        if let Ok(mut cache) = TOKEN_CACHE.lock() {
+            *cache = Some(CachedToken {
+                client_id: self.client_id.clone(),
+                client_secret: self.client_secret.clone(),
+                access_token: token_res.access_token.clone(),
+                expires_at: Instant::now() + lifetime,
+            });
+        }
         self.access_token = Some(token_res.access_token);
         Ok(())
     }
 
-    pub async fn search(&mut self, term: &str) -> Result<Vec<MetadataResult>, String> {
+    pub async fn search(&mut self, term: &str, options: &SearchOptions) -> Result<Vec<MetadataResult>, String> {
         if self.access_token.is_none() {
             self.authenticate().await?;
         }
 
         let token = self.access_token.as_ref().unwrap();
-        let client = reqwest::Client::new();
+        let client = super::http_client();
         
-        let url = format!(
-            "https://api.spotify.com/v1/search?q={}&type=track&limit=10",
-            urlencoding::encode(term)
-        );
+        let url = search_url(term, options);
 
         let response = client
             .get(&url)
@@ -106,7 +233,7 @@ impl SpotifyClient {
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
             self.authenticate().await?;
             let token = self.access_token.as_ref().unwrap();
-             return self.search_retry(term, token).await;
+             return self.search_retry(term, token, options).await;
         }
 
         if !response.status().is_success() {
@@ -118,28 +245,12 @@ impl SpotifyClient {
             .await
             .map_err(|e| format!("Search parse failed: {}", e))?;
 
-        let results = search_res.tracks.items.into_iter().map(|t| {
-            let artist = t.artists.first().map(|a| a.name.clone()).unwrap_or_default();
-            let cover_url = t.album.images.first().map(|i| i.url.clone());
-            
-            MetadataResult {
-                title: t.name,
-                artist,
-                album: t.album.name,
-                cover_url,
-                source: "Spotify".to_string(),
-            }
-        }).collect();
-
-        Ok(results)
+        Ok(parse_spotify(search_res))
     }
 
-    async fn search_retry(&self, term: &str, token: &str) -> Result<Vec<MetadataResult>, String> {
-          let client = reqwest::Client::new();
-           let url = format!(
-            "https://api.spotify.com/v1/search?q={}&type=track&limit=10",
-            urlencoding::encode(term)
-        );
+    async fn search_retry(&self, term: &str, token: &str, options: &SearchOptions) -> Result<Vec<MetadataResult>, String> {
+        let client = super::http_client();
+        let url = search_url(term, options);
 
         let response = client
             .get(&url)
@@ -153,17 +264,153 @@ impl SpotifyClient {
             .await
             .map_err(|e| format!("Retry search parse failed: {}", e))?;
 
-        Ok(search_res.tracks.items.into_iter().map(|t| {
-            let artist = t.artists.first().map(|a| a.name.clone()).unwrap_or_default();
-            let cover_url = t.album.images.first().map(|i| i.url.clone());
-            
-            MetadataResult {
-                title: t.name,
+        Ok(parse_spotify(search_res))
+    }
+
+    pub async fn search_album(&mut self, term: &str, options: &SearchOptions) -> Result<Vec<AlbumResult>, String> {
+        if self.access_token.is_none() {
+            self.authenticate().await?;
+        }
+        let token = self.access_token.clone().unwrap_or_default();
+
+        let mut url = format!(
+            "https://api.spotify.com/v1/search?q={}&type=album&limit={}",
+            urlencoding::encode(term),
+            options.limit
+        );
+        if !options.country.is_empty() {
+            url.push_str(&format!("&market={}", options.country));
+        }
+
+        let response = super::http_client()
+            .get(&url)
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Album search request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Album search failed with status: {}", response.status()));
+        }
+
+        let search_res: AlbumSearchResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Album search parse failed: {}", e))?;
+
+        let mut albums = Vec::new();
+        for a in search_res.albums.items {
+            let artist = a.artists.first().map(|a| a.name.clone()).unwrap_or_default();
+            let year = a.release_date.as_deref().and_then(super::parse_year);
+            let cover_urls = images_by_size(&a.images);
+
+            let tracks = self
+                .album_tracks(&a.id, &token, options)
+                .await?
+                .into_iter()
+                .map(|t| MetadataResult {
+                    title: t.name,
+                    artist: t.artists.first().map(|a| a.name.clone()).unwrap_or_else(|| artist.clone()),
+                    album: a.name.clone(),
+                    year,
+                    track: t.track_number,
+                    cover_urls: cover_urls.clone(),
+                    source: "Spotify".to_string(),
+                    musicbrainz_id: None,
+                    spotify_id: t.id,
+                    isrc: None,
+                    explicit: t.explicit,
+                })
+                .collect();
+
+            albums.push(AlbumResult {
+                title: a.name,
                 artist,
-                album: t.album.name,
-                cover_url,
+                year,
                 source: "Spotify".to_string(),
-            }
-        }).collect())
+                tracks,
+            });
+        }
+
+        Ok(albums)
+    }
+
+    async fn album_tracks(&self, album_id: &str, token: &str, options: &SearchOptions) -> Result<Vec<SimplifiedTrack>, String> {
+        let mut url = format!("https://api.spotify.com/v1/albums/{}/tracks?limit=50", album_id);
+        if !options.country.is_empty() {
+            url.push_str(&format!("&market={}", options.country));
+        }
+
+        let response = super::http_client()
+            .get(&url)
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| format!("Album tracks request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Album tracks failed with status: {}", response.status()));
+        }
+
+        let tracks: AlbumTracks = response
+            .json()
+            .await
+            .map_err(|e| format!("Album tracks parse failed: {}", e))?;
+
+        Ok(tracks.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_track_search() {
+        let response: SpotifySearchResponse = serde_json::from_str(include_str!("testdata/spotify.json")).unwrap();
+        let results = parse_spotify(response);
+
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.title, "One More Time");
+        assert_eq!(result.artist, "Daft Punk");
+        assert_eq!(result.album, "Discovery");
+        assert_eq!(result.year, Some(2001));
+        assert_eq!(result.track, Some(1));
+        assert_eq!(result.spotify_id.as_deref(), Some("0DiWol3AO6WpXZgp0goxAV"));
+        assert_eq!(result.isrc.as_deref(), Some("GBDUW0000053"));
+        assert_eq!(result.cover_urls, vec![
+            "https://i.scdn.co/image/discovery-640",
+            "https://i.scdn.co/image/discovery-300",
+            "https://i.scdn.co/image/discovery-64",
+        ]);
+    }
+
+    fn image(url: &str, side: Option<u32>) -> Image {
+        Image { url: url.to_string(), height: side, width: side }
+    }
+
+    #[test]
+    fn images_sort_by_area_with_unknown_sizes_last() {
+        let images = [image("small", Some(64)), image("unknown", None), image("large", Some(640)), image("medium", Some(300))];
+        assert_eq!(images_by_size(&images), vec!["large", "medium", "small", "unknown"]);
+    }
+
+    #[test]
+    fn to_result_handles_missing_artists_and_images() {
+        let track = Track {
+            id: None,
+            name: "Untitled".to_string(),
+            track_number: None,
+            album: Album { name: "Demos".to_string(), release_date: Some("1999".to_string()), images: Vec::new() },
+            artists: Vec::new(),
+            external_ids: None,
+            explicit: Some(true),
+        };
+        let result = to_result(track);
+        assert_eq!(result.artist, "");
+        assert_eq!(result.year, Some(1999));
+        assert!(result.cover_urls.is_empty());
+        assert_eq!(result.explicit, Some(true));
     }
 }
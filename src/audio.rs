@@ -1,80 +1,420 @@
 use image::GenericImageView;
 use std::io::Cursor;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use lofty::prelude::*;
 use lofty::probe::Probe;
 use lofty::file::AudioFile as LoftyAudioFile;
+use lofty::file::FileType;
 use lofty::config::WriteOptions;
 use lofty::picture::{Picture, PictureType, MimeType};
+use lofty::tag::{ItemValue, TagItem, TagType};
+use crate::casing;
+use crate::text_encoding;
+use crate::settings::{ArtworkMode, Id3Version, ThumbnailFit, UserSettings, DEFAULT_COVER_JPEG_QUALITY, DEFAULT_VALUE_SEPARATOR};
 
-#[derive(Debug, Clone)]
+/// Shown in place of a title, artist or album the file has no tag for.
+pub const UNKNOWN_TITLE: &str = "Unknown Title";
+pub const UNKNOWN_ARTIST: &str = "Unknown Artist";
+pub const UNKNOWN_ALBUM: &str = "Unknown Album";
+
+/// Stand-ins that mean a field has no real value: the ones above, plus the album name
+/// Genius and Last.fm results carry since those providers have no album data.
+const PLACEHOLDERS: [&str; 5] = [UNKNOWN_TITLE, UNKNOWN_ARTIST, UNKNOWN_ALBUM, "Unknown (Genius)", "Unknown (Last.fm)"];
+
+/// Whether `value` is empty or one of the placeholders, rather than a real tag value.
+/// Only exact matches count, so a title like "Unknown Pleasures" is a real value.
+pub fn is_placeholder(value: &str) -> bool {
+    let value = value.trim();
+    value.is_empty() || PLACEHOLDERS.contains(&value)
+}
+
+/// Name of the cover image written next to the audio files in folder artwork mode.
+pub const FOLDER_COVER_NAME: &str = "cover.jpg";
+
+/// File extensions picked up by `scan_folder`. All of these are formats lofty can read and write.
+/// Users can add more through the `extra_extensions` setting.
+pub const SUPPORTED_EXTENSIONS: [&str; 10] = [
+    "mp3", "flac", "ogg", "opus", "m4a", "wav", "aiff", "aif", "wv", "ape",
+];
+
+/// Settings that affect how tags are written.
+#[derive(Debug, Clone, Copy)]
+pub struct SaveOptions {
+    pub id3_version: Id3Version,
+    /// Separates multiple artist or genre values within one field
+    pub value_separator: char,
+    pub artwork_mode: ArtworkMode,
+    /// JPEG quality used when a cover.jpg has to be re-encoded from another format
+    pub cover_quality: u8,
+    /// Discard every existing tag item and write only the core fields and cover
+    pub minimal_tags: bool,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            id3_version: Id3Version::default(),
+            value_separator: DEFAULT_VALUE_SEPARATOR,
+            artwork_mode: ArtworkMode::default(),
+            cover_quality: DEFAULT_COVER_JPEG_QUALITY,
+            minimal_tags: false,
+        }
+    }
+}
+
+impl SaveOptions {
+    pub fn from_settings(settings: &UserSettings) -> Self {
+        Self {
+            id3_version: settings.id3_version,
+            value_separator: settings.value_separator,
+            artwork_mode: settings.artwork_mode,
+            cover_quality: settings.cover_jpeg_quality(),
+            minimal_tags: settings.minimal_tags,
+        }
+    }
+
+    fn write_options(&self) -> WriteOptions {
+        // Only consulted when writing ID3v2 tags; other tag types ignore it
+        WriteOptions::new().use_id3v23(self.id3_version == Id3Version::V23)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct AudioFile {
     pub path: PathBuf,
     pub title: String,
     pub artist: String,
     pub album: String,
+    /// Empty when the file has no AlbumArtist tag; the editor then shows the track artist as a hint.
+    pub album_artist: String,
+    /// iTunes/ID3 compilation flag (TCMP, cpil, COMPILATION)
+    pub compilation: bool,
+    /// Parental advisory (iTunes rtng / ITUNESADVISORY): Some(true) explicit, Some(false)
+    /// clean, None unrated
+    pub explicit: Option<bool>,
     pub year: Option<u32>,
+    pub track: Option<u32>,
+    pub genre: String,
+    pub composer: String,
+    pub comment: String,
+    /// Unsynchronized lyrics (USLT in ID3, LYRICS in Vorbis comments)
+    pub lyrics: String,
+    pub bpm: Option<u32>,
+    /// Text items not covered by the fields above, as (native key, value) pairs.
+    pub extra_tags: Vec<(String, String)>,
+    /// Keys deleted or renamed in the Advanced Tags dialog; saving removes them from the file.
+    /// Other items that aren't in `extra_tags` are left alone.
+    pub removed_tags: Vec<String>,
+    /// MusicBrainz recording ID, as written by Picard and beets
+    pub musicbrainz_id: Option<String>,
+    pub spotify_id: Option<String>,
+    pub isrc: Option<String>,
+    /// Type of the tag the file was read from, used to map raw keys.
+    pub tag_type: Option<TagType>,
+    pub format: String,
+    pub duration: Duration,
+    /// Size on disk in bytes when the file was loaded
+    pub file_size: u64,
+    /// Overall bitrate in kbps
+    pub bitrate: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
     pub picture_data: Option<Vec<u8>>,
     pub thumbnail_data: Option<Vec<u8>>,
+    /// Set when the in-memory fields differ from what was last written to disk.
+    pub dirty: bool,
 }
 
 impl AudioFile {
-    pub fn load(path: PathBuf) -> Option<Self> {
-        let tagged_file_opt = Probe::open(&path).ok().and_then(|p| p.read().ok());
-        let tag = tagged_file_opt.as_ref().and_then(|tf| tf.primary_tag().or_else(|| tf.first_tag()));
+    /// Reads a file's tags. Files without tags still load with filename-based defaults;
+    /// only files that can't be opened or parsed at all return an error.
+    /// Multiple artist or genre values are joined with `separator`.
+    pub fn load(path: PathBuf, separator: char) -> Result<Self, String> {
+        let tagged_file = Probe::open(&path)
+            .map_err(|e| e.to_string())?
+            .read()
+            .map_err(|e| e.to_string())?;
+        let tag = tagged_file.primary_tag().or_else(|| tagged_file.first_tag());
+
+        let properties = tagged_file.properties();
+        let format = format_name(tagged_file.file_type()).to_string();
+        let duration = properties.duration();
+        let bitrate = properties.overall_bitrate();
+        let sample_rate = properties.sample_rate();
+        let channels = properties.channels();
+        let file_size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
 
         if let Some(tag) = tag {
             let title = tag.title().as_deref()
                 .or_else(|| path.file_stem().and_then(|s| s.to_str()))
-                .unwrap_or("Unknown Title")
+                .unwrap_or(UNKNOWN_TITLE)
                 .to_string();
             
-            let picture_data = tag.pictures().first().map(|p| p.data().to_vec());
-
-            let thumbnail_data = if let Some(data) = &picture_data {
-                if let Ok(img) = image::load_from_memory(data) {
-                     let thumbnail = img.resize_to_fill(40, 40, image::imageops::FilterType::Triangle);
-                     let mut buf = Cursor::new(Vec::new());
-                     if thumbnail.write_to(&mut buf, image::ImageOutputFormat::Png).is_ok() {
-                         Some(buf.into_inner())
-                     } else {
-                         None
-                     }
-                } else {
-                    None
-                }
-            } else {
-                None
+            let artist = match join_values(tag, &ItemKey::TrackArtist, separator) {
+                artist if artist.is_empty() => UNKNOWN_ARTIST.to_string(),
+                artist => artist,
             };
 
-            Some(Self {
+            // Thumbnails are decoded later, only for rows that scroll into view
+            let picture_data = tag.pictures().first().map(|p| p.data().to_vec());
+
+            Ok(Self {
                 path,
                 title,
-                artist: tag.artist().as_deref().unwrap_or("Unknown Artist").to_string(),
-                album: tag.album().as_deref().unwrap_or("Unknown Album").to_string(),
+                artist,
+                album: tag.album().as_deref().unwrap_or(UNKNOWN_ALBUM).to_string(),
+                album_artist: tag.get_string(&ItemKey::AlbumArtist).unwrap_or_default().to_string(),
+                compilation: read_flag(tag, &ItemKey::FlagCompilation),
+                explicit: read_advisory(tag),
                 year: tag.year(),
+                track: tag.track(),
+                genre: join_values(tag, &ItemKey::Genre, separator),
+                composer: tag.get_string(&ItemKey::Composer).unwrap_or_default().to_string(),
+                comment: tag.comment().as_deref().unwrap_or_default().to_string(),
+                lyrics: tag.get_string(&ItemKey::Lyrics).unwrap_or_default().to_string(),
+                bpm: read_bpm(tag),
+                extra_tags: read_extra_tags(tag),
+                removed_tags: Vec::new(),
+                musicbrainz_id: read_id(tag, &ItemKey::MusicBrainzRecordingId),
+                spotify_id: read_id(tag, &spotify_id_key()),
+                isrc: read_id(tag, &ItemKey::Isrc),
+                tag_type: Some(tag.tag_type()),
+                format,
+                duration,
+                file_size,
+                bitrate,
+                sample_rate,
+                channels,
                 picture_data,
-                thumbnail_data,
+                thumbnail_data: None,
+                dirty: false,
             })
         } else {
-            Some(Self {
+            let mut file = Self {
                 path: path.clone(),
-                title: path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or("Unknown".to_string()),
-                artist: "Unknown Artist".to_string(),
-                album: "Unknown Album".to_string(),
+                title: path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| UNKNOWN_TITLE.to_string()),
+                artist: UNKNOWN_ARTIST.to_string(),
+                album: UNKNOWN_ALBUM.to_string(),
+                album_artist: String::new(),
+                compilation: false,
+                explicit: None,
                 year: None,
+                track: None,
+                genre: String::new(),
+                composer: String::new(),
+                comment: String::new(),
+                lyrics: String::new(),
+                bpm: None,
+                extra_tags: Vec::new(),
+                removed_tags: Vec::new(),
+                musicbrainz_id: None,
+                spotify_id: None,
+                isrc: None,
+                tag_type: Some(tagged_file.primary_tag_type()),
+                format,
+                duration,
+                file_size,
+                bitrate,
+                sample_rate,
+                channels,
                 picture_data: None,
                 thumbnail_data: None,
-            })
+                dirty: false,
+            };
+            file.guess_from_filename();
+            Ok(file)
+        }
+    }
+
+    /// The text fields "Fix Encoding" looks at, extra tags included.
+    fn text_fields_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        [
+            &mut self.title,
+            &mut self.artist,
+            &mut self.album,
+            &mut self.album_artist,
+            &mut self.genre,
+            &mut self.composer,
+            &mut self.comment,
+            &mut self.lyrics,
+        ]
+        .into_iter()
+        .chain(self.extra_tags.iter_mut().map(|(_, value)| value))
+    }
+
+    /// Whether title, artist and album are all filled in with real values, so batch
+    /// operations limited to incomplete files can skip it.
+    pub fn has_complete_tags(&self) -> bool {
+        [&self.title, &self.artist, &self.album]
+            .into_iter()
+            .all(|value| !is_placeholder(value))
+    }
+
+    /// Whether any text field looks like a legacy encoding that was read as Latin-1.
+    pub fn has_suspect_encoding(&self) -> bool {
+        [&self.title, &self.artist, &self.album, &self.album_artist, &self.genre, &self.composer, &self.comment, &self.lyrics]
+            .into_iter()
+            .chain(self.extra_tags.iter().map(|(_, value)| value))
+            .any(|value| text_encoding::looks_misdecoded(value))
+    }
+
+    /// Re-interprets every suspect text field's raw bytes as UTF-8, or as `fallback` when
+    /// they aren't valid UTF-8. Returns true if anything changed.
+    pub fn fix_encoding(&mut self, fallback: &'static encoding_rs::Encoding) -> bool {
+        let mut changed = false;
+        for value in self.text_fields_mut() {
+            if !text_encoding::looks_misdecoded(value) {
+                continue;
+            }
+            if let Some(fixed) = text_encoding::repair(value, fallback) {
+                if fixed != *value {
+                    *value = fixed;
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Rewrites the single-line text fields in the given style. Returns true if anything changed.
+    pub fn normalize_text(&mut self, style: casing::Style) -> bool {
+        let mut changed = false;
+        for value in [&mut self.title, &mut self.artist, &mut self.album, &mut self.album_artist, &mut self.genre, &mut self.composer] {
+            let normalized = style.apply(value);
+            if normalized != *value {
+                *value = normalized;
+                changed = true;
+            }
         }
+        changed
     }
 
-    pub fn save(&self) -> Result<(), String> {
-        let mut tagged_file = Probe::open(&self.path)
+    /// Fills fields that are still Unknown from the filename. Returns true if anything changed.
+    pub fn guess_from_filename(&mut self) -> bool {
+        let guess = parse_from_filename(&self.path);
+        let stem = self.path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let mut changed = false;
+
+        if let Some(title) = guess.title {
+            if (self.title == stem || is_placeholder(&self.title)) && self.title != title {
+                self.title = title;
+                changed = true;
+            }
+        }
+        if let Some(artist) = guess.artist {
+            if is_placeholder(&self.artist) {
+                self.artist = artist;
+                changed = true;
+            }
+        }
+        if let Some(album) = guess.album {
+            if is_placeholder(&self.album) {
+                self.album = album;
+                changed = true;
+            }
+        }
+        if self.track.is_none() && guess.track.is_some() {
+            self.track = guess.track;
+            changed = true;
+        }
+
+        changed
+    }
+
+    /// Fills an Unknown artist and album from an `Artist/Album/track` folder layout.
+    /// Returns true if anything changed.
+    pub fn guess_from_folders(&mut self) -> bool {
+        let guess = parse_from_folders(&self.path);
+        let mut changed = false;
+
+        if let Some(artist) = guess.artist {
+            if is_placeholder(&self.artist) {
+                self.artist = artist;
+                changed = true;
+            }
+        }
+        if let Some(album) = guess.album {
+            if is_placeholder(&self.album) {
+                self.album = album;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Strips every tag from the file on disk, embedded artwork included,
+    /// and resets the in-memory fields to match.
+    pub fn clear_tags(&mut self) -> Result<(), String> {
+        let tagged_file = Probe::open(&self.path)
             .map_err(|e| e.to_string())?
             .read()
             .map_err(|e| e.to_string())?;
 
+        for tag in tagged_file.tags() {
+            tag.remove_from_path(&self.path).map_err(|e| e.to_string())?;
+        }
+
+        self.title = UNKNOWN_TITLE.to_string();
+        self.artist = UNKNOWN_ARTIST.to_string();
+        self.album = UNKNOWN_ALBUM.to_string();
+        self.album_artist.clear();
+        self.compilation = false;
+        self.explicit = None;
+        self.year = None;
+        self.track = None;
+        self.genre.clear();
+        self.composer.clear();
+        self.comment.clear();
+        self.lyrics.clear();
+        self.bpm = None;
+        self.extra_tags.clear();
+        self.removed_tags.clear();
+        self.musicbrainz_id = None;
+        self.spotify_id = None;
+        self.isrc = None;
+        self.picture_data = None;
+        self.thumbnail_data = None;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Whether the file on disk is marked read-only, so saving would fail.
+    pub fn is_read_only(&self) -> bool {
+        std::fs::metadata(&self.path)
+            .map(|m| m.permissions().readonly())
+            .unwrap_or(false)
+    }
+
+    /// Makes the file writable for its owner.
+    pub fn clear_read_only(&self) -> Result<(), String> {
+        make_writable(&self.path)
+    }
+
+    pub fn save(&self, options: SaveOptions) -> Result<(), String> {
+        self.write_tags(&self.path, options)
+    }
+
+    /// Copies the file to `target` and writes the edited tags there, leaving the original untouched.
+    pub fn save_copy(&self, target: &Path, options: SaveOptions) -> Result<(), String> {
+        if target == self.path {
+            return Err("Choose a different location than the original file.".to_string());
+        }
+        std::fs::copy(&self.path, target).map_err(|e| format!("Could not copy file: {}", e))?;
+        // The copy inherits the original's permissions, which may be read-only
+        make_writable(target)?;
+        self.write_tags(target, options)
+    }
+
+    fn write_tags(&self, target: &Path, options: SaveOptions) -> Result<(), String> {
+        let mut tagged_file = Probe::open(target)
+            .map_err(|e| e.to_string())?
+            .read()
+            .map_err(|e| e.to_string())?;
+
+        let written_type = tagged_file.primary_tag().or_else(|| tagged_file.first_tag()).map(|t| t.tag_type());
+        let other_tags: Vec<TagType> = tagged_file.tags().iter().map(|t| t.tag_type()).filter(|t| Some(*t) != written_type).collect();
+
         let tag = match tagged_file.primary_tag_mut() {
             Some(t) => t,
             None => {
@@ -83,41 +423,864 @@ impl AudioFile {
         };
 
         tag.set_title(self.title.clone());
-        tag.set_artist(self.artist.clone());
+        write_values(tag, ItemKey::TrackArtist, &self.artist, options.value_separator);
         tag.set_album(self.album.clone());
-        
-        if let Some(data) = &self.picture_data {
+
+        match self.year {
+            Some(year) => tag.set_year(year),
+            None => tag.remove_year(),
+        }
+
+        match self.track {
+            Some(track) => tag.set_track(track),
+            None => tag.remove_track(),
+        }
+
+        write_values(tag, ItemKey::Genre, &self.genre, options.value_separator);
+
+        if options.minimal_tags {
+            // Start over from an empty tag so nothing else survives
+            let mut fresh = lofty::tag::Tag::new(tag.tag_type());
+            for item in tag.items().filter(|item| is_minimal_key(item.key())) {
+                fresh.push(item.clone());
+            }
+            *tag = fresh;
+        } else {
+            self.write_extended_fields(tag);
+        }
+
+        // lofty's ID3v2.3 writer drops a recording date it was handed as plain text, and the
+        // year with it, so the year goes out as the v2.3 TYER frame instead
+        if tag.tag_type() == TagType::Id3v2 && options.id3_version == Id3Version::V23 {
+            if let Some(year) = tag.year() {
+                tag.remove_key(&ItemKey::RecordingDate);
+                tag.insert_unchecked(TagItem::new(ItemKey::Unknown("TYER".to_string()), ItemValue::Text(year.to_string())));
+            }
+        }
+
+        // In folder-only mode the cover lives in cover.jpg, so nothing new is embedded
+        if let Some(data) = self.picture_data.as_ref().filter(|_| options.artwork_mode.embeds()) {
              let picture = Picture::new_unchecked(
                 PictureType::CoverFront,
                 Some(MimeType::Jpeg), 
                 None,
                 data.clone()
             );
+            // Replaces the old front cover; `load` shows the first picture, so an added one
+            // would stay hidden behind it
+            tag.remove_picture_type(PictureType::CoverFront);
             tag.push_picture(picture);
         }
 
-        tagged_file.save_to_path(&self.path, WriteOptions::new()).map_err(|e| e.to_string())?;
+        tagged_file.save_to_path(target, options.write_options()).map_err(|e| e.to_string())?;
+
+        if options.minimal_tags {
+            // Secondary tags (e.g. ID3v1 or APE next to ID3v2) would keep the old values around
+            for tag_type in other_tags {
+                tag_type.remove_from_path(target).map_err(|e| e.to_string())?;
+            }
+        }
+
+        if let Some(data) = self.picture_data.as_ref().filter(|_| options.artwork_mode.writes_folder_file()) {
+            let folder = target.parent().ok_or("The file has no parent folder")?;
+            write_folder_cover(folder, data, options.cover_quality)?;
+        }
         Ok(())
     }
+
+    /// Writes everything beyond the core fields: unmanaged items, album artist, compilation
+    /// and explicit flags, composer, comment, lyrics, BPM and provider IDs.
+    fn write_extended_fields(&self, tag: &mut lofty::tag::Tag) {
+        // Rewrite only the keys shown in the dialog or removed there. Items the dialog never
+        // listed (unmappable keys, binary values) stay as they are
+        let tag_type = tag.tag_type();
+        let rewritten: Vec<ItemKey> = self.extra_tags.iter().map(|(key, _)| key).chain(&self.removed_tags)
+            .map(|key| key.trim())
+            .filter(|key| !key.is_empty())
+            .map(|key| ItemKey::from_key(tag_type, key))
+            .filter(|key| !is_managed_key(key))
+            .collect();
+        tag.retain(|item| !rewritten.contains(item.key()) || !matches!(item.value(), ItemValue::Text(_)));
+        for (key, value) in &self.extra_tags {
+            if key.trim().is_empty() {
+                continue;
+            }
+            let item_key = ItemKey::from_key(tag_type, key.trim());
+            if !is_managed_key(&item_key) {
+                tag.push(TagItem::new(item_key, ItemValue::Text(value.clone())));
+            }
+        }
+
+        if self.album_artist.is_empty() {
+            tag.remove_key(&ItemKey::AlbumArtist);
+        } else {
+            tag.insert_text(ItemKey::AlbumArtist, self.album_artist.clone());
+        }
+
+        // Players treat a missing flag as "not a compilation", so don't write a 0
+        if self.compilation {
+            tag.insert_text(ItemKey::FlagCompilation, "1".to_string());
+        } else {
+            tag.remove_key(&ItemKey::FlagCompilation);
+        }
+
+        match self.explicit {
+            Some(true) => {
+                tag.insert_text(ItemKey::ParentalAdvisory, ADVISORY_EXPLICIT.to_string());
+            }
+            Some(false) => {
+                tag.insert_text(ItemKey::ParentalAdvisory, ADVISORY_CLEAN.to_string());
+            }
+            None => tag.remove_key(&ItemKey::ParentalAdvisory),
+        }
+
+        if self.composer.is_empty() {
+            tag.remove_key(&ItemKey::Composer);
+        } else {
+            tag.insert_text(ItemKey::Composer, self.composer.clone());
+        }
+
+        if self.comment.is_empty() {
+            tag.remove_comment();
+        } else {
+            tag.set_comment(self.comment.clone());
+        }
+
+        if self.lyrics.is_empty() {
+            tag.remove_key(&ItemKey::Lyrics);
+        } else {
+            tag.insert_text(ItemKey::Lyrics, self.lyrics.clone());
+        }
+
+        tag.remove_key(&ItemKey::IntegerBpm);
+        tag.remove_key(&ItemKey::Bpm);
+        if let Some(bpm) = self.bpm {
+            // Not every format maps the integer key, so fall back to the free-form one
+            if !tag.insert_text(ItemKey::IntegerBpm, bpm.to_string()) {
+                tag.insert_text(ItemKey::Bpm, bpm.to_string());
+            }
+        }
+
+        write_id(tag, ItemKey::MusicBrainzRecordingId, &self.musicbrainz_id);
+        write_id(tag, ItemKey::Isrc, &self.isrc);
+        // Free-form key; formats without custom text keys (ID3v2, MP4) skip it
+        write_id(tag, spotify_id_key(), &self.spotify_id);
+    }
+}
+
+/// Writes `data` as the folder's cover.jpg, replacing any existing one. PNG, WebP and other
+/// formats are re-encoded so the file really is a JPEG. A cover.jpg that already holds the
+/// same image is left untouched.
+pub fn write_folder_cover(folder: &Path, data: &[u8], quality: u8) -> Result<PathBuf, String> {
+    let target = folder.join(FOLDER_COVER_NAME);
+    let jpeg = if matches!(image::guess_format(data), Ok(image::ImageFormat::Jpeg)) {
+        data.to_vec()
+    } else {
+        let img = image::load_from_memory(data).map_err(|e| format!("Could not read the cover: {}", e))?;
+        // JPEG has no alpha channel
+        let rgb = image::DynamicImage::ImageRgb8(img.to_rgb8());
+        let mut out = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality)
+            .encode_image(&rgb)
+            .map_err(|e| format!("Could not encode the cover: {}", e))?;
+        out
+    };
+    if std::fs::read(&target).is_ok_and(|existing| existing == jpeg) {
+        return Ok(target);
+    }
+    std::fs::write(&target, jpeg).map_err(|e| format!("Could not write {}: {}", target.display(), e))?;
+    Ok(target)
+}
+
+/// Splits a field like "Rock; Pop" on `separator` or newlines into trimmed, non-empty values.
+pub fn split_values(value: &str, separator: char) -> Vec<String> {
+    value
+        .split([separator, '\n'])
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Every value stored under `key`, joined for display in a single field.
+fn join_values(tag: &lofty::tag::Tag, key: &ItemKey, separator: char) -> String {
+    let joiner = if separator.is_whitespace() { separator.to_string() } else { format!("{} ", separator) };
+    tag.get_strings(key).map(str::trim).filter(|v| !v.is_empty()).collect::<Vec<_>>().join(&joiner)
+}
+
+/// Replaces `key` with one tag item per value in `value`, or removes it when the field is empty.
+fn write_values(tag: &mut lofty::tag::Tag, key: ItemKey, value: &str, separator: char) {
+    tag.remove_key(&key);
+    for value in split_values(value, separator) {
+        tag.push(TagItem::new(key.clone(), ItemValue::Text(value)));
+    }
+}
+
+fn format_name(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::Aac => "AAC",
+        FileType::Aiff => "AIFF",
+        FileType::Ape => "APE",
+        FileType::Flac => "FLAC",
+        FileType::Mpeg => "MP3",
+        FileType::Mp4 => "MP4",
+        FileType::Mpc => "MPC",
+        FileType::Opus => "Opus",
+        FileType::Vorbis => "Vorbis",
+        FileType::Speex => "Speex",
+        FileType::Wav => "WAV",
+        FileType::WavPack => "WavPack",
+        _ => "Unknown",
+    }
 }
 
-pub fn scan_folder(path: &Path) -> Vec<AudioFile> {
+/// Custom key the Spotify track ID is stored under.
+const SPOTIFY_ID_KEY: &str = "SPOTIFY_TRACK_ID";
+
+fn spotify_id_key() -> ItemKey {
+    ItemKey::Unknown(SPOTIFY_ID_KEY.to_string())
+}
+
+/// Whether a flag item is set; MP4 booleans come through as "1", Vorbis comments may say "true".
+fn read_flag(tag: &lofty::tag::Tag, key: &ItemKey) -> bool {
+    tag.get_string(key).is_some_and(|value| matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// iTunes advisory ratings; 0 (or no tag) means unrated.
+const ADVISORY_EXPLICIT: &str = "1";
+const ADVISORY_CLEAN: &str = "2";
+
+fn read_advisory(tag: &lofty::tag::Tag) -> Option<bool> {
+    match tag.get_string(&ItemKey::ParentalAdvisory)?.trim() {
+        // 4 is an older explicit value some taggers still write
+        ADVISORY_EXPLICIT | "4" => Some(true),
+        ADVISORY_CLEAN => Some(false),
+        _ => None,
+    }
+}
+
+fn read_id(tag: &lofty::tag::Tag, key: &ItemKey) -> Option<String> {
+    tag.get_string(key).map(str::trim).filter(|id| !id.is_empty()).map(str::to_string)
+}
+
+fn write_id(tag: &mut lofty::tag::Tag, key: ItemKey, id: &Option<String>) {
+    match id {
+        Some(id) => {
+            tag.insert_text(key, id.clone());
+        }
+        None => tag.remove_key(&key),
+    }
+}
+
+/// Keys edited through the dedicated fields; the advanced tag editor leaves these alone.
+pub fn is_managed_key(key: &ItemKey) -> bool {
+    if let ItemKey::Unknown(name) = key {
+        return name.eq_ignore_ascii_case(SPOTIFY_ID_KEY);
+    }
+    matches!(
+        key,
+        ItemKey::TrackTitle
+            | ItemKey::TrackArtist
+            | ItemKey::AlbumTitle
+            | ItemKey::AlbumArtist
+            | ItemKey::FlagCompilation
+            | ItemKey::ParentalAdvisory
+            | ItemKey::Year
+            | ItemKey::RecordingDate
+            | ItemKey::TrackNumber
+            | ItemKey::Genre
+            | ItemKey::Composer
+            | ItemKey::Comment
+            | ItemKey::Lyrics
+            | ItemKey::Bpm
+            | ItemKey::IntegerBpm
+            | ItemKey::MusicBrainzRecordingId
+            | ItemKey::Isrc
+    )
+}
+
+/// The fields kept by the minimal tags save mode.
+fn is_minimal_key(key: &ItemKey) -> bool {
+    matches!(
+        key,
+        ItemKey::TrackTitle
+            | ItemKey::TrackArtist
+            | ItemKey::AlbumTitle
+            | ItemKey::Year
+            | ItemKey::RecordingDate
+            | ItemKey::TrackNumber
+            | ItemKey::Genre
+    )
+}
+
+/// Whether a raw key typed by the user maps onto one of the managed fields.
+pub fn is_managed_key_name(tag_type: Option<TagType>, name: &str) -> bool {
+    match tag_type {
+        Some(tag_type) => is_managed_key(&ItemKey::from_key(tag_type, name.trim())),
+        None => false,
+    }
+}
+
+fn read_extra_tags(tag: &lofty::tag::Tag) -> Vec<(String, String)> {
+    let tag_type = tag.tag_type();
+    tag.items()
+        .filter(|item| !is_managed_key(item.key()))
+        .filter_map(|item| {
+            let value = item.value().text()?;
+            let key = item.key().map_key(tag_type, true)?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn read_bpm(tag: &lofty::tag::Tag) -> Option<u32> {
+    tag.get_string(&ItemKey::IntegerBpm)
+        .or_else(|| tag.get_string(&ItemKey::Bpm))
+        .and_then(|v| v.trim().parse::<f32>().ok())
+        .filter(|bpm| *bpm > 0.0)
+        .map(|bpm| bpm.round() as u32)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FilenameGuess {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track: Option<u32>,
+}
+
+fn parse_track_number(part: &str) -> Option<u32> {
+    let part = part.trim().trim_end_matches('.');
+    if !part.is_empty() && part.len() <= 3 && part.chars().all(|c| c.is_ascii_digit()) {
+        part.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Guesses tags from common naming patterns such as
+/// `Artist - Album - 03 - Title`, `03 - Artist - Title`, `Artist - Title` and `03. Title`.
+pub fn parse_from_filename(path: &Path) -> FilenameGuess {
+    let stem = match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().replace('_', " "),
+        None => return FilenameGuess::default(),
+    };
+
+    let parts: Vec<String> = stem
+        .split(" - ")
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    let mut guess = FilenameGuess::default();
+    match parts.as_slice() {
+        [artist, album, num, title] if parse_track_number(num).is_some() => {
+            guess.artist = Some(artist.clone());
+            guess.album = Some(album.clone());
+            guess.track = parse_track_number(num);
+            guess.title = Some(title.clone());
+        }
+        [num, artist, title] if parse_track_number(num).is_some() => {
+            guess.track = parse_track_number(num);
+            guess.artist = Some(artist.clone());
+            guess.title = Some(title.clone());
+        }
+        [artist, album, title] => {
+            guess.artist = Some(artist.clone());
+            guess.album = Some(album.clone());
+            guess.title = Some(title.clone());
+        }
+        [num, title] if parse_track_number(num).is_some() => {
+            guess.track = parse_track_number(num);
+            guess.title = Some(title.clone());
+        }
+        [artist, title] => {
+            guess.artist = Some(artist.clone());
+            guess.title = Some(title.clone());
+        }
+        [single] => {
+            // "03. Title" or "03 Title"
+            let (prefix, rest) = single.split_at(single.find(|c: char| !c.is_ascii_digit()).unwrap_or(single.len()));
+            let rest = rest.trim_start_matches('.').trim();
+            if let (Some(track), false) = (parse_track_number(prefix), rest.is_empty()) {
+                guess.track = Some(track);
+                guess.title = Some(rest.to_string());
+            } else {
+                guess.title = Some(single.clone());
+            }
+        }
+        _ => {}
+    }
+
+    guess
+}
+
+/// Folders that hold a whole library rather than one artist or album, so they never name either.
+const LIBRARY_FOLDERS: [&str; 8] = ["music", "my music", "itunes", "itunes media", "media", "downloads", "desktop", "documents"];
+
+fn is_library_folder(name: &str) -> bool {
+    LIBRARY_FOLDERS.contains(&name.to_lowercase().as_str())
+}
+
+/// "CD1", "CD 2", "Disc 1", "Disk2" and the like.
+fn is_disc_folder(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["cd", "disc", "disk"].iter().any(|prefix| {
+        name.strip_prefix(prefix).is_some_and(|rest| {
+            let rest = rest.trim();
+            !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit())
+        })
+    })
+}
+
+/// Guesses artist and album from the folders around a file: the containing folder is
+/// the album and its parent the artist, skipping a disc folder such as `CD1`. Album
+/// folders named `Artist - Album` or `2019 - Album` are split.
+pub fn parse_from_folders(path: &Path) -> FilenameGuess {
+    let mut folders = path
+        .ancestors()
+        .skip(1)
+        .filter_map(|p| p.file_name())
+        .map(|name| name.to_string_lossy().replace('_', " ").trim().to_string())
+        .filter(|name| !is_disc_folder(name));
+
+    let mut guess = FilenameGuess::default();
+    let Some(album_folder) = folders.next().filter(|name| !name.is_empty() && !is_library_folder(name)) else {
+        return guess;
+    };
+
+    match album_folder.split_once(" - ") {
+        Some((prefix, album)) if prefix.len() == 4 && prefix.chars().all(|c| c.is_ascii_digit()) => {
+            guess.album = Some(album.trim().to_string());
+        }
+        Some((artist, album)) if !artist.trim().is_empty() && !album.trim().is_empty() => {
+            guess.artist = Some(artist.trim().to_string());
+            guess.album = Some(album.trim().to_string());
+        }
+        _ => guess.album = Some(album_folder.clone()),
+    }
+
+    if guess.artist.is_none() {
+        guess.artist = folders
+            .next()
+            .filter(|name| !name.is_empty() && !is_library_folder(name));
+    }
+    guess
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Filename,
+    Title,
+    Artist,
+    Album,
+    Track,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 5] = [
+        SortKey::Filename,
+        SortKey::Title,
+        SortKey::Artist,
+        SortKey::Album,
+        SortKey::Track,
+    ];
+}
+
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            SortKey::Filename => "Filename",
+            SortKey::Title => "Title",
+            SortKey::Artist => "Artist",
+            SortKey::Album => "Album",
+            SortKey::Track => "Track",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+pub fn sort_files(files: &mut [AudioFile], key: SortKey, ascending: bool) {
+    files.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Filename => a.path.file_name().cmp(&b.path.file_name()),
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            SortKey::Artist => a.artist.to_lowercase().cmp(&b.artist.to_lowercase()),
+            SortKey::Album => a.album.to_lowercase().cmp(&b.album.to_lowercase()),
+            SortKey::Track => a.track.cmp(&b.track),
+        }
+        .then_with(|| a.path.cmp(&b.path));
+
+        if ascending { ordering } else { ordering.reverse() }
+    });
+}
+
+fn make_writable(path: &Path) -> Result<(), String> {
+    let mut permissions = std::fs::metadata(path)
+        .map_err(|e| e.to_string())?
+        .permissions();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+
+    std::fs::set_permissions(path, permissions).map_err(|e| e.to_string())
+}
+
+/// Shrinks cover art so neither side exceeds `max_dimension`, re-encoding it as JPEG.
+/// Covers already within the limit, a limit of 0, or undecodable data are returned unchanged.
+pub fn shrink_cover(data: Vec<u8>, max_dimension: u32, quality: u8) -> Vec<u8> {
+    if max_dimension == 0 {
+        return data;
+    }
+    let Ok(img) = image::load_from_memory(&data) else {
+        return data;
+    };
+    if img.width() <= max_dimension && img.height() <= max_dimension {
+        return data;
+    }
+
+    let resized = img.resize(max_dimension, max_dimension, image::imageops::FilterType::Lanczos3);
+    // JPEG has no alpha channel
+    let rgb = image::DynamicImage::ImageRgb8(resized.to_rgb8());
+    let mut out = Vec::new();
+    match image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality).encode_image(&rgb) {
+        Ok(()) => out,
+        Err(_) => data,
+    }
+}
+
+/// Side of the thumbnails shown in the file list.
+pub const LIST_THUMBNAIL_SIZE: u32 = 40;
+
+/// Scales cover art down to a `size`x`size` PNG, either cropped to fill the square or
+/// letterboxed onto a transparent one so the whole image stays visible.
+pub fn make_thumbnail(data: &[u8], size: u32, fit: ThumbnailFit) -> Result<Vec<u8>, String> {
+    let img = image::load_from_memory(data).map_err(|e| e.to_string())?;
+    let thumbnail = match fit {
+        ThumbnailFit::Crop => img.resize_to_fill(size, size, image::imageops::FilterType::Triangle),
+        ThumbnailFit::Letterbox => {
+            let scaled = img.resize(size, size, image::imageops::FilterType::Triangle);
+            let mut canvas = image::RgbaImage::new(size, size);
+            let x = (size - scaled.width()) / 2;
+            let y = (size - scaled.height()) / 2;
+            image::imageops::overlay(&mut canvas, &scaled.to_rgba8(), x as i64, y as i64);
+            image::DynamicImage::ImageRgba8(canvas)
+        }
+    };
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_to(&mut buf, image::ImageOutputFormat::Png).map_err(|e| e.to_string())?;
+    Ok(buf.into_inner())
+}
+
+/// Splits a comma-separated extension list into entries lofty recognizes and the rest.
+/// Leading dots and case are ignored, and built-in extensions are dropped as duplicates.
+pub fn parse_extensions(list: &str) -> (Vec<String>, Vec<String>) {
+    let mut valid = Vec::new();
+    let mut rejected = Vec::new();
+    for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let ext = entry.trim_start_matches('.').to_lowercase();
+        let plausible = !ext.is_empty() && ext.len() <= 8 && ext.chars().all(|c| c.is_ascii_alphanumeric());
+        if !plausible || FileType::from_ext(&ext).is_none() {
+            rejected.push(entry.to_string());
+        } else if !SUPPORTED_EXTENSIONS.contains(&ext.as_str()) && !valid.contains(&ext) {
+            valid.push(ext);
+        }
+    }
+    (valid, rejected)
+}
+
+/// Whether the path has one of the extensions NaviTag can tag: a built-in one or one of `extra`.
+pub fn is_supported(path: &Path, extra: &[String]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|ext| ext.to_lowercase())
+        .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.as_str()) || extra.contains(&ext))
+        .unwrap_or(false)
+}
+
+/// Loads every supported file in `path`, counting `extra` extensions as supported.
+/// Files that fail to load are returned alongside the successes with the reason,
+/// so the UI can report them.
+pub fn scan_folder(path: &Path, separator: char, extra: &[String]) -> (Vec<AudioFile>, Vec<(PathBuf, String)>) {
     let mut files = Vec::new();
+    let mut errors = Vec::new();
     if let Ok(entries) = std::fs::read_dir(path) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                    let ext = ext.to_lowercase();
-                    if ["mp3", "flac", "ogg", "m4a", "wav"].contains(&ext.as_str()) {
-                        if let Some(audio_file) = AudioFile::load(path.clone()) {
-                            files.push(audio_file);
-                        }
-                    }
+            if path.is_file() && is_supported(&path, extra) {
+                match AudioFile::load(path.clone(), separator) {
+                    Ok(audio_file) => files.push(audio_file),
+                    Err(e) => errors.push((path, e)),
                 }
             }
         }
     }
     files.sort_by(|a, b| a.path.cmp(&b.path));
-    files
+    errors.sort_by(|a, b| a.0.cmp(&b.0));
+    (files, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guess(name: &str) -> FilenameGuess {
+        parse_from_filename(Path::new(name))
+    }
+
+    fn expected(artist: Option<&str>, album: Option<&str>, track: Option<u32>, title: &str) -> FilenameGuess {
+        FilenameGuess {
+            title: Some(title.to_string()),
+            artist: artist.map(str::to_string),
+            album: album.map(str::to_string),
+            track,
+        }
+    }
+
+    #[test]
+    fn filename_patterns() {
+        assert_eq!(guess("Artist - Album - 03 - Title.mp3"), expected(Some("Artist"), Some("Album"), Some(3), "Title"));
+        assert_eq!(guess("03 - Artist - Title.flac"), expected(Some("Artist"), None, Some(3), "Title"));
+        assert_eq!(guess("Artist - Album - Title.ogg"), expected(Some("Artist"), Some("Album"), None, "Title"));
+        assert_eq!(guess("07 - Title.mp3"), expected(None, None, Some(7), "Title"));
+        assert_eq!(guess("Artist - Title.mp3"), expected(Some("Artist"), None, None, "Title"));
+        assert_eq!(guess("03. Title.mp3"), expected(None, None, Some(3), "Title"));
+        assert_eq!(guess("12 Title.mp3"), expected(None, None, Some(12), "Title"));
+        assert_eq!(guess("Just_A_Title.mp3"), expected(None, None, None, "Just A Title"));
+    }
+
+    #[test]
+    fn long_numbers_are_not_track_numbers() {
+        assert_eq!(guess("1999 - Title.mp3"), expected(Some("1999"), None, None, "Title"));
+        assert_eq!(guess("2001.mp3"), expected(None, None, None, "2001"));
+    }
+
+    #[test]
+    fn placeholders_match_exactly() {
+        assert!(is_placeholder(UNKNOWN_TITLE));
+        assert!(is_placeholder("  "));
+        assert!(is_placeholder("Unknown (Genius)"));
+        assert!(!is_placeholder("Unknown Pleasures"));
+        assert!(!is_placeholder("Unknown"));
+    }
+
+    #[test]
+    fn folders_skip_disc_folders() {
+        let guess = parse_from_folders(Path::new("/music/Artist/Album/CD 2/01 - Title.flac"));
+        assert_eq!(guess.artist.as_deref(), Some("Artist"));
+        assert_eq!(guess.album.as_deref(), Some("Album"));
+    }
+
+    #[test]
+    fn folders_split_artist_and_year_prefixes() {
+        let guess = parse_from_folders(Path::new("/music/Artist - Album/01 - Title.flac"));
+        assert_eq!(guess.artist.as_deref(), Some("Artist"));
+        assert_eq!(guess.album.as_deref(), Some("Album"));
+
+        let guess = parse_from_folders(Path::new("/home/Artist/2019 - Album/01 - Title.flac"));
+        assert_eq!(guess.artist.as_deref(), Some("Artist"));
+        assert_eq!(guess.album.as_deref(), Some("Album"));
+    }
+
+    #[test]
+    fn library_folders_are_never_artist_or_album() {
+        let guess = parse_from_folders(Path::new("/home/user/Music/Album/01 - Title.flac"));
+        assert_eq!(guess.artist, None);
+        assert_eq!(guess.album.as_deref(), Some("Album"));
+
+        assert_eq!(parse_from_folders(Path::new("/home/user/Downloads/01 - Title.flac")), FilenameGuess::default());
+    }
+
+    /// Path in the temp folder that no other test run uses.
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("navitag-{}-{}", std::process::id(), name))
+    }
+
+    /// The smallest FLAC lofty reads and writes: a STREAMINFO block for 44.1 kHz 16-bit
+    /// stereo with no samples, an empty Vorbis comment, a little padding and no audio frames.
+    fn empty_flac() -> Vec<u8> {
+        let mut data = b"fLaC".to_vec();
+        data.extend_from_slice(&[0x00, 0, 0, 34]);
+        data.extend_from_slice(&[0x10, 0x00, 0x10, 0x00, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0x0A, 0xC4, 0x42, 0xF0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0; 16]);
+        data.extend_from_slice(&[0x04, 0, 0, 15]);
+        data.extend_from_slice(&7u32.to_le_bytes());
+        data.extend_from_slice(b"NaviTag");
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&[0x81, 0, 0, 16]);
+        data.extend_from_slice(&[0; 16]);
+        data
+    }
+
+    #[test]
+    fn saving_a_cover_replaces_the_old_one() {
+        let path = temp_file("cover.flac");
+        std::fs::write(&path, empty_flac()).unwrap();
+
+        let mut file = AudioFile::load(path.clone(), DEFAULT_VALUE_SEPARATOR).unwrap();
+        file.picture_data = Some(b"old cover".to_vec());
+        file.save(SaveOptions::default()).unwrap();
+        let mut file = AudioFile::load(path.clone(), DEFAULT_VALUE_SEPARATOR).unwrap();
+        file.picture_data = Some(b"new cover".to_vec());
+        file.save(SaveOptions::default()).unwrap();
+
+        let tagged_file = Probe::open(&path).unwrap().read().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let pictures = tagged_file.primary_tag().unwrap().pictures();
+        assert_eq!(pictures.len(), 1);
+        assert_eq!(pictures[0].data(), b"new cover");
+    }
+
+    /// Writes `data` to a temp file and gives it a tag of the format's own type, since
+    /// `save` only edits a tag that is already there.
+    fn tagged_fixture(name: &str, data: &[u8]) -> PathBuf {
+        let path = temp_file(name);
+        std::fs::write(&path, data).unwrap();
+        let mut tagged_file = Probe::open(&path).unwrap().read().unwrap();
+        let mut tag = lofty::tag::Tag::new(tagged_file.primary_tag_type());
+        tag.set_title("Fixture".to_string());
+        tagged_file.insert_tag(tag);
+        tagged_file.save_to_path(&path, WriteOptions::default()).unwrap();
+        path
+    }
+
+    /// Edits the core fields of the file at `path`, saves it and checks that the fields come
+    /// back on reload and the audio properties are unchanged.
+    fn assert_round_trip(path: &Path, format: &str) {
+        let mut file = AudioFile::load(path.to_path_buf(), DEFAULT_VALUE_SEPARATOR).unwrap();
+        assert_eq!(file.format, format);
+        let (duration, sample_rate, channels) = (file.duration, file.sample_rate, file.channels);
+
+        file.title = "Title".to_string();
+        file.artist = "Artist".to_string();
+        file.album = "Album".to_string();
+        file.year = Some(2001);
+        file.track = Some(3);
+        file.genre = "Rock".to_string();
+        file.save(SaveOptions::default()).unwrap();
+
+        let saved = AudioFile::load(path.to_path_buf(), DEFAULT_VALUE_SEPARATOR);
+        std::fs::remove_file(path).unwrap();
+        let saved = saved.unwrap();
+        assert_eq!(
+            (saved.title.as_str(), saved.artist.as_str(), saved.album.as_str(), saved.year, saved.track, saved.genre.as_str()),
+            ("Title", "Artist", "Album", Some(2001), Some(3), "Rock")
+        );
+        assert_eq!((saved.format.as_str(), saved.duration, saved.sample_rate, saved.channels), (format, duration, sample_rate, channels));
+    }
+
+    /// One Ogg page holding a single packet shorter than 255 bytes.
+    fn ogg_page(header_type: u8, sequence: u32, granule: u64, packet: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS".to_vec();
+        page.extend_from_slice(&[0, header_type]);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&1u32.to_le_bytes());
+        page.extend_from_slice(&sequence.to_le_bytes());
+        page.extend_from_slice(&[0; 4]);
+        page.extend_from_slice(&[1, packet.len() as u8]);
+        page.extend_from_slice(packet);
+        let crc = page.iter().fold(0u32, |crc, &byte| {
+            (0..8).fold(crc ^ (u32::from(byte) << 24), |crc, _| if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04C1_1DB7 } else { crc << 1 })
+        });
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    #[test]
+    fn mp3_round_trips() {
+        // Two silent MPEG-1 Layer III frames, 128 kbps at 44.1 kHz
+        let frame = [&[0xFF, 0xFB, 0x90, 0x00][..], &[0; 413]].concat();
+        let data = frame.repeat(2);
+
+        // Saved as ID3v2.3, the default, where the year has to go out as TYER
+        assert_round_trip(&tagged_fixture("round-trip.mp3", &data), "MP3");
+    }
+
+    #[test]
+    fn opus_round_trips() {
+        let mut head = b"OpusHead".to_vec();
+        head.extend_from_slice(&[1, 2]);
+        head.extend_from_slice(&312u16.to_le_bytes());
+        head.extend_from_slice(&48000u32.to_le_bytes());
+        head.extend_from_slice(&[0, 0, 0]);
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&7u32.to_le_bytes());
+        tags.extend_from_slice(b"NaviTag");
+        tags.extend_from_slice(&0u32.to_le_bytes());
+
+        // One 20 ms frame of silence after the headers
+        let mut data = ogg_page(0x02, 0, 0, &head);
+        data.extend(ogg_page(0x00, 1, 0, &tags));
+        data.extend(ogg_page(0x04, 2, 312 + 960, &[0xF8, 0xFF, 0xFE]));
+
+        assert_round_trip(&tagged_fixture("round-trip.opus", &data), "Opus");
+    }
+
+    #[test]
+    fn aiff_round_trips() {
+        // 44.1 kHz 16-bit stereo, one sample frame of silence
+        let mut chunks = b"COMM".to_vec();
+        chunks.extend_from_slice(&18u32.to_be_bytes());
+        chunks.extend_from_slice(&2u16.to_be_bytes());
+        chunks.extend_from_slice(&1u32.to_be_bytes());
+        chunks.extend_from_slice(&16u16.to_be_bytes());
+        chunks.extend_from_slice(&[0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]);
+        chunks.extend_from_slice(b"SSND");
+        chunks.extend_from_slice(&12u32.to_be_bytes());
+        chunks.extend_from_slice(&[0; 12]);
+
+        let mut data = b"FORM".to_vec();
+        data.extend_from_slice(&(4 + chunks.len() as u32).to_be_bytes());
+        data.extend_from_slice(b"AIFF");
+        data.extend(chunks);
+
+        assert_round_trip(&tagged_fixture("round-trip.aiff", &data), "AIFF");
+        assert_round_trip(&tagged_fixture("round-trip.aif", &data), "AIFF");
+    }
+
+    #[test]
+    fn wavpack_round_trips() {
+        // A single block header: 44.1 kHz 16-bit stereo, one sample, first and last block
+        let flags: u32 = 1 | 0x800 | 0x1000 | (9 << 23);
+        let mut data = b"wvpk".to_vec();
+        data.extend_from_slice(&24u32.to_le_bytes());
+        data.extend_from_slice(&0x410u16.to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        for field in [1u32, 0, 1, flags, 0] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+
+        assert_round_trip(&tagged_fixture("round-trip.wv", &data), "WavPack");
+    }
+
+    #[test]
+    fn monkeys_audio_round_trips() {
+        // Descriptor and header of a version 3.99 file: 44.1 kHz 16-bit stereo, one frame
+        // holding one block, followed by four bytes of frame data
+        let mut data = b"MAC ".to_vec();
+        data.extend_from_slice(&3990u16.to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        for field in [52u32, 24, 0, 0, 4, 0, 0] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(&[0; 16]);
+        data.extend_from_slice(&2000u16.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        for field in [73728u32, 1, 1] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(&16u16.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&44100u32.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+
+        assert_round_trip(&tagged_fixture("round-trip.ape", &data), "APE");
+    }
 }
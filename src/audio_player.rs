@@ -0,0 +1,185 @@
+use rodio::{Decoder, OutputStream, Sink, Source};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlaybackStatus {
+    #[default]
+    Stopped,
+    Playing,
+    Paused,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlayerState {
+    pub path: Option<PathBuf>,
+    pub status: PlaybackStatus,
+    pub elapsed: Duration,
+    pub total: Option<Duration>,
+    pub error: Option<String>,
+}
+
+enum Command {
+    Play(PathBuf),
+    Pause,
+    Resume,
+    Stop,
+}
+
+/// Plays one track at a time on a dedicated audio thread.
+/// The output stream isn't `Send`, so the thread owns it along with the sink.
+pub struct AudioPlayer {
+    commands: Sender<Command>,
+    state: Arc<Mutex<PlayerState>>,
+}
+
+impl AudioPlayer {
+    pub fn new() -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let state = Arc::new(Mutex::new(PlayerState::default()));
+
+        let thread_state = state.clone();
+        std::thread::spawn(move || run_player(receiver, thread_state));
+
+        Self { commands, state }
+    }
+
+    pub fn state(&self) -> PlayerState {
+        self.state.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state().status != PlaybackStatus::Stopped
+    }
+
+    /// Starts `path` from the beginning, or resumes it if it's the paused track.
+    pub fn play(&self, path: PathBuf) {
+        let state = self.state();
+        if state.status == PlaybackStatus::Paused && state.path.as_ref() == Some(&path) {
+            let _ = self.commands.send(Command::Resume);
+        } else {
+            let _ = self.commands.send(Command::Play(path));
+        }
+    }
+
+    pub fn pause(&self) {
+        let _ = self.commands.send(Command::Pause);
+    }
+
+    pub fn stop(&self) {
+        let _ = self.commands.send(Command::Stop);
+    }
+}
+
+impl Default for AudioPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn run_player(receiver: Receiver<Command>, state: Arc<Mutex<PlayerState>>) {
+    let (_stream, handle) = match OutputStream::try_default() {
+        Ok(output) => output,
+        Err(e) => {
+            if let Ok(mut s) = state.lock() {
+                s.error = Some(format!("No audio output available: {}", e));
+            }
+            // Keep draining commands so senders never block on a dead player
+            while receiver.recv().is_ok() {}
+            return;
+        }
+    };
+
+    let mut sink: Option<Sink> = None;
+
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(200)) {
+            Ok(Command::Play(path)) => {
+                if let Some(old) = sink.take() {
+                    old.stop();
+                }
+
+                let decoded = File::open(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|f| Decoder::new(BufReader::new(f)).map_err(|e| e.to_string()));
+
+                match decoded.and_then(|source| {
+                    let total = source.total_duration();
+                    Sink::try_new(&handle)
+                        .map(|new_sink| {
+                            new_sink.append(source);
+                            (new_sink, total)
+                        })
+                        .map_err(|e| e.to_string())
+                }) {
+                    Ok((new_sink, total)) => {
+                        sink = Some(new_sink);
+                        if let Ok(mut s) = state.lock() {
+                            *s = PlayerState {
+                                path: Some(path),
+                                status: PlaybackStatus::Playing,
+                                elapsed: Duration::ZERO,
+                                total,
+                                error: None,
+                            };
+                        }
+                    }
+                    Err(e) => {
+                        if let Ok(mut s) = state.lock() {
+                            *s = PlayerState {
+                                error: Some(format!("Could not play file: {}", e)),
+                                ..Default::default()
+                            };
+                        }
+                    }
+                }
+            }
+            Ok(Command::Pause) => {
+                if let Some(sink) = &sink {
+                    sink.pause();
+                    if let Ok(mut s) = state.lock() {
+                        s.status = PlaybackStatus::Paused;
+                    }
+                }
+            }
+            Ok(Command::Resume) => {
+                if let Some(sink) = &sink {
+                    sink.play();
+                    if let Ok(mut s) = state.lock() {
+                        s.status = PlaybackStatus::Playing;
+                    }
+                }
+            }
+            Ok(Command::Stop) => {
+                if let Some(old) = sink.take() {
+                    old.stop();
+                }
+                if let Ok(mut s) = state.lock() {
+                    *s = PlayerState::default();
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if let Some(current) = &sink {
+            if current.empty() {
+                sink = None;
+                if let Ok(mut s) = state.lock() {
+                    *s = PlayerState::default();
+                }
+            } else if let Ok(mut s) = state.lock() {
+                s.elapsed = current.get_pos();
+            }
+        }
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -0,0 +1,81 @@
+use rodio::{Decoder, Source};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+// Only the first minute is analysed; that's plenty for a steady tempo
+const MAX_ANALYSIS_SECS: usize = 60;
+const FRAME_SIZE: usize = 1024;
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+
+/// Estimates the tempo of a track from the autocorrelation of its onset envelope.
+/// This is a blocking call; run it off the UI thread.
+pub fn detect_bpm(path: &Path) -> Result<u32, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let decoder = Decoder::new(BufReader::new(file)).map_err(|e| format!("Could not decode audio: {}", e))?;
+
+    let channels = decoder.channels().max(1) as usize;
+    let sample_rate = decoder.sample_rate() as usize;
+    if sample_rate == 0 {
+        return Err("Unknown sample rate".to_string());
+    }
+
+    // Mix down to mono while accumulating per-frame energy
+    let max_samples = sample_rate * channels * MAX_ANALYSIS_SECS;
+    let mut energies = Vec::new();
+    let mut frame_energy = 0.0f32;
+    let mut frame_len = 0;
+    let mut mono = 0.0f32;
+
+    for (i, sample) in decoder.take(max_samples).enumerate() {
+        mono += sample as f32 / i16::MAX as f32;
+        if (i + 1) % channels == 0 {
+            let value = mono / channels as f32;
+            frame_energy += value * value;
+            frame_len += 1;
+            mono = 0.0;
+
+            if frame_len == FRAME_SIZE {
+                energies.push(frame_energy);
+                frame_energy = 0.0;
+                frame_len = 0;
+            }
+        }
+    }
+
+    // Onset envelope: rises in energy between consecutive frames
+    let onsets: Vec<f32> = energies.windows(2).map(|w| (w[1] - w[0]).max(0.0)).collect();
+
+    let frames_per_sec = sample_rate as f32 / FRAME_SIZE as f32;
+    let min_lag = (frames_per_sec * 60.0 / MAX_BPM).floor().max(1.0) as usize;
+    let max_lag = (frames_per_sec * 60.0 / MIN_BPM).ceil() as usize;
+    if onsets.len() <= max_lag * 2 {
+        return Err("Track is too short to estimate tempo".to_string());
+    }
+
+    let mut best_lag = 0;
+    let mut best_score = 0.0f32;
+    for lag in min_lag..=max_lag {
+        let score: f32 = onsets.iter().zip(&onsets[lag..]).map(|(a, b)| a * b).sum();
+        if score > best_score {
+            best_score = score;
+            best_lag = lag;
+        }
+    }
+
+    if best_lag == 0 || best_score <= 0.0 {
+        return Err("No clear beat found".to_string());
+    }
+
+    let mut bpm = 60.0 * frames_per_sec / best_lag as f32;
+    // Fold octave errors into the range most music is tagged with
+    while bpm < 70.0 {
+        bpm *= 2.0;
+    }
+    while bpm > 180.0 {
+        bpm /= 2.0;
+    }
+
+    Ok(bpm.round() as u32)
+}
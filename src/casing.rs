@@ -0,0 +1,138 @@
+/// Words kept exactly as written here, wherever they appear.
+const EXCEPTIONS: [&str; 7] = ["feat.", "ft.", "vs.", "feat", "ft", "vs", "remix"];
+
+/// Short words left lowercase unless they start or end the text or follow a bracket.
+const MINOR_WORDS: [&str; 14] = ["a", "an", "the", "and", "but", "or", "nor", "of", "in", "on", "at", "to", "for", "by"];
+
+/// How the "Normalize" tools rewrite the text fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// "THE BEATLES" and "the beatles" both become "The Beatles"
+    TitleCase,
+    /// Trims and collapses runs of spaces, leaving the casing alone
+    CleanWhitespace,
+}
+
+impl Style {
+    pub const ALL: [Style; 2] = [Style::TitleCase, Style::CleanWhitespace];
+
+    pub fn apply(self, value: &str) -> String {
+        match self {
+            Style::TitleCase => title_case(value),
+            Style::CleanWhitespace => clean_whitespace(value),
+        }
+    }
+}
+
+impl std::fmt::Display for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Style::TitleCase => "Title Case",
+            Style::CleanWhitespace => "Clean Whitespace",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+pub fn clean_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Roman numerals up to XXXIX, as in "Symphony No. IV" or "Part II". Only words already
+/// written in capitals count, plus single letters other than "I", so "Xi" and "Mix" stay words.
+fn is_roman_numeral(word: &str) -> bool {
+    let upper = word.to_uppercase();
+    let single_letter = word.chars().count() == 1 && upper != "I";
+    if word != upper && !single_letter {
+        return false;
+    }
+    let tens = upper.trim_start_matches('X');
+    upper.len() - tens.len() <= 3
+        && ["", "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX"].contains(&tens)
+        && !upper.is_empty()
+}
+
+/// Lowercase, then uppercase the first letter and any letter after a hyphen or slash.
+fn capitalize(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    let mut start = true;
+    for c in word.chars() {
+        if start && c.is_alphanumeric() {
+            out.extend(c.to_uppercase());
+            start = false;
+        } else {
+            out.extend(c.to_lowercase());
+            if c == '-' || c == '/' {
+                start = true;
+            }
+        }
+    }
+    out
+}
+
+/// Words mixing cases after the first letter ("McCartney", "iPhone") were cased on purpose.
+fn is_deliberately_cased(word: &str) -> bool {
+    let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    let rest = letters.get(1..).unwrap_or_default();
+    rest.iter().any(|c| c.is_uppercase()) && rest.iter().any(|c| c.is_lowercase())
+        || letters.first().is_some_and(|c| c.is_lowercase()) && rest.iter().any(|c| c.is_uppercase())
+}
+
+pub fn title_case(value: &str) -> String {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    let last = words.len().saturating_sub(1);
+
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            // Brackets and quotes around a word don't count as part of it
+            let start = word.find(char::is_alphanumeric).unwrap_or(word.len());
+            let end = word
+                .char_indices()
+                .rev()
+                .find(|(_, c)| c.is_alphanumeric() || *c == '.')
+                .map_or(start, |(i, c)| i + c.len_utf8())
+                .max(start);
+            let (prefix, body, suffix) = (&word[..start], &word[start..end], &word[end..]);
+            let lower = body.to_lowercase();
+
+            let cased = if let Some(exception) = EXCEPTIONS.iter().find(|e| **e == lower) {
+                exception.to_string()
+            } else if is_roman_numeral(body.trim_end_matches('.')) {
+                body.to_uppercase()
+            } else if is_deliberately_cased(body) {
+                body.to_string()
+            } else if i != 0 && i != last && prefix.is_empty() && MINOR_WORDS.contains(&lower.as_str()) {
+                lower
+            } else {
+                capitalize(body)
+            };
+            format!("{}{}{}", prefix, cased, suffix)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_case_handles_words_ending_in_non_ascii_letters() {
+        assert_eq!(title_case("beyoncé"), "Beyoncé");
+        assert_eq!(title_case("(café) señor"), "(Café) Señor");
+        assert_eq!(title_case("NAÏVE ÅÄÖ"), "Naïve Åäö");
+    }
+
+    #[test]
+    fn roman_numerals_need_capitals_or_a_single_letter() {
+        assert_eq!(title_case("part II"), "Part II");
+        assert_eq!(title_case("symphony no. v"), "Symphony No. V");
+        assert_eq!(title_case("xi and mix"), "Xi and Mix");
+        assert_eq!(title_case("i am"), "I Am");
+        assert!(!is_roman_numeral("Xi"));
+        assert!(!is_roman_numeral("mix"));
+        assert!(is_roman_numeral("XIV"));
+    }
+}
@@ -0,0 +1,199 @@
+use crate::api::{self, Provider};
+use crate::audio::{self, AudioFile, SaveOptions};
+use crate::matching;
+use crate::settings::UserSettings;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: navitag [--tag <folder> [--source <name>]...]
+
+Without arguments the graphical editor opens.
+
+  --tag <folder>     Search for every file in <folder>, apply confident matches and save
+  --source <name>    Only search this provider: apple, spotify, genius, lastfm or deezer.
+                     Repeat to use several; defaults to the providers enabled in Settings
+  -h, --help         Show this message";
+
+/// What the command line asked for.
+struct Options {
+    folder: PathBuf,
+    sources: Vec<Provider>,
+}
+
+fn parse_source(name: &str) -> Option<Provider> {
+    match name.to_ascii_lowercase().as_str() {
+        "apple" | "applemusic" | "apple-music" => Some(Provider::AppleMusic),
+        "spotify" => Some(Provider::Spotify),
+        "genius" => Some(Provider::Genius),
+        "lastfm" | "last.fm" => Some(Provider::LastFm),
+        "deezer" => Some(Provider::Deezer),
+        _ => None,
+    }
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let mut folder = None;
+    let mut sources = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tag" => {
+                let value = args.next().ok_or("--tag needs a folder")?;
+                folder = Some(PathBuf::from(value));
+            }
+            "--source" => {
+                let value = args.next().ok_or("--source needs a provider name")?;
+                sources.push(parse_source(value).ok_or_else(|| format!("Unknown source: {}", value))?);
+            }
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+
+    Ok(Options {
+        folder: folder.ok_or("Nothing to do; pass --tag <folder>")?,
+        sources,
+    })
+}
+
+/// Runs a headless command if any arguments were given, returning the process exit code.
+/// Returns None when there are no arguments and the GUI should start instead.
+pub fn run(args: &[String]) -> Option<i32> {
+    if args.is_empty() {
+        return None;
+    }
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+
+    let options = match parse(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return Some(2);
+        }
+    };
+
+    // Read before the runtime starts; the keychain lookup blocks
+    let (settings, warning) = UserSettings::load_reporting();
+    if let Some(warning) = warning {
+        eprintln!("Warning: {}", warning);
+    }
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Could not start: {}", e);
+            return Some(1);
+        }
+    };
+    Some(runtime.block_on(tag_folder(options, settings)))
+}
+
+async fn tag_folder(options: Options, mut settings: UserSettings) -> i32 {
+    if !options.sources.is_empty() {
+        settings.enable_apple_music = options.sources.contains(&Provider::AppleMusic);
+        settings.enable_spotify = options.sources.contains(&Provider::Spotify);
+        settings.enable_genius = options.sources.contains(&Provider::Genius);
+        settings.enable_lastfm = options.sources.contains(&Provider::LastFm);
+        settings.enable_deezer = options.sources.contains(&Provider::Deezer);
+    }
+
+    if settings.offline {
+        eprintln!("Offline mode is on; turn it off in Settings to tag from the command line.");
+        return 2;
+    }
+    if let Err(e) = api::configure_proxy(&settings.http_proxy, &settings.https_proxy) {
+        eprintln!("{}", e);
+        return 2;
+    }
+    for warning in api::unconfigured_providers(&settings) {
+        eprintln!("Warning: {}", warning);
+    }
+
+    if !options.folder.is_dir() {
+        eprintln!("Not a folder: {}", options.folder.display());
+        return 2;
+    }
+
+    let separator = settings.value_separator;
+    let extra = settings.extra_extensions();
+    let folder = options.folder.clone();
+    let (mut files, errors) = tokio::task::spawn_blocking(move || audio::scan_folder(&folder, separator, &extra))
+        .await
+        .unwrap_or_default();
+    for (path, e) in &errors {
+        eprintln!("Skipped {}: {}", path.display(), e);
+    }
+
+    let save_options = SaveOptions::from_settings(&settings);
+    let mut tagged = 0;
+    let mut unmatched = 0;
+    let mut failed = errors.len();
+    let mut skipped = 0;
+
+    for file in &mut files {
+        if settings.only_incomplete && file.has_complete_tags() {
+            skipped += 1;
+            continue;
+        }
+        let name = file.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let query = crate::search_query_for(file, &settings.search_query_template);
+        let outcome = api::search_all(query, settings.clone()).await;
+        for (provider, e) in &outcome.errors {
+            eprintln!("{}: {} failed: {}", name, provider, e);
+        }
+
+        let best = match matching::best_match(file, &outcome.results) {
+            Some((best, score)) if score >= matching::CONFIDENT_MATCH => best.clone(),
+            _ => {
+                println!("{}: no confident match", name);
+                unmatched += 1;
+                continue;
+            }
+        };
+
+        apply_result(file, &best, &settings).await;
+        match file.save(save_options) {
+            Ok(()) => {
+                println!("{}: {} - {} ({})", name, best.artist, best.title, best.source);
+                tagged += 1;
+            }
+            Err(e) => {
+                eprintln!("{}: save failed: {}", name, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "Tagged {} of {} files ({} without a confident match, {} already complete, {} failed).",
+        tagged,
+        files.len() + errors.len(),
+        unmatched,
+        skipped,
+        failed
+    );
+    if failed > 0 { 1 } else { 0 }
+}
+
+/// Copies a result's fields onto the file, adding its cover only if the file has none.
+async fn apply_result(file: &mut AudioFile, result: &api::MetadataResult, settings: &UserSettings) {
+    file.title = result.title.clone();
+    file.artist = result.artist.clone();
+    file.album = result.album.clone();
+    if let Some(year) = result.year {
+        file.year = Some(year);
+    }
+    if let Some(track) = result.track {
+        file.track = Some(track);
+    }
+    crate::apply_optional_fields(file, result, settings);
+
+    if file.picture_data.is_none() {
+        if let Some(url) = result.cover_url() {
+            if let Ok(bytes) = crate::fetch_cover_bytes(url).await {
+                file.picture_data = Some(crate::prepare_cover(bytes, settings.max_embedded_cover, settings.cover_jpeg_quality()).await);
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Oldest entries are evicted once the cache grows past this size.
+const MAX_CACHE_BYTES: u64 = 200 * 1024 * 1024;
+
+fn cache_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("navitag").join("cover_cache"))
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher`, its output is the same across Rust releases,
+/// so cached covers keep their file names after an update.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+fn cache_path(url: &str) -> Option<PathBuf> {
+    cache_dir().map(|d| d.join(format!("{:016x}", fnv1a(url.as_bytes()))))
+}
+
+pub fn get(url: &str) -> Option<Vec<u8>> {
+    let path = cache_path(url)?;
+    let data = fs::read(&path).ok()?;
+
+    // Touch the entry so eviction drops the least recently used covers first
+    if let Ok(file) = fs::File::options().write(true).open(&path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+
+    Some(data)
+}
+
+/// Stores a downloaded cover, evicting old entries if the cache is full. Does blocking
+/// file I/O, so async callers should run it with `spawn_blocking`.
+pub fn put(url: &str, data: &[u8]) {
+    if let (Some(dir), Some(path)) = (cache_dir(), cache_path(url)) {
+        if fs::create_dir_all(&dir).is_ok() && fs::write(&path, data).is_ok() {
+            evict(&dir);
+        }
+    }
+}
+
+pub fn clear() -> Result<usize, String> {
+    let Some(dir) = cache_dir() else {
+        return Ok(0);
+    };
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())?.flatten() {
+        if fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn evict(dir: &Path) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut entries: Vec<(PathBuf, u64, SystemTime)> = read_dir
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((entry.path(), meta.len(), modified))
+        })
+        .collect();
+
+    let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    if total <= MAX_CACHE_BYTES {
+        return;
+    }
+
+    entries.sort_by_key(|(_, _, modified)| *modified);
+    for (path, size, _) in entries {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
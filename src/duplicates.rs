@@ -0,0 +1,75 @@
+use crate::audio::AudioFile;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Files are considered the same recording when their length differs by less than this.
+const DURATION_TOLERANCE_SECS: u64 = 1;
+
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+fn is_known(value: &str) -> bool {
+    !crate::audio::is_placeholder(value)
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parents, a), find(parents, b));
+    if a != b {
+        parents[b] = a;
+    }
+}
+
+/// Groups files that look like copies of each other: the same artist and title, or the
+/// same file size with (almost) the same duration. Only groups of two or more are returned.
+pub fn find_duplicates(files: &[AudioFile]) -> Vec<Vec<PathBuf>> {
+    let mut parents: Vec<usize> = (0..files.len()).collect();
+
+    let mut by_tags: HashMap<(String, String), usize> = HashMap::new();
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+
+    for (i, file) in files.iter().enumerate() {
+        if is_known(&file.artist) && is_known(&file.title) {
+            let key = (normalize(&file.artist), normalize(&file.title));
+            match by_tags.get(&key) {
+                Some(&first) => union(&mut parents, first, i),
+                None => {
+                    by_tags.insert(key, i);
+                }
+            }
+        }
+
+        if let Ok(metadata) = std::fs::metadata(&file.path) {
+            by_size.entry(metadata.len()).or_default().push(i);
+        }
+    }
+
+    for same_size in by_size.values() {
+        for (n, &a) in same_size.iter().enumerate() {
+            for &b in &same_size[n + 1..] {
+                if files[a].duration.as_secs().abs_diff(files[b].duration.as_secs()) <= DURATION_TOLERANCE_SECS {
+                    union(&mut parents, a, b);
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<PathBuf>> = HashMap::new();
+    for (i, file) in files.iter().enumerate() {
+        let root = find(&mut parents, i);
+        groups.entry(root).or_default().push(file.path.clone());
+    }
+
+    let mut groups: Vec<Vec<PathBuf>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    groups.sort();
+    groups
+}
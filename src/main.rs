@@ -1,122 +1,637 @@
 mod api;
 mod audio;
+mod audio_player;
+mod bpm;
+mod casing;
+mod cli;
+mod cover_cache;
+mod duplicates;
+mod matching;
+mod metadata_io;
+mod organizer;
 mod toast;
 mod settings;
+mod stats;
+mod text_encoding;
 
-use iced::widget::{button, checkbox, column, container, image as image_widget, row, scrollable, stack, text, text_input, vertical_space};
+use iced::widget::{button, checkbox, column, container, image as image_widget, mouse_area, pick_list, row, scrollable, stack, text, text_editor, text_input, tooltip, vertical_space};
 use iced::{Element, Length, Task, Theme};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
 use std::time::{Duration, Instant};
 
+const FILE_LIST_ID: &str = "file-list";
+const SEARCH_INPUT_ID: &str = "search-input";
+const MAX_CONCURRENT_COVER_FETCHES: usize = 4;
+/// How long a query's results are reused before the providers are asked again
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+/// Rows decoded before the file list has reported its scroll position
+const INITIAL_THUMBNAIL_ROWS: usize = 30;
+/// Rows above and below the visible range that are decoded ahead of time
+const THUMBNAIL_ROW_MARGIN: usize = 10;
+/// Idle time after the last edit before auto-save writes the file
+const AUTO_SAVE_DELAY: Duration = Duration::from_secs(1);
+/// A save can rewrite the whole file, so big files wait longer to collect more edits
+const LARGE_FILE_BYTES: u64 = 100 * 1024 * 1024;
+const LARGE_FILE_AUTO_SAVE_DELAY: Duration = Duration::from_secs(3);
+
+// Shared by every thumbnail task so a huge folder never decodes more than a few images at once
+static THUMBNAIL_DECODES: tokio::sync::Semaphore = tokio::sync::Semaphore::const_new(4);
+const MIN_WINDOW_WIDTH: f32 = 640.0;
+const MIN_WINDOW_HEIGHT: f32 = 480.0;
+
+// Saved position handed to `restore_position`, which has to be a plain fn
+static RESTORED_POSITION: OnceLock<iced::Point> = OnceLock::new();
 
 pub fn main() -> iced::Result {
-    iced::application("NaviTag - Music Tagger", App::update, App::view)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
+    let geometry = settings::UserSettings::load().window;
+
+    iced::application(App::title, App::update, App::view)
         .theme(App::theme)
         .subscription(App::subscription)
+        .window(window_settings(geometry))
         .run()
 }
 
+fn window_settings(geometry: Option<settings::WindowGeometry>) -> iced::window::Settings {
+    let mut window = iced::window::Settings::default();
+
+    if let Some(geometry) = geometry {
+        window.size = iced::Size::new(
+            geometry.width.max(MIN_WINDOW_WIDTH),
+            geometry.height.max(MIN_WINDOW_HEIGHT),
+        );
+        if let (Some(x), Some(y)) = (geometry.x, geometry.y) {
+            let _ = RESTORED_POSITION.set(iced::Point::new(x, y));
+            window.position = iced::window::Position::SpecificWith(restore_position);
+        }
+    }
+
+    window
+}
+
+/// Puts the window back where it was, or centers it if that spot is no longer on screen
+/// (e.g. the monitor it was on has been unplugged).
+fn restore_position(window: iced::Size, monitor: iced::Size) -> iced::Point {
+    // Keep at least this much of the title bar reachable
+    const MARGIN: f32 = 50.0;
+
+    let centered = iced::Point::new(
+        ((monitor.width - window.width) / 2.0).max(0.0),
+        ((monitor.height - window.height) / 2.0).max(0.0),
+    );
+
+    match RESTORED_POSITION.get() {
+        Some(saved)
+            if saved.x + window.width > MARGIN
+                && saved.x < monitor.width - MARGIN
+                && saved.y >= 0.0
+                && saved.y < monitor.height - MARGIN =>
+        {
+            *saved
+        }
+        _ => centered,
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Page {
     TitleScreen,
     Editor,
 }
 
+/// A 50x50 cover thumbnail in the search results or the cover preview.
+#[derive(Debug, Clone)]
+enum Thumbnail {
+    Loading,
+    Loaded(Vec<u8>),
+    /// The result has no cover URL
+    Missing,
+    /// Every download attempt failed
+    Failed,
+}
+
+/// Which fields "Apply Selected" copies from a compared search result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FieldMask {
+    title: bool,
+    artist: bool,
+    album: bool,
+    year: bool,
+    track: bool,
+    cover: bool,
+}
+
+impl FieldMask {
+    /// The fields where the result has a value that differs from the file's; the cover only
+    /// when the file has none yet.
+    fn differences(file: &audio::AudioFile, result: &api::MetadataResult) -> Self {
+        Self {
+            title: file.title != result.title,
+            artist: file.artist != result.artist,
+            album: file.album != result.album,
+            year: result.year.is_some() && file.year != result.year,
+            track: result.track.is_some() && file.track != result.track,
+            cover: !result.cover_urls.is_empty() && file.picture_data.is_none(),
+        }
+    }
+}
+
+/// Which files a normalize tool rewrites.
+#[derive(Debug, Clone, Copy)]
+enum NormalizeTarget {
+    Selected,
+    /// Every file the file list filter currently shows
+    Shown,
+}
+
+/// Where the user was headed when the unsaved-changes prompt was shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LeaveAction {
+    Exit,
+    BackToTitle,
+    ReloadFolder,
+    /// A folder picked with Open Folder or dropped on the window
+    OpenFolder(PathBuf),
+}
+
+/// Progress of a long folder operation, shared with the task doing the work so the
+/// loading overlay can show a count and stop it between items.
+#[derive(Debug, Clone)]
+struct BatchJob {
+    cancelled: Arc<AtomicBool>,
+    done: Arc<AtomicUsize>,
+    total: usize,
+}
+
+impl BatchJob {
+    fn new(total: usize) -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            done: Arc::new(AtomicUsize::new(0)),
+            total,
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn advance(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn done(&self) -> usize {
+        self.done.load(Ordering::Relaxed)
+    }
+}
+
+/// A batch search result waiting for the user to accept or reject it.
+#[derive(Debug, Clone)]
+struct BatchProposal {
+    path: PathBuf,
+    result: api::MetadataResult,
+    selected: bool,
+}
+
 struct App {
     current_page: Page,
     last_edit_time: Option<Instant>,
-    has_unsaved_changes: bool,
+    /// Idle time auto-save waits for, depending on the size of the file last edited
+    auto_save_delay: Duration,
+    /// File whose applied result's cover is still downloading; auto-save holds off so
+    /// the text and the cover go out in one write
+    cover_pending: Option<PathBuf>,
+    /// File changed by Swap Artist/Title or Guess from folder; auto-save holds off for it
+    /// until the next manual edit, so the change can be reviewed first
+    review_pending: Option<PathBuf>,
     current_dir: Option<PathBuf>,
+    /// File to select once the folder being loaded is ready
+    pending_selection: Option<PathBuf>,
     files: Vec<audio::AudioFile>,
+    /// Visible part of the file list as fractions of its height, once known
+    file_list_view: Option<(f32, f32)>,
+    thumbnails_requested: HashSet<PathBuf>,
     selected_file_index: Option<usize>,
+    file_filter: String,
+    /// Year range typed in the left panel, as digits; empty leaves that end open
+    year_min: String,
+    year_max: String,
+    show_unknown_year: bool,
+    sort_key: audio::SortKey,
+    sort_ascending: bool,
+    year_error: Option<String>,
+    /// Multi-line editor state for the selected file's comment
+    comment_editor: text_editor::Content,
+    /// Providers whose results are hidden in the results list
+    hidden_sources: HashSet<String>,
+    lyrics_editor: text_editor::Content,
     search_query: String,
     search_results: Vec<api::MetadataResult>,
-    search_images: Vec<Option<Vec<u8>>>,
+    search_images: Vec<Thumbnail>,
+    album_mode: bool,
+    album_results: Vec<api::AlbumResult>,
     is_searching: bool,
+    /// Results of earlier searches this session, keyed by normalized query
+    search_cache: HashMap<String, (Instant, Vec<api::MetadataResult>)>,
+    /// Providers last reported as enabled without credentials, so the warning isn't
+    /// repeated on every search until the configuration changes
+    warned_unconfigured: Vec<String>,
+    /// Name typed in Settings for "Save as New Profile"
+    new_profile_name: String,
+    /// The results on screen came from `search_cache`
+    search_from_cache: bool,
     toast_manager: toast::Manager,
+    player: audio_player::AudioPlayer,
     settings: settings::UserSettings,
+    system_prefers_light: bool,
+    window_geometry: Option<settings::WindowGeometry>,
     show_settings: bool,
+    show_advanced_tags: bool,
+    pending_batch: Vec<BatchProposal>,
+    /// Files the last batch operation left out because they were already complete
+    batch_skipped: usize,
+    /// Results behind `pending_batch`, kept so they can be re-paired with another mapping
+    batch_results: Vec<api::MetadataResult>,
+    batch_mapping: matching::BatchMapping,
+    show_duplicates: bool,
+    show_stats: bool,
+    duplicate_groups: Vec<Vec<PathBuf>>,
+    duplicates_selected: HashSet<PathBuf>,
+    confirm_delete_duplicates: bool,
+    /// Library root and planned moves shown in the organizer preview
+    organize_plan: Option<(PathBuf, Vec<organizer::PlannedMove>)>,
+    /// Read-only files the user was asked about before saving
+    read_only_pending: Vec<PathBuf>,
+    /// Files the last Save All couldn't write, with the reason
+    save_failures: Vec<(PathBuf, String)>,
+    /// File whose right-click menu is open
+    file_menu: Option<PathBuf>,
+    advanced_tag_error: Option<String>,
     
-    show_exit_confirmation: bool,
+    pending_leave: Option<LeaveAction>,
+    /// Where to go once the save started by the unsaved-changes prompt has finished
+    leave_after_save: Option<LeaveAction>,
+    show_clear_tags_confirmation: bool,
+    /// Search result shown side by side with the selected file, and the fields ticked to apply
+    compare: Option<(api::MetadataResult, FieldMask)>,
+    /// A search result suggesting this file's artist and title are reversed
+    swap_suggestion: Option<(PathBuf, api::MetadataResult)>,
+    show_cover_preview: bool,
+    show_cover_to_all_confirmation: bool,
+    /// Full-size artwork shown in the cover preview, None while it downloads
+    cover_preview: Option<Vec<u8>>,
+    /// Every cover the previewed result offers, and which one is shown
+    cover_preview_candidates: Vec<String>,
+    cover_preview_choice: usize,
+    cover_preview_thumbs: Vec<Thumbnail>,
     should_exit: bool,
     
     is_loading: bool,
     loading_message: String,
+    /// The batch operation behind the loading overlay, when it can be cancelled
+    batch_job: Option<BatchJob>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     OpenFolder,
     FolderPicked(Option<PathBuf>),
-    FilesLoaded(Vec<audio::AudioFile>),
+    PathDropped(PathBuf),
+    FilesLoaded(Vec<audio::AudioFile>, Vec<(PathBuf, String)>),
     FileSelected(usize),
+    SelectPrev,
+    SelectNext,
+    FileFilterChanged(String),
+    YearMinChanged(String),
+    YearMaxChanged(String),
+    DecadePicked(u32),
+    ToggleUnknownYear(bool),
+    FileListScrolled(scrollable::Viewport),
+    ThumbnailReady(PathBuf, Option<Vec<u8>>),
+    SortBy(audio::SortKey),
+    ToggleSortDirection,
     TitleChanged(String),
     ArtistChanged(String),
     AlbumChanged(String),
+    AlbumArtistChanged(String),
+    CompilationToggled(bool),
+    ExplicitToggled(bool),
+    YearChanged(String),
+    GenreChanged(String),
+    ComposerChanged(String),
+    CommentEdited(text_editor::Action),
+    LyricsEdited(text_editor::Action),
+    BpmChanged(String),
+    DetectBpm,
+    BpmDetected(PathBuf, Result<u32, String>),
+    GuessFromFilename,
+    /// Fills Unknown fields from the filename and the Artist/Album folders around it
+    GuessFromFolder,
+    FixEncoding,
+    NormalizeCasing(casing::Style, NormalizeTarget),
+    IdentifyByAudio,
+    AudioIdentified(PathBuf, Result<api::MetadataResult, String>),
+    RevertFile,
+    ClearTagsRequested,
+    ClearTags,
+    CancelClearTags,
+    ToggleAdvancedTags,
+    AdvancedTagKeyChanged(usize, String),
+    AdvancedTagValueChanged(usize, String),
+    AddAdvancedTag,
+    RemoveAdvancedTag(usize),
     SavePressed,
+    SaveAsCopy,
+    CopyTargetPicked(PathBuf, Option<PathBuf>),
+    Play,
+    Pause,
+    Stop,
+    PlaybackTick(Instant),
     SearchQueryChanged(String),
     SearchPressed,
-    SearchResults(Result<Vec<api::MetadataResult>, String>),
+    FocusSearch,
+    SearchResults(String, api::SearchOutcome),
+    RefreshSearch,
+    ToggleSourceFilter(String),
     SearchCoverLoaded(usize, Result<Vec<u8>, String>),
     ApplyMetadata(api::MetadataResult),
-    CoverDownloaded(Result<Vec<u8>, String>),
+    /// Like ApplyMetadata, but keeps the file's current cover
+    ApplyTextOnly(api::MetadataResult),
+    StageMetadata(api::MetadataResult),
+    ToggleAlbumMode(bool),
+    AlbumResults(api::SearchOutcome<api::AlbumResult>),
+    ApplyAlbum(usize),
+    AutoApplyBest,
+    CompareResult(api::MetadataResult),
+    CompareMaskChanged(FieldMask),
+    ApplyFields(api::MetadataResult, FieldMask),
+    CloseCompare,
+    SwapArtistTitle,
+    CheckSwappedFields,
+    SwapCheckResults(PathBuf, api::SearchOutcome),
+    DismissSwapSuggestion,
+    AutoApplyResults(PathBuf, api::SearchOutcome),
+    CoverDownloaded(PathBuf, Result<Vec<u8>, String>),
+    CoverPrepared(PathBuf, Vec<u8>),
+    PreviewCover(Vec<String>),
+    ApplyCoverToAll,
+    ConfirmCoverToAll(bool),
+    CancelCoverToAll,
+    CoverPreviewLoaded(usize, Result<Vec<u8>, String>),
+    CoverCandidateThumb(usize, Result<Vec<u8>, String>),
+    SelectCoverCandidate(usize),
+    ApplyPreviewCover,
+    CloseCoverPreview,
     SaveAll,
+    MakeWritableAndSave,
+    DismissReadOnly,
+    RetryFailedSaves,
+    DismissSaveFailures,
+    ShowFileMenu(PathBuf),
+    CloseFileMenu,
+    RevealFile(PathBuf),
+    OpenContainingFolder(PathBuf),
+    /// Ok(false) when the file manager opened the folder without highlighting the file
+    FileRevealed(Result<bool, String>),
+    FolderOpened(Result<(), String>),
+    ExportMetadata,
+    ExportPathPicked(Option<PathBuf>),
+    ImportMetadata,
+    ImportPathPicked(Option<PathBuf>),
     
     CloseRequested,
-    ConfirmExit(bool),
-    CancelExit,
+    WindowResized(iced::Size),
+    WindowMoved(iced::Point),
+    ConfirmLeave(bool),
+    CancelLeave,
     
     Tick(Instant),
     SpotifyIdChanged(String),
     SpotifySecretChanged(String),
     ToggleSpotify(bool),
     BatchTag,
-    BatchResults(Result<Vec<api::MetadataResult>, String>),
+    BatchResults(api::SearchOutcome),
+    ToggleBatchRow(usize, bool),
+    BatchMappingChanged(matching::BatchMapping),
+    ApplyBatchSelection,
+    DiscardBatch,
+    FetchAllCovers,
+    FindDuplicates,
+    ToggleStats,
+    ToggleDuplicate(PathBuf, bool),
+    DeleteDuplicatesRequested,
+    ConfirmDeleteDuplicates,
+    CancelDeleteDuplicates,
+    MoveDuplicates,
+    DuplicatesTargetPicked(Option<PathBuf>),
+    DuplicatesMoved(HashSet<PathBuf>, Vec<String>),
+    CloseDuplicates,
+    /// Asks for a library root and previews moving the loaded files into it
+    OrganizeLibrary,
+    OrganizeRootPicked(Option<PathBuf>),
+    OrganizeTemplateChanged(String),
+    ConfirmOrganize,
+    OrganizeFinished(PathBuf, Vec<(organizer::PlannedMove, Result<(), String>)>),
+    CancelOrganize,
+    CoversFetched(Vec<(PathBuf, Option<Vec<u8>>)>),
+    SaveAllFinished(Vec<(PathBuf, Result<(), String>)>, Vec<PathBuf>),
+    CancelBatch,
+    BatchProgressTick,
     ToggleSettings,
-    SettingsChanged(settings::UserSettings),
+    /// Turns offline mode on or off and remembers the choice right away
+    OfflineToggled(bool),
+    SettingsChanged(Box<settings::UserSettings>),
+    /// Moves a provider one place up (true) or down in the result order
+    MoveProvider(usize, bool),
     SaveSettings,
+    ClearCoverCache,
+    TestConnection(api::Provider),
+    ConnectionTested(api::Provider, Result<(), String>),
+    ResetProvider(api::Provider),
+    ProfileSelected(String),
+    NewProfileNameChanged(String),
+    SaveProfileAs,
+    DeleteProfile,
     SwitchToEditor,
     SwitchToTitle,
+    ReloadFolder,
+    DismissToast(u64),
+}
+
+impl Message {
+    /// Whether handling the message starts a search, credential test or download, which
+    /// offline mode refuses.
+    fn needs_network(&self) -> bool {
+        matches!(
+            self,
+            Message::SearchPressed
+                | Message::RefreshSearch
+                | Message::AutoApplyBest
+                | Message::BatchTag
+                | Message::FetchAllCovers
+                | Message::CheckSwappedFields
+                | Message::IdentifyByAudio
+                | Message::TestConnection(_)
+        )
+    }
 }
 
 impl Default for App {
     fn default() -> Self {
+        let (settings, config_warning) = settings::UserSettings::load_reporting();
+        let mut toast_manager = toast::Manager::new();
+        toast_manager.configure(settings.toast_duration(), settings.sticky_error_toasts);
+        if let Some(warning) = config_warning {
+            toast_manager.add_persistent(toast::Toast::new(toast::Status::Warning, "Settings Couldn't Be Read", warning));
+        }
+        if let Err(e) = api::configure_proxy(&settings.http_proxy, &settings.https_proxy) {
+            toast_manager.add(toast::Toast::new(toast::Status::Warning, "Proxy Not Used", e));
+        }
+        set_download_limit(settings.concurrent_downloads());
+        api::set_offline(settings.offline);
+
         Self {
             current_page: Page::TitleScreen,
             last_edit_time: None,
-            has_unsaved_changes: false,
+            auto_save_delay: AUTO_SAVE_DELAY,
+            cover_pending: None,
+            review_pending: None,
             current_dir: None,
+            pending_selection: None,
             files: Vec::new(),
+            file_list_view: None,
+            thumbnails_requested: HashSet::new(),
             selected_file_index: None,
+            file_filter: String::new(),
+            year_min: String::new(),
+            year_max: String::new(),
+            show_unknown_year: true,
+            sort_key: audio::SortKey::Filename,
+            sort_ascending: true,
+            year_error: None,
+            comment_editor: text_editor::Content::new(),
+            hidden_sources: HashSet::new(),
+            lyrics_editor: text_editor::Content::new(),
             search_query: String::new(),
             search_results: Vec::new(),
             search_images: Vec::new(),
+            album_mode: false,
+            album_results: Vec::new(),
             is_searching: false,
-            toast_manager: toast::Manager::new(),
-            settings: settings::UserSettings::load(),
+            search_cache: HashMap::new(),
+            warned_unconfigured: Vec::new(),
+            new_profile_name: String::new(),
+            search_from_cache: false,
+            toast_manager,
+            player: audio_player::AudioPlayer::new(),
+            settings,
+            system_prefers_light: matches!(dark_light::detect(), dark_light::Mode::Light),
+            window_geometry: None,
             show_settings: false,
+            show_advanced_tags: false,
+            pending_batch: Vec::new(),
+            batch_skipped: 0,
+            batch_results: Vec::new(),
+            batch_mapping: matching::BatchMapping::default(),
+            show_duplicates: false,
+            show_stats: false,
+            duplicate_groups: Vec::new(),
+            duplicates_selected: HashSet::new(),
+            confirm_delete_duplicates: false,
+            organize_plan: None,
+            read_only_pending: Vec::new(),
+            save_failures: Vec::new(),
+            file_menu: None,
+            advanced_tag_error: None,
 
-            show_exit_confirmation: false,
+            pending_leave: None,
+            leave_after_save: None,
+            show_clear_tags_confirmation: false,
+            swap_suggestion: None,
+            compare: None,
+            show_cover_preview: false,
+            show_cover_to_all_confirmation: false,
+            cover_preview: None,
+            cover_preview_candidates: Vec::new(),
+            cover_preview_choice: 0,
+            cover_preview_thumbs: Vec::new(),
             should_exit: false,
             is_loading: false,
             loading_message: String::new(),
+            batch_job: None,
         }
     }
 }
 
 impl App {
     fn subscription(&self) -> iced::Subscription<Message> {
-        let tick = if self.has_unsaved_changes {
+        let tick = if self.settings.auto_save && self.selected_file_dirty() {
              iced::time::every(Duration::from_millis(100)).map(Message::Tick)
         } else {
              iced::Subscription::none()
         };
         
+        let playback = if self.player.is_active() {
+             iced::time::every(Duration::from_millis(250)).map(Message::PlaybackTick)
+        } else {
+             iced::Subscription::none()
+        };
+        
         let events = iced::window::close_events().map(|_| Message::CloseRequested);
 
-        iced::Subscription::batch(vec![tick, events])
+        let geometry = iced::event::listen_with(|event, _status, _id| match event {
+            iced::Event::Window(iced::window::Event::Resized(size)) => Some(Message::WindowResized(size)),
+            iced::Event::Window(iced::window::Event::Moved(position)) => Some(Message::WindowMoved(position)),
+            iced::Event::Window(iced::window::Event::FileDropped(path)) => Some(Message::PathDropped(path)),
+            _ => None,
+        });
+
+        let keys = iced::keyboard::on_key_press(handle_key_press);
+
+        // Dialogs and the loading overlay own the keyboard while they are up
+        let shortcuts = if self.current_page == Page::Editor && !self.modal_open() {
+             iced::event::listen_with(handle_shortcut)
+        } else {
+             iced::Subscription::none()
+        };
+
+        // Keeps ticking while toasts are up so expired ones get pruned
+        let toasts = if self.toast_manager.is_empty() {
+             iced::Subscription::none()
+        } else {
+             iced::time::every(Duration::from_millis(500)).map(Message::Tick)
+        };
+
+        let progress = if self.batch_job.is_some() {
+             iced::time::every(Duration::from_millis(200)).map(|_| Message::BatchProgressTick)
+        } else {
+             iced::Subscription::none()
+        };
+
+        iced::Subscription::batch(vec![tick, playback, events, geometry, keys, shortcuts, progress, toasts])
     }
     fn update(&mut self, message: Message) -> Task<Message> {
+        if self.settings.offline && message.needs_network() {
+            self.toast_manager.add(toast::Toast::new(
+                toast::Status::Info,
+                "Offline Mode",
+                "Searching and downloading are off. Turn off offline mode to use online sources."
+            ));
+            return Task::none();
+        }
+
         match message {
 
             Message::OpenFolder => {
@@ -125,279 +640,1809 @@ impl App {
                 Task::perform(pick_folder(), Message::FolderPicked)
             }
             Message::FolderPicked(Some(path)) => {
-                self.current_dir = Some(path.clone());
-                self.current_page = Page::Editor;
-                self.loading_message = "Scanning files...".to_string();
-                Task::perform(load_files(path), Message::FilesLoaded)
+                if self.has_unsaved_changes() {
+                    self.is_loading = false;
+                    self.pending_leave = Some(LeaveAction::OpenFolder(path));
+                    Task::none()
+                } else {
+                    self.open_folder(path)
+                }
+            }
+            Message::PathDropped(path) => {
+                if path.is_dir() {
+                    self.pending_selection = None;
+                    self.is_loading = true;
+                    self.update(Message::FolderPicked(Some(path)))
+                } else if path.is_file() && audio::is_supported(&path, &self.settings.extra_extensions()) {
+                    match path.parent().map(|p| p.to_path_buf()) {
+                        Some(folder) => {
+                            self.pending_selection = Some(path);
+                            self.is_loading = true;
+                            self.update(Message::FolderPicked(Some(folder)))
+                        }
+                        None => Task::none(),
+                    }
+                } else {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Unsupported Item",
+                        format!(
+                            "Drop a folder or an audio file ({}).",
+                            self.settings.supported_extensions().join(", ")
+                        )
+                    ));
+                    Task::none()
+                }
             }
             Message::FolderPicked(None) => {
                 self.is_loading = false;
                 Task::none()
             }
-            Message::FilesLoaded(files) => {
+            Message::FilesLoaded(files, errors) => {
+                self.player.stop();
                 self.files = files;
                 self.is_loading = false;
                 self.selected_file_index = None;
+                self.file_list_view = None;
+                self.thumbnails_requested.clear();
+                self.apply_sort();
+                let thumbnails = self.load_visible_thumbnails();
+
+                if !errors.is_empty() {
+                    let details: Vec<String> = errors.iter().take(3).map(|(path, e)| {
+                        format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), e)
+                    }).collect();
+                    let more = if errors.len() > details.len() {
+                        format!("\n...and {} more", errors.len() - details.len())
+                    } else {
+                        String::new()
+                    };
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Error,
+                        "Some Files Failed to Load",
+                        format!("{} files could not be read:\n{}{}", errors.len(), details.join("\n"), more)
+                    ));
+                }
+
+                let garbled = self.files.iter().filter(|f| f.has_suspect_encoding()).count();
+                if garbled > 0 {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Possible Encoding Problems",
+                        format!("{} files have tags that look garbled. Use \"Fix Encoding\" in the editor to re-read them.", garbled)
+                    ));
+                }
+
+                if self.files.is_empty() && errors.is_empty() {
+                    let folder = self.current_dir.as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default();
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "No Audio Files",
+                        format!(
+                            "No supported audio files found in {}. Supported formats: {}",
+                            folder,
+                            self.settings.supported_extensions().join(", ")
+                        )
+                    ));
+                }
+
+                if let Some(path) = self.pending_selection.take() {
+                    if let Some(index) = self.files.iter().position(|f| f.path == path) {
+                        return Task::batch(vec![thumbnails, self.update(Message::FileSelected(index))]);
+                    }
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Warning,
+                        "Selected File Missing",
+                        format!("{} is no longer in the folder.", path.file_name().unwrap_or_default().to_string_lossy())
+                    ));
+                }
+                thumbnails
+            }
+            Message::FileListScrolled(viewport) => {
+                let content_height = viewport.content_bounds().height;
+                if content_height > 0.0 {
+                    let start = viewport.absolute_offset().y / content_height;
+                    let end = start + viewport.bounds().height / content_height;
+                    self.file_list_view = Some((start, end));
+                }
+                self.load_visible_thumbnails()
+            }
+            Message::ThumbnailReady(path, thumbnail) => {
+                if let Some(file) = self.files.iter_mut().find(|f| f.path == path) {
+                    file.thumbnail_data = thumbnail;
+                }
                 Task::none()
             }
             Message::SwitchToEditor => {
                 self.current_page = Page::Editor;
                 Task::none()
             }
+            Message::DismissToast(id) => {
+                self.toast_manager.dismiss(id);
+                Task::none()
+            }
+            Message::ReloadFolder => {
+                if self.has_unsaved_changes() {
+                    self.pending_leave = Some(LeaveAction::ReloadFolder);
+                    Task::none()
+                } else {
+                    self.reload_folder()
+                }
+            }
             Message::SwitchToTitle => {
-                self.current_page = Page::TitleScreen;
+                if self.has_unsaved_changes() {
+                    self.pending_leave = Some(LeaveAction::BackToTitle);
+                } else {
+                    self.current_page = Page::TitleScreen;
+                }
                 Task::none()
             }
             Message::FileSelected(index) => {
-                
-                if self.has_unsaved_changes {
+                // With auto-save off, or held for a cover or a review, the file stays dirty
+                // for Save / Save All
+                if self.settings.auto_save && self.selected_file_dirty() && !self.auto_save_held() {
                     let _ = self.update(Message::SavePressed);
                 }
 
+                self.player.stop();
                 self.selected_file_index = Some(index);
+                self.year_error = None;
                 if let Some(file) = self.files.get(index) {
-                     self.search_query = format!("{} {}", file.artist, file.title).trim().to_string();
+                     self.search_query = search_query_for(file, &self.settings.search_query_template);
                 }
+                self.sync_text_editors();
                 Task::none()
             }
+            Message::SelectPrev | Message::SelectNext => {
+                if self.current_page != Page::Editor || self.modal_open() {
+                    return Task::none();
+                }
+
+                let visible: Vec<usize> = self.files.iter().enumerate()
+                    .filter(|(_, f)| self.matches_file_filter(f))
+                    .map(|(i, _)| i)
+                    .collect();
+                if visible.is_empty() {
+                    return Task::none();
+                }
+
+                let current = self.selected_file_index.and_then(|idx| visible.iter().position(|&i| i == idx));
+                let target = match (current, &message) {
+                    (None, Message::SelectPrev) => visible.len() - 1,
+                    (None, _) => 0,
+                    (Some(pos), Message::SelectPrev) => pos.saturating_sub(1),
+                    (Some(pos), _) => (pos + 1).min(visible.len() - 1),
+                };
+
+                if Some(visible[target]) == self.selected_file_index {
+                    return Task::none();
+                }
+
+                // Goes through FileSelected so the save-before-switch behaviour still applies
+                let select = self.update(Message::FileSelected(visible[target]));
+                let offset = if visible.len() > 1 { target as f32 / (visible.len() - 1) as f32 } else { 0.0 };
+                let scroll = scrollable::snap_to(
+                    scrollable::Id::new(FILE_LIST_ID),
+                    scrollable::RelativeOffset { x: 0.0, y: offset },
+                );
+                Task::batch(vec![select, scroll])
+            }
+            Message::FileFilterChanged(val) => {
+                self.file_filter = val;
+                self.load_visible_thumbnails()
+            }
+            Message::YearMinChanged(val) => {
+                self.year_min = val.chars().filter(char::is_ascii_digit).take(4).collect();
+                self.load_visible_thumbnails()
+            }
+            Message::YearMaxChanged(val) => {
+                self.year_max = val.chars().filter(char::is_ascii_digit).take(4).collect();
+                self.load_visible_thumbnails()
+            }
+            Message::DecadePicked(decade) => {
+                self.year_min = decade.to_string();
+                self.year_max = (decade + 9).to_string();
+                self.load_visible_thumbnails()
+            }
+            Message::ToggleUnknownYear(show) => {
+                self.show_unknown_year = show;
+                self.load_visible_thumbnails()
+            }
+            Message::SortBy(key) => {
+                self.sort_key = key;
+                self.apply_sort();
+                self.load_visible_thumbnails()
+            }
+            Message::ToggleSortDirection => {
+                self.sort_ascending = !self.sort_ascending;
+                self.apply_sort();
+                self.load_visible_thumbnails()
+            }
             Message::TitleChanged(val) => {
                 if let Some(idx) = self.selected_file_index {
                     self.files[idx].title = val;
-                    self.has_unsaved_changes = true;
-                    self.last_edit_time = Some(Instant::now());
+                    self.mark_dirty(idx);
                 }
                 Task::none()
             }
             Message::ArtistChanged(val) => {
                 if let Some(idx) = self.selected_file_index {
                     self.files[idx].artist = val;
-                    self.has_unsaved_changes = true;
-                    self.last_edit_time = Some(Instant::now());
+                    self.mark_dirty(idx);
                 }
                 Task::none()
             }
             Message::AlbumChanged(val) => {
                 if let Some(idx) = self.selected_file_index {
                     self.files[idx].album = val;
-                    self.has_unsaved_changes = true;
-                    self.last_edit_time = Some(Instant::now());
+                    self.mark_dirty(idx);
                 }
                 Task::none()
             }
-            Message::SavePressed => {
+            Message::AlbumArtistChanged(val) => {
                 if let Some(idx) = self.selected_file_index {
-                    let file = &mut self.files[idx];
-                    match file.save() {
-                        Ok(_) => {
-                             self.toast_manager.add(toast::Toast::new(
-                                toast::Status::Success,
-                                "Saved",
-                                "File metadata updated successfully"
-                            ));
-                            self.has_unsaved_changes = false;
-                            self.last_edit_time = None;
-                        }
-                        Err(e) => {
-                             self.toast_manager.add(toast::Toast::new(
-                                toast::Status::Error,
-                                "Save Failed",
-                                e
-                            ));
-                        }
-                    }
+                    self.files[idx].album_artist = val;
+                    self.mark_dirty(idx);
                 }
                 Task::none()
             }
-            Message::BatchTag => {
-                if let Some(path) = &self.current_dir {
-                    if let Some(folder_name) = path.file_name().and_then(|s| s.to_str()) {
-                         self.is_searching = true;
-                         self.is_loading = true;
-                         self.loading_message = "Batch searching metadata...".to_string();
-                         let query = folder_name.to_string();
-                         let settings = self.settings.clone();
-                         
-                         Task::perform(async move {
-                              Ok(api::search_all(query, settings).await)
-                         }, Message::BatchResults)
-                    } else {
-                        Task::none()
-                    }
-                } else {
-                    Task::none()
-                }
-            }
-            Message::BatchResults(Ok(results)) => {
-                self.is_searching = false;
-                self.is_loading = false;
-                if results.is_empty() {
-                     self.toast_manager.add(toast::Toast::new(toast::Status::Info, "Batch Info", "No results found for batch tagging"));
-                } else {
-                     let count = std::cmp::min(self.files.len(), results.len());
-                     for i in 0..count {
-                         self.files[i].title = results[i].title.clone();
-                         self.files[i].artist = results[i].artist.clone();
-                         self.files[i].album = results[i].album.clone();
-                     }
-                      self.toast_manager.add(toast::Toast::new(
-                          toast::Status::Success, 
-                          "Batch Applied", 
-                          format!("Applied metadata to {} files", count)
-                      ));
+            Message::CompilationToggled(val) => {
+                if let Some(idx) = self.selected_file_index {
+                    self.files[idx].compilation = val;
+                    self.mark_dirty(idx);
                 }
                 Task::none()
             }
-            Message::BatchResults(Err(e)) => {
-                self.is_searching = false;
-                self.is_loading = false;
-                self.toast_manager.add(toast::Toast::new(toast::Status::Error, "Batch Error", e));
+            Message::ExplicitToggled(val) => {
+                if let Some(idx) = self.selected_file_index {
+                    self.files[idx].explicit = val.then_some(true);
+                    self.mark_dirty(idx);
+                }
                 Task::none()
             }
-            Message::SearchQueryChanged(query) => {
-                self.search_query = query;
+            Message::GenreChanged(val) => {
+                if let Some(idx) = self.selected_file_index {
+                    self.files[idx].genre = val;
+                    self.mark_dirty(idx);
+                }
                 Task::none()
             }
-            Message::SearchPressed => {
-                if !self.search_query.is_empty() {
-                    self.is_searching = true;
-                    self.search_results.clear();
-                    self.search_images.clear();
-                    let query = self.search_query.clone();
-                    let settings = self.settings.clone();
-                    Task::perform(async move {
-                         api::search_all(query, settings).await.into_iter().map(|r| r).collect::<Vec<_>>()
-                    }, |res| Message::SearchResults(Ok(res)))
-                } else {
-                    Task::none()
+            Message::ComposerChanged(val) => {
+                if let Some(idx) = self.selected_file_index {
+                    self.files[idx].composer = val;
+                    self.mark_dirty(idx);
                 }
+                Task::none()
             }
-            Message::SearchResults(Ok(results)) => {
-                self.is_searching = false;
-                self.search_results = results;
-                self.search_images = vec![None; self.search_results.len()];
-
-                if self.search_results.is_empty() {
-                    self.toast_manager.add(toast::Toast::new(
-                        toast::Status::Info,
-                        "No Results",
-                        "Try a different search term"
-                    ));
-                    Task::none()
-                } else {
-                    let tasks: Vec<Task<Message>> = self.search_results.iter().enumerate().filter_map(|(i, res)| {
-                        res.cover_url.clone().map(|url| {
-                             Task::perform(download_thumbnail(Some(url)), move |res| Message::SearchCoverLoaded(i, res))
-                        })
-                    }).collect();
-                    
-                    Task::batch(tasks)
+            Message::CommentEdited(action) => {
+                if let Some(idx) = self.selected_file_index {
+                    let is_edit = action.is_edit();
+                    self.comment_editor.perform(action);
+                    if is_edit {
+                        self.files[idx].comment = self.comment_editor.text().trim_end_matches('\n').to_string();
+                        self.mark_dirty(idx);
+                    }
                 }
-            }
-            Message::SearchResults(Err(e)) => {
-                self.is_searching = false;
-                self.toast_manager.add(toast::Toast::new(
-                     toast::Status::Error,
-                     "Search Error",
-                     e
-                ));
                 Task::none()
             }
-            Message::SearchCoverLoaded(index, Ok(bytes)) => {
-                if index < self.search_images.len() {
-                    self.search_images[index] = Some(bytes);
+            Message::LyricsEdited(action) => {
+                if let Some(idx) = self.selected_file_index {
+                    let is_edit = action.is_edit();
+                    self.lyrics_editor.perform(action);
+                    if is_edit {
+                        self.files[idx].lyrics = self.lyrics_editor.text().trim_end_matches('\n').to_string();
+                        self.mark_dirty(idx);
+                    }
                 }
                 Task::none()
             }
-            Message::SearchCoverLoaded(_, Err(_)) => {  
+            Message::BpmChanged(val) => {
+                if let Some(idx) = self.selected_file_index {
+                    let trimmed = val.trim();
+                    let bpm = if trimmed.is_empty() {
+                        None
+                    } else if let Ok(bpm) = trimmed.parse::<u32>() {
+                        Some(bpm)
+                    } else {
+                        return Task::none();
+                    };
+                    self.files[idx].bpm = bpm;
+                    self.mark_dirty(idx);
+                }
                 Task::none()
             }
-            Message::ToggleSettings => {
-                self.show_settings = !self.show_settings;
+            Message::DetectBpm => {
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get(idx)) {
+                    let path = file.path.clone();
+                    self.is_loading = true;
+                    self.loading_message = "Detecting BPM...".to_string();
+                    return Task::perform(detect_bpm(path.clone()), move |res| Message::BpmDetected(path.clone(), res));
+                }
                 Task::none()
             }
-            Message::SettingsChanged(settings) => {
-                self.settings = settings;
+            Message::BpmDetected(path, Ok(bpm)) => {
+                self.is_loading = false;
+                if let Some(idx) = self.files.iter().position(|f| f.path == path) {
+                    self.files[idx].bpm = Some(bpm);
+                    self.mark_dirty(idx);
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Success,
+                        "BPM Detected",
+                        format!("Estimated tempo: {} BPM", bpm)
+                    ));
+                }
                 Task::none()
             }
-            Message::SaveSettings => {
-                self.settings.save();
-                self.show_settings = false;
+            Message::BpmDetected(_, Err(e)) => {
+                self.is_loading = false;
                 self.toast_manager.add(toast::Toast::new(
-                    toast::Status::Success,
-                    "Settings Saved",
-                    "Configuration updated"
+                    toast::Status::Error,
+                    "BPM Detection Failed",
+                    e
                 ));
                 Task::none()
             }
-            Message::SpotifyIdChanged(val) => {
-                self.settings.spotify_id = val;
-                Task::none()
-            }
-            Message::SpotifySecretChanged(val) => {
-                self.settings.spotify_secret = val;
+            Message::YearChanged(val) => {
+                if let Some(idx) = self.selected_file_index {
+                    let trimmed = val.trim();
+                    if trimmed.is_empty() {
+                        self.files[idx].year = None;
+                    } else if let Ok(year) = trimmed.parse::<u32>() {
+                        self.files[idx].year = Some(year);
+                    } else {
+                        self.year_error = Some("Year must be a number".to_string());
+                        return Task::none();
+                    }
+                    self.year_error = None;
+                    self.mark_dirty(idx);
+                }
                 Task::none()
             }
-            Message::ToggleSpotify(val) => {
+            Message::GuessFromFilename => {
+                if let Some(idx) = self.selected_file_index {
+                    if self.files[idx].guess_from_filename() {
+                        self.mark_dirty(idx);
+                    } else {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Info,
+                            "Nothing to Guess",
+                            "No Unknown fields could be filled from the filename"
+                        ));
+                    }
+                }
+                Task::none()
+            }
+            Message::GuessFromFolder => {
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get_mut(idx)) {
+                    // The filename is the more specific source, so it goes first
+                    let from_filename = file.guess_from_filename();
+                    let from_folders = file.guess_from_folders();
+                    if from_filename || from_folders {
+                        file.dirty = true;
+                        self.review_pending = Some(file.path.clone());
+                    } else {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Info,
+                            "Nothing to Guess",
+                            "No Unknown fields could be filled from the filename or folders"
+                        ));
+                    }
+                }
+                Task::none()
+            }
+            Message::NormalizeCasing(style, target) => {
+                let indices: Vec<usize> = match target {
+                    NormalizeTarget::Selected => self.selected_file_index.into_iter().collect(),
+                    NormalizeTarget::Shown => (0..self.files.len()).filter(|&i| self.matches_file_filter(&self.files[i])).collect(),
+                };
+                // Only marks files dirty; nothing is written until they are saved
+                let mut changed = 0;
+                for idx in indices {
+                    if self.files[idx].normalize_text(style) {
+                        self.mark_dirty(idx);
+                        changed += 1;
+                    }
+                }
+                if changed == 0 {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Nothing to Change",
+                        format!("{} made no changes", style)
+                    ));
+                } else if matches!(target, NormalizeTarget::Shown) {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Success,
+                        style.to_string(),
+                        format!("Updated {} files. Save to keep the changes.", changed)
+                    ));
+                }
+                Task::none()
+            }
+            Message::FixEncoding => {
+                if let Some(idx) = self.selected_file_index {
+                    if self.files[idx].fix_encoding(self.settings.legacy_encoding.encoding()) {
+                        self.mark_dirty(idx);
+                        self.sync_text_editors();
+                    } else {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Info,
+                            "Nothing to Fix",
+                            "No tags could be re-read in another encoding"
+                        ));
+                    }
+                }
+                Task::none()
+            }
+            Message::ToggleAdvancedTags => {
+                self.show_advanced_tags = !self.show_advanced_tags && self.selected_file_index.is_some();
+                self.advanced_tag_error = None;
+                Task::none()
+            }
+            Message::AdvancedTagKeyChanged(row, key) => {
+                if let Some(idx) = self.selected_file_index {
+                    let file = &mut self.files[idx];
+                    // Title, artist, album etc. are only editable in the main form
+                    if audio::is_managed_key_name(file.tag_type, &key) {
+                        self.advanced_tag_error = Some(format!("\"{}\" is edited in the main form", key.trim()));
+                        return Task::none();
+                    }
+                    if let Some(entry) = file.extra_tags.get_mut(row) {
+                        let previous = std::mem::replace(&mut entry.0, key);
+                        if !previous.trim().is_empty() && !file.removed_tags.contains(&previous) {
+                            file.removed_tags.push(previous);
+                        }
+                        self.advanced_tag_error = None;
+                        self.mark_dirty(idx);
+                    }
+                }
+                Task::none()
+            }
+            Message::AdvancedTagValueChanged(row, value) => {
+                if let Some(idx) = self.selected_file_index {
+                    if let Some(entry) = self.files[idx].extra_tags.get_mut(row) {
+                        entry.1 = value;
+                        self.mark_dirty(idx);
+                    }
+                }
+                Task::none()
+            }
+            Message::AddAdvancedTag => {
+                if let Some(idx) = self.selected_file_index {
+                    self.files[idx].extra_tags.push((String::new(), String::new()));
+                }
+                Task::none()
+            }
+            Message::RemoveAdvancedTag(row) => {
+                if let Some(idx) = self.selected_file_index {
+                    let file = &mut self.files[idx];
+                    if row < file.extra_tags.len() {
+                        let (key, _) = file.extra_tags.remove(row);
+                        if !key.trim().is_empty() && !file.removed_tags.contains(&key) {
+                            file.removed_tags.push(key);
+                        }
+                        self.mark_dirty(idx);
+                    }
+                }
+                Task::none()
+            }
+            Message::IdentifyByAudio => {
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get(idx)) {
+                    let path = file.path.clone();
+                    let key = self.settings.acoustid_key.clone();
+                    self.is_searching = true;
+                    return Task::perform(
+                        async move {
+                            let result = api::acoustid::identify(&path, &key).await;
+                            (path, result)
+                        },
+                        |(path, result)| Message::AudioIdentified(path, result)
+                    );
+                }
+                Task::none()
+            }
+            Message::AudioIdentified(path, result) => {
+                self.is_searching = false;
+                let still_selected = self.selected_file_index
+                    .and_then(|idx| self.files.get(idx))
+                    .is_some_and(|f| f.path == path);
+
+                match result {
+                    Ok(meta) if still_selected => {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Success,
+                            "Identified",
+                            format!("{} - {}", meta.artist, meta.title)
+                        ));
+                        self.update(Message::ApplyMetadata(meta))
+                    }
+                    Ok(_) => Task::none(),
+                    Err(e) => {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Error,
+                            "Identify Failed",
+                            e
+                        ));
+                        Task::none()
+                    }
+                }
+            }
+            Message::ClearTagsRequested => {
+                self.show_clear_tags_confirmation = self.selected_file_index.is_some();
+                Task::none()
+            }
+            Message::CancelClearTags => {
+                self.show_clear_tags_confirmation = false;
+                Task::none()
+            }
+            Message::ClearTags => {
+                self.show_clear_tags_confirmation = false;
+                if let Some(idx) = self.selected_file_index {
+                    match self.files[idx].clear_tags() {
+                        Ok(()) => {
+                            self.sync_text_editors();
+                            self.year_error = None;
+                            self.last_edit_time = None;
+                            self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Success,
+                                "Tags Cleared",
+                                "Removed all tags and artwork from the file"
+                            ));
+                        }
+                        Err(e) => {
+                            self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Error,
+                                "Clear Failed",
+                                e
+                            ));
+                        }
+                    }
+                }
+                Task::none()
+            }
+            Message::RevertFile => {
+                if let Some(idx) = self.selected_file_index {
+                    let path = self.files[idx].path.clone();
+                    match audio::AudioFile::load(path, self.settings.value_separator) {
+                        Ok(file) => {
+                            self.files[idx] = file;
+                            self.year_error = None;
+                            self.last_edit_time = None;
+                            self.sync_text_editors();
+                            self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Info,
+                                "Reverted",
+                                "Discarded unsaved edits for this file"
+                            ));
+                            return self.refresh_thumbnail(idx);
+                        }
+                        Err(e) => {
+                            self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Error,
+                                "Revert Failed",
+                                format!("Could not reload the file from disk: {}", e)
+                            ));
+                        }
+                    }
+                }
+                Task::none()
+            }
+            Message::SavePressed => {
+                if let Some(idx) = self.selected_file_index {
+                    let file = &mut self.files[idx];
+                    if file.is_read_only() {
+                        self.read_only_pending = vec![file.path.clone()];
+                        return Task::none();
+                    }
+                    match file.save(audio::SaveOptions::from_settings(&self.settings)) {
+                        Ok(_) => {
+                             file.dirty = false;
+                             self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Success,
+                                "Saved",
+                                "File metadata updated successfully"
+                            ));
+                            self.last_edit_time = None;
+                            self.review_pending = None;
+                        }
+                        Err(e) => {
+                             self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Error,
+                                "Save Failed",
+                                e
+                            ));
+                        }
+                    }
+                }
+                Task::none()
+            }
+            Message::SaveAsCopy => {
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get(idx)) {
+                    let source = file.path.clone();
+                    return Task::perform(pick_copy_path(source.clone()), move |target| Message::CopyTargetPicked(source.clone(), target));
+                }
+                Task::none()
+            }
+            Message::CopyTargetPicked(source, Some(target)) => {
+                if let Some(file) = self.files.iter().find(|f| f.path == source) {
+                    match file.save_copy(&target, audio::SaveOptions::from_settings(&self.settings)) {
+                        Ok(()) => {
+                            self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Success,
+                                "Copy Saved",
+                                format!("Wrote tagged copy to {}", target.display())
+                            ));
+                        }
+                        Err(e) => {
+                            self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Error,
+                                "Save As Copy Failed",
+                                e
+                            ));
+                        }
+                    }
+                }
+                Task::none()
+            }
+            Message::CopyTargetPicked(_, None) => Task::none(),
+            Message::Play => {
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get(idx)) {
+                    self.player.play(file.path.clone());
+                }
+                Task::none()
+            }
+            Message::Pause => {
+                self.player.pause();
+                Task::none()
+            }
+            Message::Stop => {
+                self.player.stop();
+                Task::none()
+            }
+            Message::PlaybackTick(_) => {
+                // Nothing to update; the tick only triggers a redraw of the elapsed time
+                Task::none()
+            }
+            Message::BatchTag => {
+                if let Some(path) = &self.current_dir {
+                    if let Some(folder_name) = path.file_name().and_then(|s| s.to_str()) {
+                         self.is_searching = true;
+                         self.is_loading = true;
+                         self.loading_message = "Batch searching metadata...".to_string();
+                         self.batch_job = Some(BatchJob::new(1));
+                         let query = folder_name.to_string();
+                         self.warn_unconfigured_providers();
+                         let settings = self.settings.clone();
+                         
+                         Task::perform(api::search_all(query, settings), Message::BatchResults)
+                    } else {
+                        Task::none()
+                    }
+                } else {
+                    Task::none()
+                }
+            }
+            Message::BatchResults(outcome) => {
+                self.is_searching = false;
+                self.is_loading = false;
+                if self.batch_job.take().is_some_and(|job| job.is_cancelled()) {
+                     self.toast_manager.add(toast::Toast::new(toast::Status::Info, "Batch Cancelled", "Batch tagging was stopped; no results were applied."));
+                     return Task::none();
+                }
+                self.report_search_errors(&outcome);
+                let results = outcome.results;
+                if results.is_empty() {
+                     self.toast_manager.add(toast::Toast::new(toast::Status::Info, "Batch Info", "No results found for batch tagging"));
+                } else {
+                     // Nothing is written yet; the user reviews the proposals first
+                     self.batch_results = results;
+                     self.pair_batch();
+                }
+                Task::none()
+            }
+            Message::BatchMappingChanged(mapping) => {
+                self.batch_mapping = mapping;
+                self.pair_batch();
+                Task::none()
+            }
+            Message::ToggleBatchRow(row, selected) => {
+                if let Some(proposal) = self.pending_batch.get_mut(row) {
+                    proposal.selected = selected;
+                }
+                Task::none()
+            }
+            Message::ApplyBatchSelection => {
+                let mut count = 0;
+                self.batch_results.clear();
+                for proposal in std::mem::take(&mut self.pending_batch).into_iter().filter(|p| p.selected) {
+                    if let Some(file) = self.files.iter_mut().find(|f| f.path == proposal.path) {
+                        apply_optional_fields(file, &proposal.result, &self.settings);
+                        file.title = proposal.result.title;
+                        file.artist = proposal.result.artist;
+                        file.album = proposal.result.album;
+                        if let Some(year) = proposal.result.year {
+                            file.year = Some(year);
+                        }
+                        if let Some(track) = proposal.result.track {
+                            file.track = Some(track);
+                        }
+                        file.dirty = true;
+                        count += 1;
+                    }
+                }
+                self.toast_manager.add(toast::Toast::new(
+                    toast::Status::Success,
+                    "Batch Applied",
+                    match self.batch_skipped {
+                        0 => format!("Applied metadata to {} files", count),
+                        skipped => format!("Applied metadata to {} files; skipped {} already complete", count, skipped),
+                    }
+                ));
+                Task::none()
+            }
+            Message::DiscardBatch => {
+                self.pending_batch.clear();
+                self.batch_results.clear();
+                Task::none()
+            }
+            Message::FetchAllCovers => {
+                let jobs: Vec<(PathBuf, String)> = self.files.iter()
+                    .filter(|f| f.picture_data.is_none())
+                    .map(|f| (f.path.clone(), search_query_for(f, &self.settings.search_query_template)))
+                    .collect();
+                self.batch_skipped = self.files.len() - jobs.len();
+
+                if jobs.is_empty() {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Covers",
+                        "Every file already has cover art"
+                    ));
+                    return Task::none();
+                }
+
+                self.is_loading = true;
+                self.loading_message = "Fetching covers...".to_string();
+                let job = BatchJob::new(jobs.len());
+                self.batch_job = Some(job.clone());
+                self.warn_unconfigured_providers();
+                Task::perform(fetch_covers(jobs, self.settings.clone(), job), Message::CoversFetched)
+            }
+            Message::CoversFetched(results) => {
+                self.is_loading = false;
+                let job = self.batch_job.take();
+                let total = job.as_ref().map(|j| j.total).unwrap_or(results.len());
+                let cancelled = job.is_some_and(|j| j.is_cancelled());
+                let processed = results.len();
+                let mut applied = 0;
+                let mut thumbnails = Vec::new();
+
+                for (path, cover) in results {
+                    if let (Some(bytes), Some(idx)) = (cover, self.files.iter().position(|f| f.path == path)) {
+                        self.files[idx].picture_data = Some(bytes);
+                        self.files[idx].dirty = true;
+                        thumbnails.push(self.refresh_thumbnail(idx));
+                        applied += 1;
+                    }
+                }
+                if cancelled {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Cover Fetch Cancelled",
+                        format!("Stopped after {} of {} files; {} covers applied. Use Save All to write them.", processed, total, applied)
+                    ));
+                } else {
+                    self.toast_manager.add(toast::Toast::new(
+                        if applied > 0 { toast::Status::Success } else { toast::Status::Info },
+                        "Covers Fetched",
+                        format!(
+                            "Applied covers to {} of {} files{}. Use Save All to write them.",
+                            applied,
+                            total,
+                            match self.batch_skipped {
+                                0 => String::new(),
+                                skipped => format!("; skipped {} that already had one", skipped),
+                            }
+                        )
+                    ));
+                }
+                Task::batch(thumbnails)
+            }
+            Message::SearchQueryChanged(query) => {
+                self.search_query = query;
+                Task::none()
+            }
+            Message::SearchPressed if self.album_mode => {
+                if self.search_query.is_empty() {
+                    return Task::none();
+                }
+                self.is_searching = true;
+                self.album_results.clear();
+                self.warn_unconfigured_providers();
+                Task::perform(
+                    api::search_albums(self.search_query.clone(), self.settings.clone()),
+                    Message::AlbumResults
+                )
+            }
+            Message::SearchPressed => {
+                if !self.search_query.is_empty() {
+                    self.warn_unconfigured_providers();
+                    let key = normalize_query(&self.search_query);
+                    self.search_cache.retain(|_, (stored, _)| stored.elapsed() < SEARCH_CACHE_TTL);
+                    if let Some((_, results)) = self.search_cache.get(&key) {
+                        let outcome = api::SearchOutcome { results: results.clone(), errors: Vec::new() };
+                        let task = self.update(Message::SearchResults(key, outcome));
+                        self.search_from_cache = true;
+                        return task;
+                    }
+
+                    self.is_searching = true;
+                    self.search_results.clear();
+                    self.search_images.clear();
+                    let query = self.search_query.clone();
+                    let settings = self.settings.clone();
+                    Task::perform(api::search_all(query, settings), move |outcome| Message::SearchResults(key.clone(), outcome))
+                } else {
+                    Task::none()
+                }
+            }
+            Message::FocusSearch => {
+                let id = text_input::Id::new(SEARCH_INPUT_ID);
+                Task::batch(vec![text_input::focus(id.clone()), text_input::select_all(id)])
+            }
+            Message::RefreshSearch => {
+                self.search_cache.remove(&normalize_query(&self.search_query));
+                self.update(Message::SearchPressed)
+            }
+            Message::SearchResults(key, outcome) => {
+                self.is_searching = false;
+                self.search_from_cache = false;
+                // Partial results would hide a provider until the entry expires, so skip those
+                if outcome.errors.is_empty() {
+                    self.search_cache.entry(key).or_insert_with(|| (Instant::now(), outcome.results.clone()));
+                }
+                self.report_search_errors(&outcome);
+                self.search_results = outcome.results;
+                self.search_images = self.search_results.iter()
+                    .map(|res| if res.cover_urls.is_empty() { Thumbnail::Missing } else { Thumbnail::Loading })
+                    .collect();
+
+                if self.search_results.is_empty() && outcome.errors.is_empty() {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "No Results",
+                        "Try a different search term"
+                    ));
+                    Task::none()
+                } else {
+                    let tasks: Vec<Task<Message>> = self.search_results.iter().enumerate().filter_map(|(i, res)| {
+                        res.cover_url().map(|url| {
+                             Task::perform(download_thumbnail(Some(url.to_string()), self.settings.thumbnail_fit), move |res| Message::SearchCoverLoaded(i, res))
+                        })
+                    }).collect();
+                    
+                    Task::batch(tasks)
+                }
+            }
+            Message::ToggleSourceFilter(source) => {
+                if !self.hidden_sources.remove(&source) {
+                    self.hidden_sources.insert(source);
+                }
+                Task::none()
+            }
+            Message::SearchCoverLoaded(index, result) => {
+                if let Some(slot) = self.search_images.get_mut(index) {
+                    *slot = match result {
+                        Ok(bytes) => Thumbnail::Loaded(bytes),
+                        Err(_) => Thumbnail::Failed,
+                    };
+                }
+                Task::none()
+            }
+            Message::OfflineToggled(offline) => {
+                self.persist_setting(|stored| stored.offline = offline);
+                self.settings.offline = offline;
+                api::set_offline(offline);
+                Task::none()
+            }
+            Message::ToggleSettings => {
+                self.show_settings = !self.show_settings;
+                Task::none()
+            }
+            Message::SettingsChanged(settings) => {
+                let refit = settings.thumbnail_fit != self.settings.thumbnail_fit;
+                self.settings = *settings;
+                if refit {
+                    // Redraw the list thumbnails so the new fit shows right away
+                    for file in &mut self.files {
+                        file.thumbnail_data = None;
+                    }
+                    self.thumbnails_requested.clear();
+                    return self.load_visible_thumbnails();
+                }
+                Task::none()
+            }
+            Message::MoveProvider(index, up) => {
+                self.settings.move_provider(index, up);
+                Task::none()
+            }
+            Message::SaveSettings => {
+                if let Err(e) = api::configure_proxy(&self.settings.http_proxy, &self.settings.https_proxy) {
+                    self.toast_manager.add(toast::Toast::new(toast::Status::Error, "Invalid Proxy", e));
+                    return Task::none();
+                }
+                if let Some(geometry) = self.window_geometry {
+                    self.settings.window = Some(geometry);
+                }
+                self.settings.update_active_profile();
+                let warning = self.settings.save();
+                // Provider and limit changes would make the cached results misleading
+                self.search_cache.clear();
+                set_download_limit(self.settings.concurrent_downloads());
+                api::set_offline(self.settings.offline);
+                self.toast_manager.configure(self.settings.toast_duration(), self.settings.sticky_error_toasts);
+                self.show_settings = false;
+                self.toast_manager.add(toast::Toast::new(
+                    toast::Status::Success,
+                    "Settings Saved",
+                    "Configuration updated"
+                ));
+                if let Some(warning) = warning {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Warning,
+                        "Secrets Stored in Plaintext",
+                        warning
+                    ));
+                }
+                Task::none()
+            }
+            Message::TestConnection(provider) => {
+                Task::perform(
+                    api::test_connection(provider, self.settings.clone()),
+                    move |result| Message::ConnectionTested(provider, result)
+                )
+            }
+            Message::ConnectionTested(provider, Ok(())) => {
+                self.toast_manager.add(toast::Toast::new(
+                    toast::Status::Success,
+                    "Connection OK",
+                    format!("Connected to {} successfully", provider)
+                ));
+                Task::none()
+            }
+            Message::ConnectionTested(provider, Err(e)) => {
+                self.toast_manager.add(toast::Toast::new(
+                    toast::Status::Error,
+                    "Connection Failed",
+                    format!("{}: {}", provider, e)
+                ));
+                Task::none()
+            }
+            Message::ProfileSelected(name) => {
+                let dir = self.current_dir.clone();
+                self.persist_setting(|stored| {
+                    if stored.activate_profile(&name) {
+                        if let Some(dir) = &dir {
+                            stored.remember_folder_profile(dir);
+                        }
+                    }
+                });
+                if self.settings.activate_profile(&name) {
+                    if let Some(dir) = &self.current_dir {
+                        self.settings.remember_folder_profile(dir);
+                    }
+                    // Different providers would make the cached results misleading
+                    self.search_cache.clear();
+                }
+                Task::none()
+            }
+            Message::NewProfileNameChanged(name) => {
+                self.new_profile_name = name;
+                Task::none()
+            }
+            Message::SaveProfileAs => {
+                let name = self.new_profile_name.trim().to_string();
+                if !name.is_empty() {
+                    self.settings.save_profile_as(&name);
+                    self.new_profile_name.clear();
+                }
+                Task::none()
+            }
+            Message::DeleteProfile => {
+                let name = self.settings.active_profile.clone();
+                if let Err(e) = self.settings.delete_profile(&name) {
+                    self.toast_manager.add(toast::Toast::new(toast::Status::Warning, "Profile Kept", e));
+                }
+                Task::none()
+            }
+            Message::ResetProvider(provider) => {
+                api::reset_provider_state(provider);
+                // Cached searches may hold results fetched with the old credentials
+                self.search_cache.clear();
+                self.toast_manager.add(toast::Toast::new(
+                    toast::Status::Info,
+                    "Provider Reset",
+                    format!("{} will reconnect with the current settings on the next search", provider)
+                ));
+                Task::none()
+            }
+            Message::ToggleStats => {
+                self.show_stats = !self.show_stats;
+                Task::none()
+            }
+            Message::FindDuplicates => {
+                self.duplicate_groups = duplicates::find_duplicates(&self.files);
+                self.duplicates_selected.clear();
+                if self.duplicate_groups.is_empty() {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "No Duplicates",
+                        "No duplicate tracks found in this folder"
+                    ));
+                } else {
+                    self.show_duplicates = true;
+                }
+                Task::none()
+            }
+            Message::ToggleDuplicate(path, selected) => {
+                if selected {
+                    self.duplicates_selected.insert(path);
+                } else {
+                    self.duplicates_selected.remove(&path);
+                }
+                Task::none()
+            }
+            Message::DeleteDuplicatesRequested => {
+                self.confirm_delete_duplicates = !self.duplicates_selected.is_empty();
+                Task::none()
+            }
+            Message::CancelDeleteDuplicates => {
+                self.confirm_delete_duplicates = false;
+                Task::none()
+            }
+            Message::ConfirmDeleteDuplicates => {
+                self.confirm_delete_duplicates = false;
+                let selected = std::mem::take(&mut self.duplicates_selected);
+                let mut removed = HashSet::new();
+                let mut failed = Vec::new();
+                for path in selected {
+                    match std::fs::remove_file(&path) {
+                        Ok(()) => {
+                            removed.insert(path);
+                        }
+                        Err(e) => failed.push(format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), e)),
+                    }
+                }
+                self.finish_duplicate_action("Deleted", removed, failed);
+                Task::none()
+            }
+            Message::MoveDuplicates => {
+                if self.duplicates_selected.is_empty() {
+                    return Task::none();
+                }
+                Task::perform(pick_folder(), Message::DuplicatesTargetPicked)
+            }
+            Message::DuplicatesTargetPicked(Some(target)) => {
+                let selected = std::mem::take(&mut self.duplicates_selected);
+                self.is_loading = true;
+                self.loading_message = format!("Moving {} file(s)...", selected.len());
+                Task::perform(move_duplicates(selected, target), |(moved, failed)| Message::DuplicatesMoved(moved, failed))
+            }
+            Message::DuplicatesTargetPicked(None) => Task::none(),
+            Message::DuplicatesMoved(moved, failed) => {
+                self.is_loading = false;
+                self.finish_duplicate_action("Moved", moved, failed);
+                Task::none()
+            }
+            Message::CloseDuplicates => {
+                self.show_duplicates = false;
+                self.duplicate_groups.clear();
+                self.duplicates_selected.clear();
+                Task::none()
+            }
+            Message::OrganizeLibrary => {
+                if self.files.is_empty() {
+                    return Task::none();
+                }
+                Task::perform(pick_folder(), Message::OrganizeRootPicked)
+            }
+            Message::OrganizeRootPicked(Some(root)) => {
+                let moves = organizer::plan(&self.files, &root, &self.settings.organize_template);
+                self.organize_plan = Some((root, moves));
+                Task::none()
+            }
+            Message::OrganizeRootPicked(None) => Task::none(),
+            Message::OrganizeTemplateChanged(template) => {
+                self.settings.organize_template = template;
+                if let Some((root, moves)) = &mut self.organize_plan {
+                    *moves = organizer::plan(&self.files, root, &self.settings.organize_template);
+                }
+                Task::none()
+            }
+            Message::CancelOrganize => {
+                self.organize_plan = None;
+                Task::none()
+            }
+            Message::ConfirmOrganize => {
+                let Some((root, moves)) = self.organize_plan.take() else {
+                    return Task::none();
+                };
+                let template = self.settings.organize_template.clone();
+                self.persist_setting(|stored| stored.organize_template = template);
+
+                let selected_path = self.selected_file_index.and_then(|idx| self.files.get(idx)).map(|f| f.path.clone());
+                if selected_path.as_ref().is_some_and(|p| moves.iter().any(|m| &m.from == p)) {
+                    self.player.stop();
+                }
+
+                self.is_loading = true;
+                self.loading_message = format!("Moving {} file(s)...", moves.len());
+                Task::perform(organize_files(moves), move |results| Message::OrganizeFinished(root.clone(), results))
+            }
+            Message::OrganizeFinished(root, results) => {
+                self.is_loading = false;
+                let mut moved = 0;
+                let mut failed = Vec::new();
+                for (planned, result) in &results {
+                    match result {
+                        Ok(()) => {
+                            if let Some(file) = self.files.iter_mut().find(|f| f.path == planned.from) {
+                                file.path = planned.to.clone();
+                            }
+                            moved += 1;
+                        }
+                        Err(e) => failed.push(format!("{}: {}", planned.from.file_name().unwrap_or_default().to_string_lossy(), e)),
+                    }
+                }
+
+                if moved > 0 {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Success,
+                        "Files Organized",
+                        format!("Moved {} file(s) into {}", moved, root.display())
+                    ));
+                }
+                if !failed.is_empty() {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Error,
+                        "Some Files Failed",
+                        failed.join("\n")
+                    ));
+                }
+                Task::none()
+            }
+            Message::ClearCoverCache => {
+                match cover_cache::clear() {
+                    Ok(count) => {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Success,
+                            "Cache Cleared",
+                            format!("Removed {} cached covers", count)
+                        ));
+                    }
+                    Err(e) => {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Error,
+                            "Cache Error",
+                            e
+                        ));
+                    }
+                }
+                Task::none()
+            }
+            Message::SpotifyIdChanged(val) => {
+                self.settings.spotify_id = val;
+                Task::none()
+            }
+            Message::SpotifySecretChanged(val) => {
+                self.settings.spotify_secret = val;
+                Task::none()
+            }
+            Message::ToggleSpotify(val) => {
                 self.settings.enable_spotify = val;
                 Task::none()
             }
-            Message::ApplyMetadata(meta) => {
-                if let Some(idx) = self.selected_file_index {
-                    self.files[idx].title = meta.title;
-                    self.files[idx].artist = meta.artist;
-                    self.files[idx].album = meta.album;
-                    
-                    return Task::perform(download_image(meta.cover_url), Message::CoverDownloaded);
+            Message::ApplyMetadata(meta) => {
+                let Some(path) = self.selected_file_index.and_then(|idx| self.files.get(idx)).map(|f| f.path.clone()) else {
+                    return Task::none();
+                };
+                let cover_url = meta.cover_urls.first().cloned();
+                self.cover_pending = Some(path.clone());
+                let _ = self.update(Message::ApplyTextOnly(meta));
+                Task::perform(download_image(cover_url), move |result| Message::CoverDownloaded(path.clone(), result))
+            }
+            Message::ApplyTextOnly(meta) => {
+                if let Some(idx) = self.selected_file_index {
+                    apply_optional_fields(&mut self.files[idx], &meta, &self.settings);
+                    self.files[idx].title = meta.title;
+                    self.files[idx].artist = meta.artist;
+                    self.files[idx].album = meta.album;
+                    if let Some(year) = meta.year {
+                        self.files[idx].year = Some(year);
+                    }
+                    if let Some(track) = meta.track {
+                        self.files[idx].track = Some(track);
+                    }
+                    self.mark_dirty(idx);
+                }
+                Task::none()
+            }
+            Message::CompareResult(result) => {
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get(idx)) {
+                    let mask = FieldMask::differences(file, &result);
+                    self.compare = Some((result, mask));
+                }
+                Task::none()
+            }
+            Message::CompareMaskChanged(mask) => {
+                if let Some((_, current)) = &mut self.compare {
+                    *current = mask;
+                }
+                Task::none()
+            }
+            Message::CloseCompare => {
+                self.compare = None;
+                Task::none()
+            }
+            Message::ApplyFields(result, mask) => {
+                self.compare = None;
+                let Some(idx) = self.selected_file_index.filter(|&idx| idx < self.files.len()) else {
+                    return Task::none();
+                };
+                let file = &mut self.files[idx];
+                if mask.title {
+                    file.title = result.title;
+                }
+                if mask.artist {
+                    file.artist = result.artist;
+                }
+                if mask.album {
+                    file.album = result.album;
+                }
+                if mask.year && result.year.is_some() {
+                    file.year = result.year;
+                }
+                if mask.track && result.track.is_some() {
+                    file.track = result.track;
+                }
+                if mask.title || mask.artist || mask.album || mask.year || mask.track {
+                    self.mark_dirty(idx);
+                }
+
+                if mask.cover {
+                    let path = self.files[idx].path.clone();
+                    self.cover_pending = Some(path.clone());
+                    return Task::perform(download_image(result.cover_urls.into_iter().next()), move |result| Message::CoverDownloaded(path.clone(), result));
+                }
+                Task::none()
+            }
+            Message::StageMetadata(meta) => {
+                // Fills the editor only: no cover download, and auto-save waits for a real edit
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get_mut(idx)) {
+                    apply_optional_fields(file, &meta, &self.settings);
+                    file.title = meta.title;
+                    file.artist = meta.artist;
+                    file.album = meta.album;
+                    if let Some(year) = meta.year {
+                        file.year = Some(year);
+                    }
+                    if let Some(track) = meta.track {
+                        file.track = Some(track);
+                    }
+                    file.dirty = true;
+                    self.last_edit_time = None;
+                }
+                Task::none()
+            }
+            Message::ToggleAlbumMode(enabled) => {
+                self.album_mode = enabled;
+                Task::none()
+            }
+            Message::AlbumResults(outcome) => {
+                self.is_searching = false;
+                self.report_search_errors(&outcome);
+                self.album_results = outcome.results;
+                if self.album_results.is_empty() && outcome.errors.is_empty() {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "No Albums Found",
+                        "Album search uses Apple Music and Spotify. Try a different search term."
+                    ));
+                }
+                Task::none()
+            }
+            Message::ApplyAlbum(index) => {
+                let Some(album) = self.album_results.get(index) else {
+                    return Task::none();
+                };
+                if self.files.is_empty() {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "No Files",
+                        "Open a folder before applying an album."
+                    ));
+                    return Task::none();
+                }
+
+                // Goes through the same review as batch tagging before anything is written
+                self.batch_results = album.tracks.clone();
+                self.pair_batch();
+                Task::none()
+            }
+            Message::SwapArtistTitle => {
+                self.swap_suggestion = None;
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get_mut(idx)) {
+                    std::mem::swap(&mut file.artist, &mut file.title);
+                    file.dirty = true;
+                    self.review_pending = Some(file.path.clone());
+                }
+                Task::none()
+            }
+            Message::CheckSwappedFields => {
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get(idx)) {
+                    let path = file.path.clone();
+                    let query = search_query_for(file, &self.settings.search_query_template);
+                    self.is_searching = true;
+                    self.warn_unconfigured_providers();
+                    return Task::perform(
+                        api::search_all(query, self.settings.clone()),
+                        move |outcome| Message::SwapCheckResults(path.clone(), outcome)
+                    );
+                }
+                Task::none()
+            }
+            Message::SwapCheckResults(path, outcome) => {
+                self.is_searching = false;
+                self.report_search_errors(&outcome);
+                // Ignore results that arrive after the user moved to another file
+                let Some(file) = self.selected_file_index.and_then(|idx| self.files.get(idx)).filter(|f| f.path == path) else {
+                    return Task::none();
+                };
+
+                match matching::swapped_match(file, &outcome.results) {
+                    Some(result) => self.swap_suggestion = Some((path, result.clone())),
+                    None if outcome.results.is_empty() => {}
+                    None => self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Fields Look Right",
+                        "The search results don't suggest that artist and title are swapped."
+                    )),
+                }
+                Task::none()
+            }
+            Message::DismissSwapSuggestion => {
+                self.swap_suggestion = None;
+                Task::none()
+            }
+            Message::AutoApplyBest => {
+                if let Some(file) = self.selected_file_index.and_then(|idx| self.files.get(idx)) {
+                    let path = file.path.clone();
+                    let query = search_query_for(file, &self.settings.search_query_template);
+                    self.is_searching = true;
+                    self.warn_unconfigured_providers();
+                    return Task::perform(
+                        api::search_all(query, self.settings.clone()),
+                        move |results| Message::AutoApplyResults(path.clone(), results)
+                    );
+                }
+                Task::none()
+            }
+            Message::AutoApplyResults(path, outcome) => {
+                self.is_searching = false;
+                self.report_search_errors(&outcome);
+                let results = outcome.results;
+                // Ignore results that arrive after the user moved to another file
+                let Some(file) = self.selected_file_index.and_then(|idx| self.files.get(idx)).filter(|f| f.path == path) else {
+                    return Task::none();
+                };
+
+                match matching::best_match(file, &results) {
+                    Some((best, score)) if score >= matching::CONFIDENT_MATCH => {
+                        let best = best.clone();
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Success,
+                            "Best Match Applied",
+                            format!("{} - {} ({}, {:.0}% match)", best.artist, best.title, best.source, score * 100.0)
+                        ));
+                        self.update(Message::ApplyMetadata(best))
+                    }
+                    _ => {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Info,
+                            "No Confident Match",
+                            "None of the results matched this file closely enough. Pick one manually."
+                        ));
+                        Task::none()
+                    }
+                }
+            }
+            Message::CoverDownloaded(path, Ok(bytes)) => {
+                // Goes to the file the cover was applied to, even if another one is selected by now
+                Task::perform(
+                    prepare_cover(bytes, self.settings.max_embedded_cover, self.settings.cover_jpeg_quality()),
+                    move |bytes| Message::CoverPrepared(path.clone(), bytes)
+                )
+            }
+            Message::CoverPrepared(path, bytes) => {
+                if self.cover_pending.as_ref() == Some(&path) {
+                    self.cover_pending = None;
+                }
+                if let Some(idx) = self.files.iter().position(|f| f.path == path) {
+                     self.files[idx].picture_data = Some(bytes);
+                     self.mark_dirty(idx);
+                     let body = match self.settings.artwork_mode {
+                         settings::ArtworkMode::Embed => "New cover art downloaded and applied.".to_string(),
+                         settings::ArtworkMode::Folder => format!("New cover art will be saved as {} next to the file.", audio::FOLDER_COVER_NAME),
+                         settings::ArtworkMode::Both => format!("New cover art applied; {} is written when the file is saved.", audio::FOLDER_COVER_NAME),
+                     };
+                     self.toast_manager.add(toast::Toast::new(toast::Status::Success, "Cover Updated", body));
+                     return self.refresh_thumbnail(idx);
+                }
+                Task::none()
+            }
+            Message::ApplyCoverToAll => {
+                let Some(idx) = self.selected_file_index.filter(|&idx| self.files.get(idx).is_some_and(|f| f.picture_data.is_some())) else {
+                    return Task::none();
+                };
+                let others_with_art = self.files.iter().enumerate()
+                    .filter(|(i, f)| *i != idx && f.picture_data.is_some())
+                    .count();
+                if others_with_art > 0 {
+                    self.show_cover_to_all_confirmation = true;
+                    Task::none()
+                } else {
+                    self.apply_cover_to_all(false)
+                }
+            }
+            Message::ConfirmCoverToAll(overwrite) => {
+                self.show_cover_to_all_confirmation = false;
+                self.apply_cover_to_all(overwrite)
+            }
+            Message::CancelCoverToAll => {
+                self.show_cover_to_all_confirmation = false;
+                Task::none()
+            }
+            Message::PreviewCover(urls) => {
+                self.show_cover_preview = true;
+                self.cover_preview = None;
+                self.cover_preview_choice = 0;
+                self.cover_preview_thumbs = vec![Thumbnail::Loading; urls.len()];
+                self.cover_preview_candidates = urls;
+
+                let full = self.cover_preview_candidates.first().cloned();
+                let mut tasks = vec![Task::perform(download_image(full), |res| Message::CoverPreviewLoaded(0, res))];
+                if self.cover_preview_candidates.len() > 1 {
+                    tasks.extend(self.cover_preview_candidates.iter().cloned().enumerate().map(|(i, url)| {
+                        Task::perform(download_thumbnail(Some(url), self.settings.thumbnail_fit), move |res| Message::CoverCandidateThumb(i, res))
+                    }));
+                }
+                Task::batch(tasks)
+            }
+            Message::SelectCoverCandidate(index) => {
+                if index == self.cover_preview_choice || index >= self.cover_preview_candidates.len() {
+                    return Task::none();
+                }
+                self.cover_preview_choice = index;
+                self.cover_preview = None;
+                let url = self.cover_preview_candidates[index].clone();
+                Task::perform(download_image(Some(url)), move |res| Message::CoverPreviewLoaded(index, res))
+            }
+            Message::CoverCandidateThumb(index, result) => {
+                if let Some(slot) = self.cover_preview_thumbs.get_mut(index) {
+                    *slot = match result {
+                        Ok(bytes) => Thumbnail::Loaded(bytes),
+                        Err(_) => Thumbnail::Failed,
+                    };
+                }
+                Task::none()
+            }
+            Message::CoverPreviewLoaded(index, result) => {
+                // Ignore downloads for a candidate the user has since moved away from
+                if !self.show_cover_preview || index != self.cover_preview_choice {
+                    return Task::none();
+                }
+                match result {
+                    Ok(bytes) => self.cover_preview = Some(bytes),
+                    Err(e) => {
+                        self.show_cover_preview = false;
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Error,
+                            "Cover Error",
+                            format!("Failed to download cover: {}", e)
+                        ));
+                    }
+                }
+                Task::none()
+            }
+            Message::ApplyPreviewCover => {
+                self.show_cover_preview = false;
+                let path = self.selected_file_index.and_then(|idx| self.files.get(idx)).map(|f| f.path.clone());
+                match (path, self.cover_preview.take()) {
+                    (Some(path), Some(bytes)) => Task::done(Message::CoverDownloaded(path, Ok(bytes))),
+                    _ => Task::none(),
+                }
+            }
+            Message::CloseCoverPreview => {
+                self.show_cover_preview = false;
+                self.cover_preview = None;
+                self.cover_preview_candidates.clear();
+                self.cover_preview_thumbs.clear();
+                Task::none()
+            }
+            Message::CoverDownloaded(path, Err(e)) => {
+                  if self.cover_pending.as_ref() == Some(&path) {
+                      self.cover_pending = None;
+                  }
+                  self.toast_manager.add(toast::Toast::new(
+                     toast::Status::Error,
+                     "Cover Error",
+                     format!("Failed to download cover: {}", e)
+                 ));
+                  Task::none()
+            }
+            Message::SaveAll => self.start_save_all(),
+            Message::SaveAllFinished(results, read_only) => {
+                self.is_loading = false;
+                let job = self.batch_job.take();
+
+                let attempted = results.len();
+                let mut success_count = 0;
+                let mut failures = Vec::new();
+                for (path, result) in results {
+                    match result {
+                        Ok(_) => {
+                            if let Some(file) = self.files.iter_mut().find(|f| f.path == path) {
+                                file.dirty = false;
+                            }
+                            success_count += 1;
+                        }
+                        Err(e) => failures.push((path, e)),
+                    }
+                }
+
+                if let Some(job) = job.filter(|j| j.is_cancelled()) {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Save Cancelled",
+                        format!("Stopped after {} of {} files (saved: {}, failed: {}).", attempted, job.total, success_count, failures.len())
+                    ));
+                    self.save_failures = failures;
+                    self.last_edit_time = None;
+                } else {
+                    self.report_save_all(success_count, failures, read_only);
+                }
+
+                // Leaves only once every edit is on disk; otherwise the prompt comes back
+                // under the failure or read-only dialog
+                match self.leave_after_save.take() {
+                    Some(action) if self.has_unsaved_changes() => {
+                        self.pending_leave = Some(action);
+                        Task::none()
+                    }
+                    Some(action) => self.leave(action, true),
+                    None => Task::none(),
+                }
+            }
+            Message::CancelBatch => {
+                if let Some(job) = &self.batch_job {
+                    job.cancel();
+                    self.loading_message = "Cancelling...".to_string();
+                }
+                Task::none()
+            }
+            Message::BatchProgressTick => {
+                // Nothing to update; the tick only redraws the progress count
+                Task::none()
+            }
+            Message::ExportMetadata => {
+                if self.files.is_empty() {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Nothing to Export",
+                        "Open a folder with audio files first"
+                    ));
+                    Task::none()
+                } else {
+                    Task::perform(pick_export_path(), Message::ExportPathPicked)
+                }
+            }
+            Message::ExportPathPicked(Some(path)) => {
+                match metadata_io::export(&self.files, self.current_dir.as_deref(), &path) {
+                    Ok(count) => {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Success,
+                            "Metadata Exported",
+                            format!("Wrote {} files to {}", count, path.display())
+                        ));
+                    }
+                    Err(e) => {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Error,
+                            "Export Failed",
+                            e
+                        ));
+                    }
                 }
                 Task::none()
             }
-            Message::CoverDownloaded(Ok(bytes)) => {
-                if let Some(idx) = self.selected_file_index {
-                     self.files[idx].picture_data = Some(bytes);
-                     self.toast_manager.add(toast::Toast::new(
+            Message::ExportPathPicked(None) => Task::none(),
+            Message::ImportMetadata => {
+                if self.files.is_empty() {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Nothing to Import Into",
+                        "Open a folder with audio files first"
+                    ));
+                    Task::none()
+                } else {
+                    Task::perform(pick_import_path(), Message::ImportPathPicked)
+                }
+            }
+            Message::ImportPathPicked(Some(path)) => {
+                match metadata_io::import(&path) {
+                    Ok(rows) => {
+                        let base_dir = self.current_dir.clone();
+                        let (updated, unmatched) = metadata_io::apply_rows(&mut self.files, base_dir.as_deref(), rows);
+
+                        if !updated.is_empty() {
+                            // Imported values stay in memory until the user saves
+                            self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Success,
+                                "Metadata Imported",
+                                format!("Updated {} files. Use Save All to write them.", updated.len())
+                            ));
+                        }
+
+                        if !unmatched.is_empty() {
+                            let preview: Vec<&str> = unmatched.iter().take(5).map(|p| p.as_str()).collect();
+                            let more = if unmatched.len() > preview.len() {
+                                format!(" and {} more", unmatched.len() - preview.len())
+                            } else {
+                                String::new()
+                            };
+                            self.toast_manager.add(toast::Toast::new(
+                                toast::Status::Info,
+                                "Unmatched Rows",
+                                format!("{} rows matched no loaded file: {}{}", unmatched.len(), preview.join(", "), more)
+                            ));
+                        }
+                    }
+                    Err(e) => {
+                        self.toast_manager.add(toast::Toast::new(
+                            toast::Status::Error,
+                            "Import Failed",
+                            e
+                        ));
+                    }
+                }
+                Task::none()
+            }
+            Message::ImportPathPicked(None) => Task::none(),
+
+            Message::MakeWritableAndSave => {
+                let save_options = audio::SaveOptions::from_settings(&self.settings);
+                let mut saved = 0;
+                let mut failed = Vec::new();
+
+                for path in std::mem::take(&mut self.read_only_pending) {
+                    let Some(file) = self.files.iter_mut().find(|f| f.path == path) else {
+                        continue;
+                    };
+                    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    match file.clear_read_only().and_then(|_| file.save(save_options)) {
+                        Ok(_) => {
+                            file.dirty = false;
+                            saved += 1;
+                        }
+                        Err(e) => failed.push(format!("{}: {}", name, e)),
+                    }
+                }
+
+                if failed.is_empty() {
+                    self.last_edit_time = None;
+                    self.toast_manager.add(toast::Toast::new(
                         toast::Status::Success,
-                        "Cover Updated",
-                        "New cover art downloaded and applied."
+                        "Saved",
+                        format!("Made {} file(s) writable and saved them.", saved)
+                    ));
+                } else {
+                    self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Error,
+                        "Save Failed",
+                        failed.join("\n")
                     ));
                 }
                 Task::none()
             }
-            Message::CoverDownloaded(Err(e)) => {
-                  self.toast_manager.add(toast::Toast::new(
-                     toast::Status::Error,
-                     "Cover Error",
-                     format!("Failed to download cover: {}", e)
-                 ));
-                  Task::none()
+            Message::DismissReadOnly => {
+                self.read_only_pending.clear();
+                Task::none()
+            }
+            Message::RetryFailedSaves => {
+                let failed: HashSet<PathBuf> = std::mem::take(&mut self.save_failures).into_iter().map(|(path, _)| path).collect();
+                self.start_save(|f| failed.contains(&f.path))
+            }
+            Message::DismissSaveFailures => {
+                self.save_failures.clear();
+                Task::none()
+            }
+            Message::ShowFileMenu(path) => {
+                self.file_menu = Some(path);
+                Task::none()
+            }
+            Message::CloseFileMenu => {
+                self.file_menu = None;
+                Task::none()
+            }
+            Message::RevealFile(path) => {
+                self.file_menu = None;
+                Task::perform(reveal_in_file_manager(path), Message::FileRevealed)
+            }
+            Message::OpenContainingFolder(path) => {
+                self.file_menu = None;
+                Task::perform(open_containing_folder(path), Message::FolderOpened)
+            }
+            Message::FileRevealed(result) => {
+                match result {
+                    Ok(true) => {}
+                    Ok(false) => self.toast_manager.add(toast::Toast::new(
+                        toast::Status::Info,
+                        "Opened Folder",
+                        "Your file manager can't highlight files, so the containing folder was opened instead"
+                    )),
+                    Err(e) => self.toast_manager.add(toast::Toast::new(toast::Status::Error, "Couldn't Show File", e)),
+                }
+                Task::none()
+            }
+            Message::FolderOpened(result) => {
+                if let Err(e) = result {
+                    self.toast_manager.add(toast::Toast::new(toast::Status::Error, "Couldn't Open Folder", e));
+                }
+                Task::none()
             }
-            Message::SaveAll => self.perform_save_all(),
-
             Message::CloseRequested => {
-                if self.has_unsaved_changes {
-                    self.show_exit_confirmation = true;
+                if self.has_unsaved_changes() {
+                    self.pending_leave = Some(LeaveAction::Exit);
                     Task::none()
                 } else {
-                    iced::window::get_latest().and_then(iced::window::close)
+                    self.close_window()
                 }
             }
-            Message::ConfirmExit(save) => {
-                self.show_exit_confirmation = false;
+            Message::WindowResized(size) => {
+                let geometry = self.window_geometry.get_or_insert_with(|| self.settings.window.unwrap_or_default());
+                geometry.width = size.width;
+                geometry.height = size.height;
+                Task::none()
+            }
+            Message::WindowMoved(position) => {
+                let geometry = self.window_geometry.get_or_insert_with(|| self.settings.window.unwrap_or_default());
+                geometry.x = Some(position.x);
+                geometry.y = Some(position.y);
+                Task::none()
+            }
+            Message::ConfirmLeave(save) => {
+                let Some(action) = self.pending_leave.take() else {
+                    return Task::none();
+                };
                 if save {
-                    let _ = self.perform_save_all(); 
-                     iced::window::get_latest().and_then(iced::window::close)
-                } else {
-                     iced::window::get_latest().and_then(iced::window::close)
+                    // SaveAllFinished carries on with the action
+                    self.leave_after_save = Some(action);
+                    return self.start_save_all();
                 }
+                self.leave(action, false)
             }
-            Message::CancelExit => {
-                self.show_exit_confirmation = false;
+            Message::CancelLeave => {
+                self.pending_leave = None;
+                self.pending_selection = None;
                 Task::none()
             }
             
-            Message::Tick(_) => {
-                 if self.has_unsaved_changes {
+            Message::Tick(now) => {
+                 self.toast_manager.update(now);
+                 if self.settings.auto_save && self.selected_file_dirty() && !self.modal_open() {
                      match self.last_edit_time {
-                         Some(time) if time.elapsed() > Duration::from_secs(1) => {
+                         Some(time) if time.elapsed() > self.auto_save_delay && !self.auto_save_held() => {
                              return Task::done(Message::SavePressed);
                          }
                          _ => {}
@@ -410,56 +2455,450 @@ impl App {
     }
 
 
-    fn perform_save_all(&mut self) -> Task<Message> {
-        let mut success_count = 0;
-        let mut error_count = 0;
-        
-        for file in &mut self.files {
-            match file.save() {
-                Ok(_) => success_count += 1,
-                Err(_) => error_count += 1,
+    /// Writes one change straight to config.json, leaving out the rest of `self.settings` so
+    /// edits in the settings dialog that haven't been saved stay unsaved.
+    /// Skipped while config.json is damaged; saving the dialog is what replaces it.
+    fn persist_setting(&mut self, change: impl FnOnce(&mut settings::UserSettings)) {
+        if settings::UserSettings::config_damaged() {
+            return;
+        }
+        let mut stored = settings::UserSettings::load();
+        change(&mut stored);
+        if let Some(warning) = stored.save() {
+            self.toast_manager.add(toast::Toast::new(toast::Status::Warning, "Secrets Stored in Plaintext", warning));
+        }
+    }
+
+    /// Remembers the window geometry and closes the window.
+    fn close_window(&mut self) -> Task<Message> {
+        if let Some(geometry) = self.window_geometry {
+            self.persist_setting(|stored| stored.window = Some(geometry));
+            self.settings.window = Some(geometry);
+        }
+        iced::window::get_latest().and_then(iced::window::close)
+    }
+
+    /// Goes where the unsaved-changes prompt was headed. `saved` is false when the user
+    /// chose to discard their edits.
+    fn leave(&mut self, action: LeaveAction, saved: bool) -> Task<Message> {
+        match action {
+            LeaveAction::Exit => self.close_window(),
+            LeaveAction::BackToTitle => {
+                let task = if saved { Task::none() } else { self.discard_changes() };
+                self.current_page = Page::TitleScreen;
+                task
             }
+            // Reloading replaces every file, so unsaved edits are dropped either way
+            LeaveAction::ReloadFolder => self.reload_folder(),
+            LeaveAction::OpenFolder(path) => self.open_folder(path),
+        }
+    }
+
+    /// Saves every dirty file off the UI thread behind a cancellable progress overlay.
+    fn start_save_all(&mut self) -> Task<Message> {
+        self.start_save(|_| true)
+    }
+
+    /// Saves the dirty files matching `include` in the background.
+    fn start_save(&mut self, include: impl Fn(&audio::AudioFile) -> bool) -> Task<Message> {
+        let (read_only, writable): (Vec<&audio::AudioFile>, Vec<&audio::AudioFile>) = self.files
+            .iter()
+            .filter(|f| f.dirty && include(f))
+            .partition(|f| f.is_read_only());
+        let read_only: Vec<PathBuf> = read_only.into_iter().map(|f| f.path.clone()).collect();
+        let writable: Vec<audio::AudioFile> = writable.into_iter().cloned().collect();
+
+        if writable.is_empty() {
+            return Task::done(Message::SaveAllFinished(Vec::new(), read_only));
         }
 
-        if error_count == 0 && success_count > 0 {
+        let job = BatchJob::new(writable.len());
+        self.batch_job = Some(job.clone());
+        self.is_loading = true;
+        self.loading_message = "Saving files...".to_string();
+        let save_options = audio::SaveOptions::from_settings(&self.settings);
+        Task::perform(save_files(writable, save_options, job), move |results| Message::SaveAllFinished(results, read_only.clone()))
+    }
+
+    /// Reports a finished save. Failures are listed in a dialog offering a retry.
+    fn report_save_all(&mut self, success_count: usize, failures: Vec<(PathBuf, String)>, read_only: Vec<PathBuf>) {
+        let has_read_only = !read_only.is_empty();
+        let error_count = failures.len();
+        self.read_only_pending = read_only;
+        self.save_failures = failures;
+
+        if success_count == 0 && error_count == 0 && has_read_only {
+            // The read-only dialog explains what happened
+        } else if success_count == 0 && error_count == 0 {
+             self.toast_manager.add(toast::Toast::new(
+                toast::Status::Info,
+                "Nothing to Save",
+                "No files have unsaved changes."
+            ));
+        } else if error_count == 0 {
              self.toast_manager.add(toast::Toast::new(
                 toast::Status::Success,
                 "All Saved",
                 format!("Successfully saved {} files.", success_count)
             ));
-        } else if error_count > 0 {
-             self.toast_manager.add(toast::Toast::new(
+        }
+
+        self.last_edit_time = None;
+    }
+
+    fn title(&self) -> String {
+        if self.has_unsaved_changes() {
+            "NaviTag - Music Tagger •".to_string()
+        } else {
+            "NaviTag - Music Tagger".to_string()
+        }
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        self.files.iter().any(|f| f.dirty)
+    }
+
+    fn selected_file_dirty(&self) -> bool {
+        self.selected_file_index
+            .and_then(|idx| self.files.get(idx))
+            .map(|f| f.dirty)
+            .unwrap_or(false)
+    }
+
+    /// Whether a cover download or a change waiting for review holds auto-save back for
+    /// the selected file.
+    fn auto_save_held(&self) -> bool {
+        let selected_path = self.selected_file_index.and_then(|idx| self.files.get(idx)).map(|f| &f.path);
+        [&self.cover_pending, &self.review_pending].into_iter().any(|pending| pending.is_some() && pending.as_ref() == selected_path)
+    }
+
+    fn mark_dirty(&mut self, idx: usize) {
+        if let Some(file) = self.files.get_mut(idx) {
+            file.dirty = true;
+            self.last_edit_time = Some(Instant::now());
+            if self.review_pending.as_ref() == Some(&file.path) {
+                self.review_pending = None;
+            }
+            let large = file.file_size >= LARGE_FILE_BYTES;
+            self.auto_save_delay = if large { LARGE_FILE_AUTO_SAVE_DELAY } else { AUTO_SAVE_DELAY };
+        }
+    }
+
+
+    fn modal_open(&self) -> bool {
+        self.show_settings
+            || self.show_advanced_tags
+            || self.pending_leave.is_some()
+            || self.show_clear_tags_confirmation
+            || self.swap_suggestion.is_some()
+            || self.compare.is_some()
+            || self.show_cover_preview
+            || self.show_cover_to_all_confirmation
+            || !self.pending_batch.is_empty()
+            || self.show_duplicates
+            || self.organize_plan.is_some()
+            || !self.read_only_pending.is_empty()
+            || !self.save_failures.is_empty()
+            || self.file_menu.is_some()
+            || self.is_loading
+    }
+
+    /// Drops deleted or moved duplicates from the file list and the open groups, then reports the outcome.
+    fn finish_duplicate_action(&mut self, verb: &str, done: HashSet<PathBuf>, failed: Vec<String>) {
+        let selected_path = self.selected_file_index.and_then(|idx| self.files.get(idx)).map(|f| f.path.clone());
+        if selected_path.as_ref().is_some_and(|p| done.contains(p)) {
+            self.player.stop();
+        }
+
+        self.files.retain(|f| !done.contains(&f.path));
+        self.selected_file_index = selected_path.and_then(|path| self.files.iter().position(|f| f.path == path));
+        self.sync_text_editors();
+
+        for group in &mut self.duplicate_groups {
+            group.retain(|p| !done.contains(p));
+        }
+        self.duplicate_groups.retain(|g| g.len() > 1);
+        if self.duplicate_groups.is_empty() {
+            self.show_duplicates = false;
+        }
+
+        if !done.is_empty() {
+            self.toast_manager.add(toast::Toast::new(
+                toast::Status::Success,
+                "Duplicates Removed",
+                format!("{} {} file(s)", verb, done.len())
+            ));
+        }
+        if !failed.is_empty() {
+            self.toast_manager.add(toast::Toast::new(
                 toast::Status::Error,
-                "Save Errors",
-                format!("Saved: {}, Failed: {}. Check file permissions.", success_count, error_count)
+                "Some Files Failed",
+                failed.join("\n")
+            ));
+        }
+    }
+
+    /// Loads the selected file's comment and lyrics into the multi-line editors.
+    fn sync_text_editors(&mut self) {
+        let file = self.selected_file_index.and_then(|idx| self.files.get(idx));
+        self.comment_editor = text_editor::Content::with_text(file.map(|f| f.comment.as_str()).unwrap_or_default());
+        self.lyrics_editor = text_editor::Content::with_text(file.map(|f| f.lyrics.as_str()).unwrap_or_default());
+    }
+
+    /// Rebuilds the batch review rows from the stored results using the current mapping.
+    fn pair_batch(&mut self) {
+        let targets: Vec<usize> = (0..self.files.len())
+            .filter(|&idx| !(self.settings.only_incomplete && self.files[idx].has_complete_tags()))
+            .collect();
+        self.batch_skipped = self.files.len() - targets.len();
+
+        let files: Vec<&audio::AudioFile> = targets.iter().map(|&idx| &self.files[idx]).collect();
+        self.pending_batch = matching::pair(self.batch_mapping, &files, self.batch_results.clone())
+            .into_iter()
+            .map(|(n, result)| BatchProposal {
+                path: self.files[targets[n]].path.clone(),
+                result,
+                selected: true,
+            })
+            .collect();
+    }
+
+    /// Warns about providers that are enabled but skipped for missing credentials, once per
+    /// configuration rather than on every search.
+    fn warn_unconfigured_providers(&mut self) {
+        let unconfigured = api::unconfigured_providers(&self.settings);
+        if !unconfigured.is_empty() && unconfigured != self.warned_unconfigured {
+            self.toast_manager.add(toast::Toast::new(
+                toast::Status::Warning,
+                "Provider Not Configured",
+                format!("{}\nAdd the missing credentials in Settings or disable the provider.", unconfigured.join("\n"))
+            ));
+        }
+        self.warned_unconfigured = unconfigured;
+    }
+
+    /// Names the providers a search couldn't reach; the results from the others are still shown.
+    fn report_search_errors<T>(&mut self, outcome: &api::SearchOutcome<T>) {
+        if outcome.errors.is_empty() {
+            return;
+        }
+        let (status, title) = if outcome.results.is_empty() {
+            (toast::Status::Error, "Search Error")
+        } else {
+            (toast::Status::Warning, "Some Sources Failed")
+        };
+        self.toast_manager.add(toast::Toast::new(status, title, outcome.error_summary()));
+    }
+
+    /// Copies the selected file's cover to every other file, skipping files that already
+    /// have artwork unless `overwrite` is set.
+    fn apply_cover_to_all(&mut self, overwrite: bool) -> Task<Message> {
+        let Some(cover) = self.selected_file_index.and_then(|idx| self.files.get(idx)).and_then(|f| f.picture_data.clone()) else {
+            return Task::none();
+        };
+
+        let targets: Vec<usize> = self.files.iter().enumerate()
+            .filter(|(i, _)| Some(*i) != self.selected_file_index)
+            .filter(|(_, f)| f.picture_data.as_ref() != Some(&cover))
+            .filter(|(_, f)| overwrite || f.picture_data.is_none())
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut thumbnails = Vec::new();
+        for &idx in &targets {
+            self.files[idx].picture_data = Some(cover.clone());
+            self.files[idx].dirty = true;
+            thumbnails.push(self.refresh_thumbnail(idx));
+        }
+
+        self.toast_manager.add(toast::Toast::new(
+            if targets.is_empty() { toast::Status::Info } else { toast::Status::Success },
+            "Cover Applied",
+            format!("Applied the cover to {} other files. Use Save All to write them.", targets.len())
+        ));
+        Task::batch(thumbnails)
+    }
+
+    /// Switches the editor to `path`, remembering it as a recent folder and activating the
+    /// profile last used there.
+    fn open_folder(&mut self, path: PathBuf) -> Task<Message> {
+        let profile = self.settings.profile_for_folder(&path).map(str::to_string);
+        self.persist_setting(|stored| {
+            stored.remember_folder(&path);
+            if let Some(name) = &profile {
+                stored.activate_profile(name);
+            }
+        });
+        self.settings.remember_folder(&path);
+        if let Some(name) = profile.filter(|name| *name != self.settings.active_profile) {
+            self.settings.activate_profile(&name);
+            self.search_cache.clear();
+            self.toast_manager.add(toast::Toast::new(
+                toast::Status::Info,
+                "Profile Switched",
+                format!("Using the \"{}\" profile last used in this folder", name)
             ));
         }
 
-        self.has_unsaved_changes = false;
-        Task::none()
+        self.current_dir = Some(path.clone());
+        self.current_page = Page::Editor;
+        self.is_loading = true;
+        self.loading_message = "Scanning files...".to_string();
+        Task::perform(load_files(path, self.settings.value_separator, self.settings.extra_extensions()), |(files, errors)| Message::FilesLoaded(files, errors))
+    }
+
+    /// Re-reads the open folder from disk, keeping the selected file selected if it's still there.
+    fn reload_folder(&mut self) -> Task<Message> {
+        let Some(path) = self.current_dir.clone() else {
+            return Task::none();
+        };
+        self.pending_selection = self.selected_file_index.and_then(|idx| self.files.get(idx)).map(|f| f.path.clone());
+        self.is_loading = true;
+        self.loading_message = "Reloading folder...".to_string();
+        Task::perform(load_files(path, self.settings.value_separator, self.settings.extra_extensions()), |(files, errors)| Message::FilesLoaded(files, errors))
+    }
+
+    /// Reloads every file with unsaved edits from disk, throwing the edits away.
+    fn discard_changes(&mut self) -> Task<Message> {
+        let dirty: Vec<usize> = self.files.iter().enumerate().filter(|(_, f)| f.dirty).map(|(i, _)| i).collect();
+        let mut tasks = Vec::new();
+        for idx in dirty {
+            if let Ok(file) = audio::AudioFile::load(self.files[idx].path.clone(), self.settings.value_separator) {
+                self.files[idx] = file;
+                tasks.push(self.refresh_thumbnail(idx));
+            }
+        }
+        self.sync_text_editors();
+        Task::batch(tasks)
+    }
+
+    /// Re-renders a file's list thumbnail from its current cover, e.g. after a new one was applied.
+    fn refresh_thumbnail(&mut self, idx: usize) -> Task<Message> {
+        let Some(file) = self.files.get_mut(idx) else {
+            return Task::none();
+        };
+        file.thumbnail_data = None;
+
+        match file.picture_data.clone() {
+            Some(data) => {
+                let path = file.path.clone();
+                self.thumbnails_requested.insert(path.clone());
+                Task::perform(decode_thumbnail(data, self.settings.thumbnail_fit), move |thumb| Message::ThumbnailReady(path.clone(), thumb))
+            }
+            None => Task::none(),
+        }
+    }
+
+    /// Starts decoding thumbnails for the rows in view, plus a margin, that don't have one yet.
+    fn load_visible_thumbnails(&mut self) -> Task<Message> {
+        let visible: Vec<usize> = (0..self.files.len())
+            .filter(|&i| self.matches_file_filter(&self.files[i]))
+            .collect();
+
+        let (first, last) = match self.file_list_view {
+            Some((start, end)) => (
+                (start * visible.len() as f32).floor() as usize,
+                (end * visible.len() as f32).ceil() as usize,
+            ),
+            None => (0, INITIAL_THUMBNAIL_ROWS),
+        };
+        let first = first.saturating_sub(THUMBNAIL_ROW_MARGIN).min(visible.len());
+        let last = (last + THUMBNAIL_ROW_MARGIN).clamp(first, visible.len());
+
+        let mut tasks = Vec::new();
+        for &idx in &visible[first..last] {
+            let file = &self.files[idx];
+            if file.thumbnail_data.is_some() || self.thumbnails_requested.contains(&file.path) {
+                continue;
+            }
+            if let Some(data) = file.picture_data.clone() {
+                let path = file.path.clone();
+                self.thumbnails_requested.insert(path.clone());
+                tasks.push(Task::perform(decode_thumbnail(data, self.settings.thumbnail_fit), move |thumb| Message::ThumbnailReady(path.clone(), thumb)));
+            }
+        }
+        Task::batch(tasks)
+    }
+
+    fn apply_sort(&mut self) {
+        // Track the selection by path so it survives the reorder
+        let selected_path = self.selected_file_index
+            .and_then(|idx| self.files.get(idx))
+            .map(|f| f.path.clone());
+
+        audio::sort_files(&mut self.files, self.sort_key, self.sort_ascending);
+
+        self.selected_file_index = selected_path
+            .and_then(|path| self.files.iter().position(|f| f.path == path));
     }
 
+    fn matches_file_filter(&self, file: &audio::AudioFile) -> bool {
+        self.matches_year_filter(file) && self.matches_text_filter(file)
+    }
+
+    fn matches_year_filter(&self, file: &audio::AudioFile) -> bool {
+        let Some(year) = file.year else {
+            return self.show_unknown_year;
+        };
+        let min = self.year_min.parse::<u32>().unwrap_or(0);
+        let max = self.year_max.parse::<u32>().unwrap_or(u32::MAX);
+        (min..=max).contains(&year)
+    }
+
+    fn matches_text_filter(&self, file: &audio::AudioFile) -> bool {
+        let filter = self.file_filter.trim().to_lowercase();
+        if filter.is_empty() {
+            return true;
+        }
+
+        let file_name = file.path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+        file.title.to_lowercase().contains(&filter)
+            || file.artist.to_lowercase().contains(&filter)
+            || file_name.contains(&filter)
+    }
 
     fn view(&self) -> Element<'_, Message> {
         let content = match self.current_page {
             Page::TitleScreen => {
-                container(
-                    column![
-                         image_widget(image_widget::Handle::from_bytes(include_bytes!("logo.png").to_vec())).width(Length::Fixed(150.0)),
-                         text("NaviTag").size(40).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
-                         vertical_space().height(20),
-                         button("Open Folder").on_press(Message::OpenFolder).padding(15).width(Length::Fixed(200.0)),
-                         button("Settings").on_press(Message::ToggleSettings).padding(15).width(Length::Fixed(200.0)),
-                    ]
-                    .align_x(iced::Alignment::Center)
-                    .spacing(20)
-                )
+                let mut title_column = column![
+                     image_widget(image_widget::Handle::from_bytes(include_bytes!("logo.png").to_vec())).width(Length::Fixed(150.0)),
+                     text("NaviTag").size(40).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     vertical_space().height(20),
+                     button("Open Folder").on_press(Message::OpenFolder).padding(15).width(Length::Fixed(200.0)),
+                     button("Settings").on_press(Message::ToggleSettings).padding(15).width(Length::Fixed(200.0)),
+                ]
+                .align_x(iced::Alignment::Center)
+                .spacing(20);
+
+                let recent: Vec<Element<Message>> = self.settings.recent_folders().map(|folder| {
+                    let name = folder.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| folder.to_string_lossy().to_string());
+                    button(column![
+                            text(name).size(14),
+                            text(folder.to_string_lossy().to_string()).size(10).color(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+                        ].spacing(2))
+                        .on_press(Message::FolderPicked(Some(folder.clone())))
+                        .padding(8)
+                        .width(Length::Fixed(400.0))
+                        .style(button::secondary)
+                        .into()
+                }).collect();
+
+                if !recent.is_empty() {
+                    title_column = title_column.push(
+                        column![
+                            text("Recent Folders").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                            scrollable(column(recent).spacing(5)).height(Length::Shrink),
+                        ].spacing(10).align_x(iced::Alignment::Center)
+                    );
+                }
+
+                container(title_column)
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .center_x(Length::Fill)
                 .center_y(Length::Fill)
-                .style(|_theme: &Theme| container::Style {
-                     background: Some(iced::Color::from_rgb(0.1, 0.1, 0.1).into()),
+                .style(|theme: &Theme| container::Style {
+                     background: Some(theme.extended_palette().background.base.color.into()),
                      ..Default::default()
                 })
                 .into()
@@ -470,9 +2909,58 @@ impl App {
                 } else {
                     "No folder open".to_string()
                 }).size(18).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() });
+                let album_warnings = stats::album_warnings(&self.files);
+                let file_list_header = row![
+                    container(file_list_header).width(Length::Fill),
+                    button(text(if album_warnings.is_empty() { "Stats".to_string() } else { format!("Stats ({} warnings)", album_warnings.len()) }).size(12))
+                        .on_press(Message::ToggleStats)
+                        .padding([4, 8])
+                        .style(if self.show_stats { button::primary } else { button::secondary }),
+                ]
+                .align_y(iced::Alignment::Center);
+
+                let stats_panel = if self.show_stats {
+                    let stats = stats::compute(&self.files);
+                    let line = |label: &str, value: String| {
+                        row![
+                            text(label.to_string()).size(12).width(Length::Fill),
+                            text(value).size(12),
+                        ]
+                    };
+                    let needs_fixing = |count: usize| {
+                        text(count.to_string()).size(12).color(if count > 0 { iced::Color::from_rgb(0.9, 0.6, 0.1) } else { iced::Color::from_rgb(0.3, 0.7, 0.3) })
+                    };
+                    Element::from(
+                        container(
+                            column![
+                                line("Files", stats.total.to_string()),
+                                line("Total length", stats::format_total_duration(stats.duration)),
+                                row![text("Missing cover art").size(12).width(Length::Fill), needs_fixing(stats.missing_cover)],
+                                row![text("Unknown artist").size(12).width(Length::Fill), needs_fixing(stats.unknown_artist)],
+                                row![text("Unknown album").size(12).width(Length::Fill), needs_fixing(stats.unknown_album)],
+                                row![text("No year").size(12).width(Length::Fill), needs_fixing(stats.missing_year)],
+                                line("Unsaved changes", stats.unsaved.to_string()),
+                                text(stats::format_breakdown(&stats.formats)).size(11).color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
+                                column(album_warnings.iter().map(|warning| {
+                                    text(format!("/!\\ {}", warning)).size(11).color(iced::Color::from_rgb(0.9, 0.6, 0.1)).into()
+                                })).spacing(2),
+                            ]
+                            .spacing(4)
+                        )
+                        .padding(10)
+                        .width(Length::Fill)
+                        .style(|theme: &Theme| container::Style {
+                            background: Some(theme.extended_palette().background.weak.color.into()),
+                            border: iced::border::Border { radius: 5.0.into(), ..Default::default() },
+                            ..Default::default()
+                        })
+                    )
+                } else {
+                    Element::from(column![])
+                };
 
                         let file_list_content = column(
-                    self.files.iter().enumerate().map(|(i, f)| {
+                    self.files.iter().enumerate().filter(|(_, f)| self.matches_file_filter(f)).map(|(i, f)| {
                         let is_selected = Some(i) == self.selected_file_index;
                         
                         let thumb: Element<Message> = if let Some(data) = &f.thumbnail_data {
@@ -483,8 +2971,8 @@ impl App {
                                 .height(Length::Fixed(40.0))
                                 .align_x(iced::alignment::Horizontal::Center)
                                 .align_y(iced::alignment::Vertical::Center)
-                                .style(|_theme: &Theme| container::Style {
-                                    background: Some(iced::Color::from_rgb(0.2, 0.2, 0.2).into()),
+                                .style(|theme: &Theme| container::Style {
+                                    background: Some(theme.extended_palette().background.strong.color.into()),
                                     ..Default::default()
                                 })
                                 .into()
@@ -493,14 +2981,17 @@ impl App {
                         let content = row![
                             thumb,
                             column![
-                                text(&f.title).size(14).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                                row![
+                                    text(&f.title).size(14).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                                    if f.dirty { text("•").size(14).color(iced::Color::from_rgb(0.9, 0.7, 0.2)) } else { text("") },
+                                ].spacing(5),
                                 text(&f.artist).size(12).color(iced::Color::from_rgb(0.7, 0.7, 0.7))
                             ].spacing(2)
                         ]
                         .spacing(10)
                         .align_y(iced::Alignment::Center);
 
-                        button(content)
+                        let row_button = button(content)
                             .on_press(Message::FileSelected(i))
                             .width(Length::Fill)
                             .padding(10)
@@ -515,13 +3006,16 @@ impl App {
                                      }
                                 } else {
                                      button::Style {
-                                        background: Some(iced::Color::from_rgb(0.15, 0.15, 0.15).into()),
+                                        background: Some(theme.extended_palette().background.weak.color.into()),
                                         text_color: palette.text,
                                         border: iced::border::Border { radius: 8.0.into(), ..Default::default() },
                                         ..Default::default()
                                      }
                                 }
-                            })
+                            });
+
+                        mouse_area(row_button)
+                            .on_right_press(Message::ShowFileMenu(f.path.clone()))
                             .into()
                     })
                     .collect::<Vec<_>>()
@@ -529,21 +3023,73 @@ impl App {
                 .spacing(8)
                 .height(Length::Shrink);
 
-                let file_list = scrollable(file_list_content).height(Length::Fill);
+                let file_list = scrollable(file_list_content)
+                    .id(scrollable::Id::new(FILE_LIST_ID))
+                    .on_scroll(Message::FileListScrolled)
+                    .height(Length::Fill);
+
+                let file_filter_input = text_input("Filter files...", &self.file_filter)
+                    .on_input(Message::FileFilterChanged)
+                    .padding(8);
+
+                // Decades that actually occur in the folder, oldest first
+                let decades: Vec<u32> = self.files.iter()
+                    .filter_map(|f| f.year.map(|y| y / 10 * 10))
+                    .collect::<std::collections::BTreeSet<_>>()
+                    .into_iter()
+                    .collect();
+                let year_filter = row![
+                    text("Year").size(12),
+                    text_input("from", &self.year_min).on_input(Message::YearMinChanged).padding(5).width(Length::Fixed(55.0)),
+                    text("-").size(12),
+                    text_input("to", &self.year_max).on_input(Message::YearMaxChanged).padding(5).width(Length::Fixed(55.0)),
+                    pick_list(decades, None::<u32>, Message::DecadePicked).placeholder("Decade").padding(5),
+                    checkbox("Unknown", self.show_unknown_year).on_toggle(Message::ToggleUnknownYear).size(14).text_size(12),
+                ]
+                .spacing(8)
+                .align_y(iced::Alignment::Center);
+
+                let sort_controls = row![
+                    text("Sort by").size(12),
+                    pick_list(&audio::SortKey::ALL[..], Some(self.sort_key), Message::SortBy).width(Length::Fill),
+                    button(if self.sort_ascending { "Asc" } else { "Desc" }).on_press(Message::ToggleSortDirection).padding(5),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center);
 
                 let left_panel = container(
                     column![
                         file_list_header,
+                        stats_panel,
                         button("Open Folder").on_press(Message::OpenFolder).width(Length::Fill),
                         button("Back to Title").on_press(Message::SwitchToTitle).width(Length::Fill),
-                        button("Save All").on_press(Message::SaveAll).width(Length::Fill).style(|_theme, status| {
+                        with_shortcut(button("Save All").on_press(Message::SaveAll).width(Length::Fill).style(|_theme, status| {
                               button::Style {
                                  background: Some(iced::Color::from_rgb(0.2, 0.6, 0.2).into()),
                                  text_color: iced::Color::WHITE,
                                  border: iced::border::Border { radius: 5.0.into(), ..Default::default() },
                                  ..Default::default()
                               }
-                        }),
+                        }), "Ctrl+Shift+S"),
+                        row![
+                            button("Export Metadata").on_press(Message::ExportMetadata).width(Length::Fill),
+                            button("Import Metadata").on_press(Message::ImportMetadata).width(Length::Fill),
+                        ].spacing(10),
+                        row![
+                            button("Find Duplicates").on_press(Message::FindDuplicates).width(Length::Fill),
+                            button("Reload Folder").on_press_maybe(self.current_dir.as_ref().map(|_| Message::ReloadFolder)).width(Length::Fill),
+                        ].spacing(10),
+                        button("Organize into Folders...").on_press_maybe((!self.files.is_empty()).then_some(Message::OrganizeLibrary)).width(Length::Fill),
+                        file_filter_input,
+                        year_filter,
+                        pick_list(&casing::Style::ALL[..], None::<casing::Style>, |style| Message::NormalizeCasing(style, NormalizeTarget::Shown))
+                            .placeholder("Normalize shown files...")
+                            .width(Length::Fill),
+                        row![
+                            text("Profile").size(12),
+                            pick_list(self.settings.profile_names(), Some(self.settings.active_profile.clone()), Message::ProfileSelected).width(Length::Fill),
+                        ].spacing(10).align_y(iced::Alignment::Center),
+                        sort_controls,
                         file_list
                     ]
                     .spacing(10)
@@ -559,6 +3105,34 @@ impl App {
 
                 let editor_content = if let Some(idx) = self.selected_file_index {
                     let file = &self.files[idx];
+                    let dirty = file.dirty;
+                    let year_value = file.year.map(|y| y.to_string()).unwrap_or_default();
+                    let bpm_value = file.bpm.map(|b| b.to_string()).unwrap_or_default();
+                    let fields = self.settings.editor_fields;
+
+                    let playback = self.player.state();
+                    let is_current_track = playback.path.as_ref() == Some(&file.path);
+                    let elapsed = if is_current_track { playback.elapsed } else { Duration::ZERO };
+                    let total = if is_current_track { playback.total } else { None };
+                    let playback_time = format!(
+                        "{} / {}",
+                        audio_player::format_duration(elapsed),
+                        total.map(audio_player::format_duration).unwrap_or_else(|| "--:--".to_string())
+                    );
+
+                    let playback_controls = row![
+                        button("Play").on_press(Message::Play).padding(5),
+                        button("Pause").on_press(Message::Pause).padding(5),
+                        button("Stop").on_press(Message::Stop).padding(5),
+                        text(playback_time).size(12),
+                        if let Some(err) = &playback.error {
+                            text(err.clone()).size(10).color(iced::Color::from_rgb(0.8, 0.3, 0.3))
+                        } else {
+                            text("")
+                        },
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center);
                     
                     let image_preview: Element<Message> = if let Some(data) = &file.picture_data {
                          image_widget(image_widget::Handle::from_bytes(data.clone())).width(Length::Fixed(200.0)).height(Length::Fixed(200.0)).into()
@@ -568,8 +3142,8 @@ impl App {
                             .height(Length::Fixed(200.0))
                             .center_x(Length::Fill)
                             .center_y(Length::Fill)
-                            .style(|_theme: &Theme| container::Style {
-                                background: Some(iced::Color::from_rgb(0.2, 0.2, 0.2).into()),
+                            .style(|theme: &Theme| container::Style {
+                                background: Some(theme.extended_palette().background.strong.color.into()),
                                 ..Default::default()
                             })
                             .into()
@@ -577,9 +3151,18 @@ impl App {
 
                     column![
                         text(format!("Editing: {}", file.path.file_name().unwrap().to_string_lossy())).size(20).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                        text(format_properties(file)).size(12).color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
+
+                        playback_controls,
                         
                         row![
-                            image_preview,
+                            column![
+                                image_preview,
+                                button("Apply Cover to All")
+                                    .on_press_maybe((file.picture_data.is_some() && self.files.len() > 1).then_some(Message::ApplyCoverToAll))
+                                    .padding(5)
+                                    .width(Length::Fixed(200.0)),
+                            ].spacing(10),
                             column![
                                  text("Title").size(12),
                                  text_input("Title", &file.title).on_input(Message::TitleChanged).padding(10),
@@ -589,20 +3172,138 @@ impl App {
                                  
                                  text("Album").size(12),
                                  text_input("Album", &file.album).on_input(Message::AlbumChanged).padding(10),
+
+                                 if fields.album_artist {
+                                     Element::from(column![
+                                         text("Album Artist").size(12),
+                                         text_input(if file.compilation { "Various Artists" } else { &file.artist }, &file.album_artist).on_input(Message::AlbumArtistChanged).padding(10),
+                                     ].spacing(10))
+                                 } else {
+                                     Element::from(column![])
+                                 },
+                                 row![
+                                     checkbox("Part of a compilation", file.compilation).on_toggle(Message::CompilationToggled),
+                                     checkbox("Explicit", file.explicit == Some(true)).on_toggle(Message::ExplicitToggled),
+                                 ].spacing(20),
+
+                                 text("Year").size(12),
+                                 text_input("Year", &year_value).on_input(Message::YearChanged).padding(10),
+                                 if let Some(err) = &self.year_error {
+                                     text(err).size(10).color(iced::Color::from_rgb(0.8, 0.3, 0.3))
+                                 } else {
+                                     text("")
+                                 },
+
+                                 text("Genre").size(12),
+                                 text_input("Genre", &file.genre).on_input(Message::GenreChanged).padding(10),
+
+                                 if fields.composer {
+                                     Element::from(column![
+                                         text("Composer").size(12),
+                                         text_input("Composer", &file.composer).on_input(Message::ComposerChanged).padding(10),
+                                     ].spacing(10))
+                                 } else {
+                                     Element::from(column![])
+                                 },
+
+                                 if fields.comment {
+                                     Element::from(column![
+                                         text("Comment").size(12),
+                                         text_editor(&self.comment_editor)
+                                             .on_action(Message::CommentEdited)
+                                             .height(Length::Fixed(80.0))
+                                             .padding(10),
+                                     ].spacing(10))
+                                 } else {
+                                     Element::from(column![])
+                                 },
+
+                                 if fields.lyrics {
+                                     Element::from(column![
+                                         text("Lyrics").size(12),
+                                         text_editor(&self.lyrics_editor)
+                                             .placeholder("No embedded lyrics")
+                                             .on_action(Message::LyricsEdited)
+                                             .height(Length::Fixed(160.0))
+                                             .padding(10),
+                                     ].spacing(10))
+                                 } else {
+                                     Element::from(column![])
+                                 },
+
+                                 if fields.bpm {
+                                     Element::from(column![
+                                         text("BPM").size(12),
+                                         row![
+                                             text_input("BPM", &bpm_value).on_input(Message::BpmChanged).padding(10),
+                                             button("Detect BPM").on_press(Message::DetectBpm).padding(10),
+                                         ].spacing(10),
+                                     ].spacing(10))
+                                 } else {
+                                     Element::from(column![])
+                                 },
+
+                                 match provider_ids_summary(file) {
+                                     Some(ids) => Element::from(text(ids).size(12).color(iced::Color::from_rgb(0.6, 0.6, 0.6))),
+                                     None => Element::from(column![]),
+                                 },
+
+                                 if file.has_suspect_encoding() {
+                                     Element::from(row![
+                                         text(format!("Some tags look garbled. Re-read them as UTF-8 or {}?", self.settings.legacy_encoding))
+                                             .size(12)
+                                             .color(iced::Color::from_rgb(0.9, 0.6, 0.1))
+                                             .width(Length::Fill),
+                                         button("Fix Encoding").on_press(Message::FixEncoding).padding(5),
+                                     ].spacing(10).align_y(iced::Alignment::Center))
+                                 } else {
+                                     Element::from(column![])
+                                 },
+
+                                 row![
+                                     button("Guess from filename").on_press(Message::GuessFromFilename).padding(5),
+                                     button("Guess from folder").on_press(Message::GuessFromFolder).padding(5),
+                                     button("Title Case").on_press(Message::NormalizeCasing(casing::Style::TitleCase, NormalizeTarget::Selected)).padding(5),
+                                     button("Clean Whitespace").on_press(Message::NormalizeCasing(casing::Style::CleanWhitespace, NormalizeTarget::Selected)).padding(5),
+                                     button("Advanced Tags").on_press(Message::ToggleAdvancedTags).padding(5),
+                                     button("Identify by Audio").on_press(Message::IdentifyByAudio).padding(5),
+                                 ].spacing(10),
+                                 row![
+                                     button("Swap Artist↔Title").on_press(Message::SwapArtistTitle).padding(5),
+                                     button("Fix Swapped Fields").on_press_maybe((!self.is_searching).then_some(Message::CheckSwappedFields)).padding(5),
+                                 ].spacing(10),
                             ].spacing(10).width(Length::Fill)
                         ].spacing(20),
 
-                        button(if self.has_unsaved_changes { "Saving..." } else { "Saved" })
-                            .on_press(Message::SavePressed)
-                            .padding(10)
-                            .width(Length::Fill)
-                            .style(move |theme: &Theme, status| {
-                                if self.has_unsaved_changes {
-                                     button::primary(theme, status)
-                                } else {
-                                     button::success(theme, status)
-                                }
-                             })
+                        row![
+                            with_shortcut(button(match (dirty, self.settings.auto_save) {
+                                (true, true) => "Saving...",
+                                (true, false) => "Save",
+                                (false, _) => "Saved",
+                            })
+                                .on_press(Message::SavePressed)
+                                .padding(10)
+                                .width(Length::Fill)
+                                .style(move |theme: &Theme, status| {
+                                    if dirty {
+                                         button::primary(theme, status)
+                                    } else {
+                                         button::success(theme, status)
+                                    }
+                                 }), "Ctrl+S"),
+                            button("Save As Copy...")
+                                .on_press(Message::SaveAsCopy)
+                                .padding(10)
+                                .style(button::secondary),
+                            button("Revert")
+                                .on_press_maybe(dirty.then_some(Message::RevertFile))
+                                .padding(10)
+                                .style(button::secondary),
+                            button("Clear Tags")
+                                .on_press(Message::ClearTagsRequested)
+                                .padding(10)
+                                .style(button::danger),
+                        ].spacing(10)
                     ].spacing(20)
                 } else {
                     column![
@@ -611,7 +3312,7 @@ impl App {
                     ].spacing(20).align_x(iced::Alignment::Center)
                 };
 
-                let editor_panel = container(editor_content)
+                let editor_panel = container(scrollable(editor_content))
                 .width(Length::FillPortion(2))
                 .padding(20)
                 .style(|_theme: &Theme| container::Style {
@@ -620,49 +3321,116 @@ impl App {
                     ..Default::default()
                 });
 
-                let search_input = text_input("Search Artist/Album...", &self.search_query)
+                let online = !self.settings.offline;
+                let search_input = text_input("Search Artist/Album... (Ctrl+F)", &self.search_query)
+                    .id(text_input::Id::new(SEARCH_INPUT_ID))
                     .on_input(Message::SearchQueryChanged)
                     .on_submit(Message::SearchPressed)
                     .padding(10);
                 
+                let album_results_list = scrollable(
+                    column(
+                        self.album_results.iter().enumerate().map(|(i, album)| {
+                            let info = match album.year {
+                                Some(year) => format!("{} - {} ({})\n{} tracks", album.artist, album.title, year, album.tracks.len()),
+                                None => format!("{} - {}\n{} tracks", album.artist, album.title, album.tracks.len()),
+                            };
+                            let source = format!("Source: {}", album.source);
+
+                            container(
+                                row![
+                                    column![
+                                        text(info).size(12).width(Length::Fill),
+                                        text(source).size(10).color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
+                                    ].width(Length::Fill).spacing(5),
+                                    button("Apply to Folder").on_press(Message::ApplyAlbum(i)).padding(5)
+                                ]
+                                .align_y(iced::Alignment::Center)
+                                .spacing(10)
+                            )
+                            .padding(5)
+                            .style(|theme: &Theme| container::Style {
+                                 background: Some(theme.extended_palette().background.weak.color.into()),
+                                 border: iced::border::Border {
+                                     color: theme.extended_palette().background.strong.color,
+                                     width: 1.0,
+                                     radius: 3.0.into(),
+                                 },
+                                 ..Default::default()
+                            })
+                            .into()
+                        }).collect::<Vec<_>>()
+                    )
+                    .spacing(10)
+                    .height(Length::Shrink)
+                ).height(Length::Fill);
+
+                let mut sources: Vec<&str> = Vec::new();
+                for res in &self.search_results {
+                    if !sources.contains(&res.source.as_str()) {
+                        sources.push(&res.source);
+                    }
+                }
+                let source_chips = row(sources.iter().map(|source| {
+                    let shown = !self.hidden_sources.contains(*source);
+                    let count = self.search_results.iter().filter(|r| r.source == *source).count();
+                    button(text(format!("{} ({})", source, count)).size(12))
+                        .on_press(Message::ToggleSourceFilter(source.to_string()))
+                        .padding([4, 8])
+                        .style(if shown { button::primary } else { button::secondary })
+                        .into()
+                })).spacing(5).wrap();
+
                 let search_results_list = scrollable(
                     column(
-                        self.search_results.iter().enumerate().map(|(i, res)| {
-                            let info = format!("{} - {}\n{}", res.artist, res.title, res.album);
+                        self.search_results.iter().enumerate().filter(|(_, res)| !self.hidden_sources.contains(&res.source)).map(|(i, res)| {
+                            let info = match res.year {
+                                Some(year) => format!("{} - {}\n{} ({})", res.artist, res.title, res.album, year),
+                                None => format!("{} - {}\n{}", res.artist, res.title, res.album),
+                            };
                             let source = format!("Source: {}", res.source);
-                            
-                            let image_preview: Element<Message> = if let Some(Some(data)) = self.search_images.get(i) {
-                                 image_widget(image_widget::Handle::from_bytes(data.clone())).width(Length::Fixed(50.0)).height(Length::Fixed(50.0)).into()
+                            let explicit_badge = if res.explicit == Some(true) {
+                                Element::from(
+                                    container(text("E").size(10).color(iced::Color::WHITE))
+                                        .padding([1, 4])
+                                        .style(|_theme: &Theme| container::Style {
+                                            background: Some(iced::Color::from_rgb(0.5, 0.5, 0.5).into()),
+                                            border: iced::border::Border { radius: 2.0.into(), ..Default::default() },
+                                            ..Default::default()
+                                        })
+                                )
                             } else {
-                                 container(text("?").size(20))
-                                    .width(Length::Fixed(50.0))
-                                    .height(Length::Fixed(50.0))
-                                    .center_x(Length::Fill)
-                                    .center_y(Length::Fill)
-                                    .style(|_theme: &Theme| container::Style {
-                                        background: Some(iced::Color::from_rgb(0.2, 0.2, 0.2).into()),
-                                        ..Default::default()
-                                    })
-                                    .into()
+                                Element::from(row![])
                             };
+                            
+                            let image_preview = thumbnail_view(self.search_images.get(i).unwrap_or(&Thumbnail::Missing));
 
                             container(
                                 row![
                                     image_preview,
                                     column![
                                         text(info).size(12).width(Length::Fill),
-                                        text(source).size(10).color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
+                                        row![
+                                            explicit_badge,
+                                            text(source).size(10).color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
+                                        ].spacing(5).align_y(iced::Alignment::Center),
                                     ].width(Length::Fill).spacing(5),
-                                    button("Apply").on_press(Message::ApplyMetadata(res.clone())).padding(5)
+                                    column![
+                                        button("Apply").on_press(Message::ApplyMetadata(res.clone())).padding(5).width(Length::Fill),
+                                        button("Apply Text").on_press(Message::ApplyTextOnly(res.clone())).padding(5).width(Length::Fill),
+                                        button("Edit").on_press(Message::StageMetadata(res.clone())).padding(5).width(Length::Fill),
+                                        button("Compare").on_press(Message::CompareResult(res.clone())).padding(5).width(Length::Fill),
+                                        button("Preview Cover").on_press_maybe((!res.cover_urls.is_empty()).then(|| Message::PreviewCover(res.cover_urls.clone()))).padding(5).width(Length::Fill),
+                                    ].spacing(5).width(Length::Fixed(110.0))
                                 ]
                                 .align_y(iced::Alignment::Center)
                                 .spacing(10)
                             )
                             .padding(5)
-                            .style(|_theme: &Theme| container::Style {
-                                 background: Some(iced::Color::from_rgb(0.15, 0.15, 0.15).into()),
+                            .style(|theme: &Theme| container::Style {
+                                 background: Some(theme.extended_palette().background.weak.color.into()),
                                  border: iced::border::Border {
-                                     color: iced::Color::from_rgb(0.3, 0.3, 0.3),
+                                     color: theme.extended_palette().background.strong.color,
                                      width: 1.0,
                                      radius: 3.0.into(),
                                  },
@@ -675,159 +3443,816 @@ impl App {
                     .height(Length::Shrink)
                 ).height(Length::Fill);
 
-                let right_panel = container(
-                    column![
-                        row![
-                             text("Online Search").size(20).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }).width(Length::Fill),
-                             button("Settings").on_press(Message::ToggleSettings).padding(5)
-                        ].align_y(iced::Alignment::Center),
+                let right_panel = container(
+                    column![
+                        row![
+                             text("Online Search").size(20).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }).width(Length::Fill),
+                             checkbox("Offline", self.settings.offline).on_toggle(Message::OfflineToggled),
+                             button("Settings").on_press(Message::ToggleSettings).padding(5)
+                        ].align_y(iced::Alignment::Center),
+
+                        row![
+                            search_input,
+                            button("Go").on_press_maybe(online.then_some(Message::SearchPressed)).padding(10),
+                            button("Refresh").on_press_maybe((online && !self.album_mode && !self.search_query.is_empty()).then_some(Message::RefreshSearch)).padding(10),
+                        ].spacing(10),
+                        checkbox("Album Search", self.album_mode).on_toggle(Message::ToggleAlbumMode),
+                        
+                        if !online {
+                            text("Offline mode: searches and cover downloads are off").size(12).color(iced::Color::from_rgb(0.9, 0.6, 0.1))
+                        } else if self.is_searching {
+                            text("Searching...")
+                        } else if self.search_from_cache && !self.album_mode {
+                            text("Showing cached results; Refresh to search again").size(12)
+                        } else {
+                            text("")
+                        },
+                        
+                        button("Auto Apply Best").on_press_maybe(self.selected_file_index.filter(|_| online).map(|_| Message::AutoApplyBest)).padding(10).width(Length::Fill),
+                        button("Batch Tag (Folder)").on_press_maybe(online.then_some(Message::BatchTag)).padding(10).width(Length::Fill),
+                        button("Fetch Missing Covers (Folder)").on_press_maybe(online.then_some(Message::FetchAllCovers)).padding(10).width(Length::Fill),
+
+                        if self.album_mode {
+                            Element::from(album_results_list)
+                        } else if sources.len() > 1 || sources.iter().any(|s| self.hidden_sources.contains(*s)) {
+                            // A hidden source needs its chip even when it's the only one, or its results can't come back
+                            column![source_chips, search_results_list].spacing(10).into()
+                        } else {
+                            search_results_list.into()
+                        }
+                    ]
+                    .spacing(20)
+                )
+                .padding(15)
+                .width(Length::FillPortion(1))
+                .style(|_theme: &Theme| container::Style {
+                    background: Some(_theme.palette().background.into()),
+                    border: iced::border::Border { color: _theme.palette().text, width: 1.0, radius: 5.0.into() },
+                    ..Default::default()
+                });
+
+                row![left_panel, editor_panel, right_panel]
+                    .spacing(10)
+                    .padding(10)
+                    .height(Length::Fill)
+                    .into()
+            }
+        };
+        
+        let mut layers = vec![content];
+
+        if self.show_settings {
+             let settings_modal = modal(
+                 column![
+                     text("Settings").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+
+                     text(format!("Profile: {}", self.settings.active_profile)).size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     text("The enabled providers, the default search query and the editor fields below belong to this profile. Switch profiles from the file list.").size(12),
+                     row![
+                         text("Editor fields").size(12),
+                         checkbox("Album artist", self.settings.editor_fields.album_artist)
+                             .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { editor_fields: settings::EditorFields { album_artist: v, ..self.settings.editor_fields }, ..self.settings.clone() }))),
+                         checkbox("Composer", self.settings.editor_fields.composer)
+                             .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { editor_fields: settings::EditorFields { composer: v, ..self.settings.editor_fields }, ..self.settings.clone() }))),
+                         checkbox("Comment", self.settings.editor_fields.comment)
+                             .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { editor_fields: settings::EditorFields { comment: v, ..self.settings.editor_fields }, ..self.settings.clone() }))),
+                         checkbox("Lyrics", self.settings.editor_fields.lyrics)
+                             .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { editor_fields: settings::EditorFields { lyrics: v, ..self.settings.editor_fields }, ..self.settings.clone() }))),
+                         checkbox("BPM", self.settings.editor_fields.bpm)
+                             .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { editor_fields: settings::EditorFields { bpm: v, ..self.settings.editor_fields }, ..self.settings.clone() }))),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text_input("New profile name", &self.new_profile_name).on_input(Message::NewProfileNameChanged).on_submit(Message::SaveProfileAs),
+                         button("Save as New Profile").on_press_maybe((!self.new_profile_name.trim().is_empty()).then_some(Message::SaveProfileAs)).padding(5),
+                         button("Delete Profile").on_press_maybe((self.settings.profiles.len() > 1).then_some(Message::DeleteProfile)).padding(5).style(button::danger),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+
+                     text("Editing").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     checkbox("Auto-save edits after one second (three for files over 100 MB)", self.settings.auto_save)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { auto_save: v, ..self.settings.clone() }))),
+                     row![
+                         text("Max embedded cover size in px (0 = original)").size(12),
+                         text_input("1200", &self.settings.max_embedded_cover.to_string())
+                             .on_input(|v| match v.trim().parse::<u32>() {
+                                 Ok(max) => Message::SettingsChanged(Box::new(settings::UserSettings { max_embedded_cover: max, ..self.settings.clone() })),
+                                 Err(_) => Message::SettingsChanged(Box::new(self.settings.clone())),
+                             })
+                             .width(Length::Fixed(80.0)),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text("Resized cover JPEG quality (1-100)").size(12),
+                         text_input("90", &self.settings.cover_jpeg_quality.to_string())
+                             .on_input(|v| match v.trim().parse::<u8>() {
+                                 Ok(quality) => Message::SettingsChanged(Box::new(settings::UserSettings { cover_jpeg_quality: quality.clamp(1, 100), ..self.settings.clone() })),
+                                 Err(_) => Message::SettingsChanged(Box::new(self.settings.clone())),
+                             })
+                             .width(Length::Fixed(60.0)),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text("Artwork").size(12),
+                         pick_list(&settings::ArtworkMode::ALL[..], Some(self.settings.artwork_mode), |v| Message::SettingsChanged(Box::new(settings::UserSettings { artwork_mode: v, ..self.settings.clone() }))),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text("Thumbnails").size(12),
+                         pick_list(&settings::ThumbnailFit::ALL[..], Some(self.settings.thumbnail_fit), |v| Message::SettingsChanged(Box::new(settings::UserSettings { thumbnail_fit: v, ..self.settings.clone() }))),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     checkbox("Minimal tags: keep only title, artist, album, year, track, genre and cover", self.settings.minimal_tags)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { minimal_tags: v, ..self.settings.clone() }))),
+                     text("Destructive: every other tag (album artist, composer, comments, lyrics, custom fields) is erased from files when they are saved.")
+                         .size(10)
+                         .color(iced::Color::from_rgb(0.8, 0.2, 0.2)),
+                     checkbox("Store MusicBrainz recording ID, Spotify ID and ISRC from applied results", self.settings.write_provider_ids)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { write_provider_ids: v, ..self.settings.clone() }))),
+                     checkbox("Set the explicit advisory tag from applied results", self.settings.write_explicit_flag)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { write_explicit_flag: v, ..self.settings.clone() }))),
+                     checkbox("Only process incomplete files in batch tagging (title, artist or album unknown)", self.settings.only_incomplete)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { only_incomplete: v, ..self.settings.clone() }))),
+                     row![
+                         text("Separator for multiple artists or genres").size(12),
+                         text_input(";", &self.settings.value_separator.to_string())
+                             .on_input(|v| match v.chars().last() {
+                                 Some(separator) => Message::SettingsChanged(Box::new(settings::UserSettings { value_separator: separator, ..self.settings.clone() })),
+                                 None => Message::SettingsChanged(Box::new(self.settings.clone())),
+                             })
+                             .width(Length::Fixed(40.0)),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text("Extra file extensions (comma-separated)").size(12),
+                         text_input("aac, mpc", &self.settings.extra_extensions)
+                             .on_input(|v| Message::SettingsChanged(Box::new(settings::UserSettings { extra_extensions: v, ..self.settings.clone() })))
+                             .width(Length::Fixed(160.0)),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     match audio::parse_extensions(&self.settings.extra_extensions).1 {
+                         rejected if rejected.is_empty() => text("Used the next time a folder is opened").size(10),
+                         rejected => text(format!("Ignored (not a format NaviTag can read): {}", rejected.join(", ")))
+                             .size(10)
+                             .color(iced::Color::from_rgb(0.9, 0.6, 0.1)),
+                     },
+                     row![
+                         text("Encoding for garbled legacy tags").size(12),
+                         pick_list(&settings::LegacyEncoding::ALL[..], Some(self.settings.legacy_encoding), |v| Message::SettingsChanged(Box::new(settings::UserSettings { legacy_encoding: v, ..self.settings.clone() }))),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text("MP3 tag version (ID3 only; other formats are unaffected)").size(12),
+                         pick_list(&settings::Id3Version::ALL[..], Some(self.settings.id3_version), |v| Message::SettingsChanged(Box::new(settings::UserSettings { id3_version: v, ..self.settings.clone() }))),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+
+                     text("Appearance").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     row![
+                         text("Theme").size(12),
+                         pick_list(&settings::ThemePref::ALL[..], Some(self.settings.theme), |v| Message::SettingsChanged(Box::new(settings::UserSettings { theme: v, ..self.settings.clone() }))),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+
+                     text("Notifications").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     row![
+                         text(format!("Show notifications for (1-{} seconds)", settings::MAX_TOAST_DURATION_SECS)).size(12),
+                         text_input("5", &self.settings.toast_duration_secs.to_string())
+                             .on_input(|v| match v.trim().parse::<u64>() {
+                                 Ok(secs) => Message::SettingsChanged(Box::new(settings::UserSettings { toast_duration_secs: secs, ..self.settings.clone() })),
+                                 Err(_) => Message::SettingsChanged(Box::new(self.settings.clone())),
+                             })
+                             .width(Length::Fixed(60.0)),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     checkbox("Keep error notifications until dismissed", self.settings.sticky_error_toasts)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { sticky_error_toasts: v, ..self.settings.clone() }))),
+
+                     text("Search").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     row![
+                         text(format!("Results per provider (1-{})", settings::MAX_RESULT_LIMIT)).size(12),
+                         text_input("10", &self.settings.result_limit.to_string())
+                             .on_input(|v| match v.trim().parse::<u32>() {
+                                 Ok(limit) => Message::SettingsChanged(Box::new(settings::UserSettings { result_limit: limit.clamp(1, settings::MAX_RESULT_LIMIT), ..self.settings.clone() })),
+                                 Err(_) => Message::SettingsChanged(Box::new(self.settings.clone())),
+                             })
+                             .width(Length::Fixed(60.0)),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text(format!("Thumbnails downloaded at once (1-{})", settings::MAX_CONCURRENT_DOWNLOADS)).size(12),
+                         text_input("4", &self.settings.concurrent_downloads.to_string())
+                             .on_input(|v| match v.trim().parse::<usize>() {
+                                 Ok(count) => Message::SettingsChanged(Box::new(settings::UserSettings { concurrent_downloads: count.clamp(1, settings::MAX_CONCURRENT_DOWNLOADS), ..self.settings.clone() })),
+                                 Err(_) => Message::SettingsChanged(Box::new(self.settings.clone())),
+                             })
+                             .width(Length::Fixed(60.0)),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text(format!("Provider timeout in seconds (1-{})", settings::MAX_PROVIDER_TIMEOUT_SECS)).size(12),
+                         text_input("10", &self.settings.provider_timeout_secs.to_string())
+                             .on_input(|v| match v.trim().parse::<u64>() {
+                                 Ok(secs) => Message::SettingsChanged(Box::new(settings::UserSettings { provider_timeout_secs: secs.clamp(1, settings::MAX_PROVIDER_TIMEOUT_SECS), ..self.settings.clone() })),
+                                 Err(_) => Message::SettingsChanged(Box::new(self.settings.clone())),
+                             })
+                             .width(Length::Fixed(60.0)),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text("Default query ({artist}, {title}, {album})").size(12),
+                         text_input(settings::DEFAULT_SEARCH_QUERY_TEMPLATE, &self.settings.search_query_template)
+                             .on_input(|v| Message::SettingsChanged(Box::new(settings::UserSettings { search_query_template: v, ..self.settings.clone() })))
+                             .width(Length::Fixed(200.0)),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text("Region (Spotify market / Apple country)").size(12),
+                         pick_list(&settings::COUNTRIES[..], Some(self.settings.country.as_str()), |v: &str| Message::SettingsChanged(Box::new(settings::UserSettings { country: v.to_string(), ..self.settings.clone() }))),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     text("Result order (most trusted first; also decides ties when auto-applying)").size(12),
+                     column(self.settings.provider_priority.iter().enumerate().map(|(i, name)| {
+                         let last = i + 1 == self.settings.provider_priority.len();
+                         row![
+                             text(format!("{}. {}", i + 1, name)).size(12).width(Length::Fixed(120.0)),
+                             button(text("Up").size(10)).on_press_maybe((i > 0).then_some(Message::MoveProvider(i, true))).padding(3),
+                             button(text("Down").size(10)).on_press_maybe((!last).then_some(Message::MoveProvider(i, false))).padding(3),
+                         ].spacing(5).align_y(iced::Alignment::Center).into()
+                     })).spacing(3),
+
+                     text("Apple Music").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     checkbox("Enable Apple Music Search", self.settings.enable_apple_music)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { enable_apple_music: v, ..self.settings.clone() }))),
+                     button("Test Connection").on_press(Message::TestConnection(api::Provider::AppleMusic)).padding(5),
+
+                     text("Deezer").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     checkbox("Enable Deezer Search", self.settings.enable_deezer)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { enable_deezer: v, ..self.settings.clone() }))),
+                     button("Test Connection").on_press(Message::TestConnection(api::Provider::Deezer)).padding(5),
+                     
+                     text("Spotify").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     checkbox("Enable Spotify Search", self.settings.enable_spotify)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { enable_spotify: v, ..self.settings.clone() }))),
+                     
+                     text("Client ID").size(12),
+                     text_input("Client ID", &self.settings.spotify_id)
+                         .on_input(|v| Message::SettingsChanged(Box::new(settings::UserSettings { spotify_id: v, ..self.settings.clone() }))),
+                     text("Client Secret").size(12),
+                     text_input("Client Secret", &self.settings.spotify_secret)
+                         .on_input(|v| Message::SettingsChanged(Box::new(settings::UserSettings { spotify_secret: v, ..self.settings.clone() }))),
+                     row![
+                         button("Test Connection").on_press(Message::TestConnection(api::Provider::Spotify)).padding(5),
+                         button("Reset Provider State").on_press(Message::ResetProvider(api::Provider::Spotify)).padding(5).style(button::secondary),
+                     ].spacing(10),
+                    
+                     text("Genius").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     checkbox("Enable Genius Search", self.settings.enable_genius)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { enable_genius: v, ..self.settings.clone() }))),
+                     text("Access Token").size(12),
+                     text_input("Genius Access Token", &self.settings.genius_token)
+                         .on_input(|v| Message::SettingsChanged(Box::new(settings::UserSettings { genius_token: v, ..self.settings.clone() })))
+                         .secure(true),
+                     button("Test Connection").on_press(Message::TestConnection(api::Provider::Genius)).padding(5),
+
+                     text("Last.fm").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     checkbox("Enable Last.fm Search", self.settings.enable_lastfm)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { enable_lastfm: v, ..self.settings.clone() }))),
+                     text("API Key").size(12),
+                     text_input("Last.fm API Key", &self.settings.lastfm_api_key)
+                         .on_input(|v| Message::SettingsChanged(Box::new(settings::UserSettings { lastfm_api_key: v, ..self.settings.clone() })))
+                         .secure(true),
+                     button("Test Connection").on_press(Message::TestConnection(api::Provider::LastFm)).padding(5),
+
+                     text("AcoustID").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     text("API Key (used by Identify by Audio; requires Chromaprint's fpcalc)").size(12),
+                     text_input("AcoustID API Key", &self.settings.acoustid_key)
+                         .on_input(|v| Message::SettingsChanged(Box::new(settings::UserSettings { acoustid_key: v, ..self.settings.clone() })))
+                         .secure(true),
+
+                     text("Network").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     row![
+                         text("HTTP proxy").size(12).width(Length::Fixed(90.0)),
+                         text_input("http://proxy:8080", &self.settings.http_proxy)
+                             .on_input(|v| Message::SettingsChanged(Box::new(settings::UserSettings { http_proxy: v, ..self.settings.clone() }))),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     row![
+                         text("HTTPS proxy").size(12).width(Length::Fixed(90.0)),
+                         text_input("http://proxy:8080", &self.settings.https_proxy)
+                             .on_input(|v| Message::SettingsChanged(Box::new(settings::UserSettings { https_proxy: v, ..self.settings.clone() }))),
+                     ].spacing(10).align_y(iced::Alignment::Center),
+                     match api::validate_proxy_url(&self.settings.http_proxy).and(api::validate_proxy_url(&self.settings.https_proxy)) {
+                         Ok(()) => text("Leave empty to use the http_proxy and https_proxy environment variables.").size(10),
+                         Err(e) => text(e).size(10).color(iced::Color::from_rgb(0.8, 0.3, 0.3)),
+                     },
+                     checkbox("Offline mode: no searches, credential tests or cover downloads", self.settings.offline)
+                         .on_toggle(|v| Message::SettingsChanged(Box::new(settings::UserSettings { offline: v, ..self.settings.clone() }))),
+
+                     text("Cover Cache").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                     button("Clear Cover Cache").on_press(Message::ClearCoverCache).padding(5),
+
+                     row![
+                         primary_button("Save & Close").on_press(Message::SaveSettings),
+                         secondary_button("Cancel").on_press(Message::ToggleSettings)
+                     ].spacing(10)
+                 ]
+                 .spacing(10)
+                 .padding(20)
+             );
+            
+            layers.push(settings_modal);
+        }
+
+        if let (true, Some(file)) = (self.show_advanced_tags, self.selected_file_index.and_then(|idx| self.files.get(idx))) {
+            let rows = column(
+                file.extra_tags.iter().enumerate().map(|(i, (key, value))| {
+                    row![
+                        text_input("Key", key)
+                            .on_input(move |v| Message::AdvancedTagKeyChanged(i, v))
+                            .padding(5)
+                            .width(Length::FillPortion(1)),
+                        text_input("Value", value)
+                            .on_input(move |v| Message::AdvancedTagValueChanged(i, v))
+                            .padding(5)
+                            .width(Length::FillPortion(2)),
+                        danger_button("Remove").on_press(Message::RemoveAdvancedTag(i)).padding(5),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center)
+                    .into()
+                }).collect::<Vec<_>>()
+            )
+            .spacing(5);
+
+            let advanced_modal = modal(
+                column![
+                    text("Advanced Tags").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text("Raw tag items not covered by the editor fields. Keys use the file's native names (e.g. COMPOSER, TXXX:MOOD).").size(12),
+                    if let Some(err) = &self.advanced_tag_error {
+                        text(err).size(12).color(iced::Color::from_rgb(0.8, 0.3, 0.3))
+                    } else {
+                        text("")
+                    },
+                    scrollable(rows).height(Length::Fixed(300.0)),
+                    row![
+                        primary_button("Add Tag").on_press(Message::AddAdvancedTag),
+                        secondary_button("Close").on_press(Message::ToggleAdvancedTags),
+                    ].spacing(10)
+                ]
+                .spacing(10)
+                .padding(20)
+                .width(Length::Fixed(600.0))
+            );
+            layers.push(advanced_modal);
+        }
+
+        if !self.pending_batch.is_empty() {
+            let rows = column(
+                self.pending_batch.iter().enumerate().map(|(i, proposal)| {
+                    let current = self.files.iter().find(|f| f.path == proposal.path);
+                    let file_name = proposal.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                    let current_text = current
+                        .map(|f| format!("Current:  {} - {} ({})", f.artist, f.title, f.album))
+                        .unwrap_or_default();
+                    let proposed_text = format!("Proposed: {} - {} ({})", proposal.result.artist, proposal.result.title, proposal.result.album);
+                    let changed = current.map(|f| {
+                        f.title != proposal.result.title || f.artist != proposal.result.artist || f.album != proposal.result.album
+                    }).unwrap_or(true);
+
+                    row![
+                        checkbox("", proposal.selected).on_toggle(move |v| Message::ToggleBatchRow(i, v)),
+                        column![
+                            text(file_name).size(14).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                            text(current_text).size(12).color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
+                            text(proposed_text).size(12).color(if changed {
+                                iced::Color::from_rgb(0.9, 0.7, 0.2)
+                            } else {
+                                iced::Color::from_rgb(0.7, 0.7, 0.7)
+                            }),
+                        ].spacing(2).width(Length::Fill),
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center)
+                    .into()
+                }).collect::<Vec<_>>()
+            )
+            .spacing(10);
+
+            let selected_count = self.pending_batch.iter().filter(|p| p.selected).count();
+            let batch_modal = modal(
+                column![
+                    text("Review Batch Results").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text("Uncheck any rows you don't want applied. Nothing is changed until you apply.").size(12),
+                    if self.batch_skipped > 0 {
+                        text(format!("{} files with complete title, artist and album were skipped.", self.batch_skipped)).size(12).color(iced::Color::from_rgb(0.7, 0.7, 0.7))
+                    } else {
+                        text("")
+                    },
+                    row![
+                        text("Match results to files").size(12),
+                        pick_list(&matching::BatchMapping::ALL[..], Some(self.batch_mapping), Message::BatchMappingChanged),
+                    ].spacing(10).align_y(iced::Alignment::Center),
+                    scrollable(rows).height(Length::Fixed(400.0)),
+                    row![
+                        primary_button(text(format!("Apply Selected ({})", selected_count))).on_press(Message::ApplyBatchSelection),
+                        secondary_button("Cancel").on_press(Message::DiscardBatch),
+                    ].spacing(10)
+                ]
+                .spacing(10)
+                .padding(20)
+                .width(Length::Fixed(700.0))
+            );
+            layers.push(batch_modal);
+        }
+
+        if self.show_duplicates {
+            let groups = column(
+                self.duplicate_groups.iter().enumerate().map(|(n, group)| {
+                    let rows = group.iter().fold(column![].spacing(4), |col, path| {
+                        let file = self.files.iter().find(|f| &f.path == path);
+                        let details = file
+                            .map(|f| format!("{} - {} · {}", f.artist, f.title, audio_player::format_duration(f.duration)))
+                            .unwrap_or_default();
+                        let path_for_toggle = path.clone();
+                        col.push(row![
+                            checkbox("", self.duplicates_selected.contains(path))
+                                .on_toggle(move |v| Message::ToggleDuplicate(path_for_toggle.clone(), v)),
+                            column![
+                                text(path.file_name().unwrap_or_default().to_string_lossy().to_string()).size(14),
+                                text(details).size(12).color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
+                            ].spacing(2),
+                        ].spacing(10).align_y(iced::Alignment::Center))
+                    });
+
+                    column![
+                        text(format!("Group {}", n + 1)).size(14).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                        rows,
+                    ].spacing(6).into()
+                }).collect::<Vec<_>>()
+            )
+            .spacing(15);
+
+            let selected_count = self.duplicates_selected.len();
+            let duplicates_modal = modal(
+                column![
+                    text("Duplicate Tracks").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text("Files with the same artist and title, or the same size and length. Tick the copies you want to remove.").size(12),
+                    scrollable(groups).height(Length::Fixed(400.0)),
+                    row![
+                        danger_button(text(format!("Delete Selected ({})", selected_count)))
+                            .on_press_maybe((selected_count > 0).then_some(Message::DeleteDuplicatesRequested)),
+                        primary_button(text(format!("Move Selected ({})...", selected_count)))
+                            .on_press_maybe((selected_count > 0).then_some(Message::MoveDuplicates)),
+                        secondary_button("Close").on_press(Message::CloseDuplicates),
+                    ].spacing(10)
+                ]
+                .spacing(10)
+                .padding(20)
+                .width(Length::Fixed(700.0))
+            );
+            layers.push(duplicates_modal);
+        }
+
+        if let Some((root, moves)) = &self.organize_plan {
+            let planned = column(
+                moves.iter().map(|planned| {
+                    let destination = planned.to.strip_prefix(root).unwrap_or(&planned.to);
+                    column![
+                        text(planned.from.file_name().unwrap_or_default().to_string_lossy().to_string()).size(14),
+                        text(format!("→ {}", destination.display())).size(12).color(iced::Color::from_rgb(0.7, 0.7, 0.7)),
+                    ].spacing(2).into()
+                }).collect::<Vec<_>>()
+            )
+            .spacing(8);
+
+            let summary = if moves.is_empty() {
+                "Every file is already where the template puts it.".to_string()
+            } else {
+                format!("{} of {} file(s) will be moved into {}. Nothing is changed until you confirm.", moves.len(), self.files.len(), root.display())
+            };
+            let organize_modal = modal(
+                column![
+                    text("Organize into Folders").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    row![
+                        text("Folders").size(12),
+                        text_input(settings::DEFAULT_ORGANIZE_TEMPLATE, &self.settings.organize_template)
+                            .on_input(Message::OrganizeTemplateChanged)
+                            .width(Length::Fill),
+                    ].spacing(10).align_y(iced::Alignment::Center),
+                    text("Use {albumartist}, {artist}, {album}, {year} and {genre}, separated by /. Names already taken get a number added.").size(10),
+                    text(summary).size(12),
+                    scrollable(planned).height(Length::Fixed(400.0)),
+                    row![
+                        primary_button(text(format!("Move {} File(s)", moves.len())))
+                            .on_press_maybe((!moves.is_empty()).then_some(Message::ConfirmOrganize)),
+                        secondary_button("Cancel").on_press(Message::CancelOrganize),
+                    ].spacing(10)
+                ]
+                .spacing(10)
+                .padding(20)
+                .width(Length::Fixed(700.0))
+            );
+            layers.push(organize_modal);
+        }
+
+        if self.confirm_delete_duplicates {
+            let overlay = modal(
+                column![
+                    text("Delete Files?").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text(format!("{} file(s) will be permanently deleted from disk. This can't be undone.", self.duplicates_selected.len())).size(16),
+                    row![
+                        danger_button("Delete").on_press(Message::ConfirmDeleteDuplicates),
+                        secondary_button("Cancel").on_press(Message::CancelDeleteDuplicates),
+                    ].spacing(20)
+                ]
+                .spacing(20)
+                .padding(30)
+                .max_width(500)
+                .align_x(iced::Alignment::Center)
+            );
+             layers.push(overlay);
+        }
+
+        if let Some(action) = &self.pending_leave {
+            let (prompt, save_label, discard_label) = match action {
+                LeaveAction::Exit => ("You have unsaved changes. Do you want to save before quitting?", "Save & Quit", "Quit without Saving"),
+                LeaveAction::BackToTitle => ("You have unsaved changes. Do you want to save before leaving the editor?", "Save & Leave", "Discard Changes"),
+                LeaveAction::ReloadFolder => ("You have unsaved changes. Do you want to save before reloading the folder?", "Save & Reload", "Discard & Reload"),
+                LeaveAction::OpenFolder(_) => ("You have unsaved changes. Do you want to save before opening another folder?", "Save & Open", "Discard & Open"),
+            };
+            let overlay = modal(
+                column![
+                    text("Unsaved Changes").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text(prompt).size(16),
+                    row![
+                        primary_button(save_label).on_press(Message::ConfirmLeave(true)),
+                        danger_button(discard_label).on_press(Message::ConfirmLeave(false)),
+                        secondary_button("Cancel").on_press(Message::CancelLeave),
+                    ].spacing(20)
+                ]
+                .spacing(20)
+                .padding(30)
+                .align_x(iced::Alignment::Center)
+            );
+             layers.push(overlay);
+        }
+
+        if self.show_cover_preview {
+            let preview: Element<Message> = match &self.cover_preview {
+                Some(data) => image_widget(image_widget::Handle::from_bytes(data.clone()))
+                    .width(Length::Fill)
+                    .height(Length::Fixed(400.0))
+                    .into(),
+                None => container(text("Downloading cover...").size(16))
+                    .width(Length::Fill)
+                    .height(Length::Fixed(400.0))
+                    .center_x(Length::Fill)
+                    .center_y(Length::Fill)
+                    .into(),
+            };
+            let size = self.cover_preview.as_ref()
+                .and_then(|data| image::io::Reader::new(std::io::Cursor::new(data)).with_guessed_format().ok()?.into_dimensions().ok())
+                .map(|(width, height)| format!("{} x {}", width, height))
+                .unwrap_or_default();
+
+            let candidates: Element<Message> = if self.cover_preview_candidates.len() > 1 {
+                row(self.cover_preview_thumbs.iter().enumerate().map(|(i, thumb)| {
+                    let selected = i == self.cover_preview_choice;
+                    button(thumbnail_view(thumb))
+                        .on_press(Message::SelectCoverCandidate(i))
+                        .padding(3)
+                        .style(move |theme: &Theme, _status| button::Style {
+                            background: None,
+                            border: iced::border::Border {
+                                color: if selected { theme.palette().primary } else { iced::Color::TRANSPARENT },
+                                width: 2.0,
+                                radius: 5.0.into(),
+                            },
+                            ..Default::default()
+                        })
+                        .into()
+                }))
+                .spacing(10)
+                .into()
+            } else {
+                column![].into()
+            };
+
+            let overlay = modal(
+                column![
+                    text("Cover Preview").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    preview,
+                    text(size).size(12),
+                    candidates,
+                    row![
+                        primary_button("Apply This Cover").on_press_maybe(self.cover_preview.as_ref().map(|_| Message::ApplyPreviewCover)),
+                        secondary_button("Close").on_press(Message::CloseCoverPreview),
+                    ].spacing(20)
+                ]
+                .spacing(20)
+                .padding(30)
+                .max_width(500)
+                .align_x(iced::Alignment::Center)
+            );
+             layers.push(overlay);
+        }
+
+        if self.show_cover_to_all_confirmation {
+            let with_art = self.files.iter().enumerate()
+                .filter(|(i, f)| Some(*i) != self.selected_file_index && f.picture_data.is_some())
+                .count();
+
+            let overlay = modal(
+                column![
+                    text("Replace Existing Artwork?").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text(format!("{} other files already have cover art. Replace it, or only fill in files without a cover?", with_art)).size(16),
+                    row![
+                        danger_button("Replace All").on_press(Message::ConfirmCoverToAll(true)),
+                        primary_button("Only Missing").on_press(Message::ConfirmCoverToAll(false)),
+                        secondary_button("Cancel").on_press(Message::CancelCoverToAll),
+                    ].spacing(20)
+                ]
+                .spacing(20)
+                .padding(30)
+                .max_width(500)
+                .align_x(iced::Alignment::Center)
+            );
+             layers.push(overlay);
+        }
+
+        if self.show_clear_tags_confirmation {
+            let file_name = self.selected_file_index
+                .and_then(|idx| self.files.get(idx))
+                .and_then(|f| f.path.file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
 
-                        row![search_input, button("Go").on_press(Message::SearchPressed).padding(10)].spacing(10),
-                        
-                        if self.is_searching { text("Searching...") } else { text("") },
-                        
-                        button("Batch Tag (Folder)").on_press(Message::BatchTag).padding(10).width(Length::Fill),
+            let overlay = modal(
+                column![
+                    text("Clear All Tags?").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text(format!("Every tag and all embedded artwork will be removed from {}. This can't be undone.", file_name)).size(16),
+                    row![
+                        danger_button("Clear Tags").on_press(Message::ClearTags),
+                        secondary_button("Cancel").on_press(Message::CancelClearTags),
+                    ].spacing(20)
+                ]
+                .spacing(20)
+                .padding(30)
+                .max_width(500)
+                .align_x(iced::Alignment::Center)
+            );
+             layers.push(overlay);
+        }
 
-                        search_results_list
-                    ]
-                    .spacing(20)
-                )
-                .padding(15)
-                .width(Length::FillPortion(1))
-                .style(|_theme: &Theme| container::Style {
-                    background: Some(_theme.palette().background.into()),
-                    border: iced::border::Border { color: _theme.palette().text, width: 1.0, radius: 5.0.into() },
-                    ..Default::default()
-                });
+        if let (Some((result, mask)), Some(file)) = (&self.compare, self.selected_file_index.and_then(|idx| self.files.get(idx))) {
+            let mask = *mask;
+            let optional = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+            let any_selected = mask.title || mask.artist || mask.album || mask.year || mask.track || mask.cover;
 
-                row![left_panel, editor_panel, right_panel]
-                    .spacing(10)
-                    .padding(10)
-                    .height(Length::Fill)
-                    .into()
-            }
-        };
-        
-        let mut layers = vec![content];
+            let rows = column![
+                row![
+                    text("").width(Length::Fixed(110.0)),
+                    text("Current").size(12).color(iced::Color::from_rgb(0.7, 0.7, 0.7)).width(Length::FillPortion(1)),
+                    text(result.source.clone()).size(12).color(iced::Color::from_rgb(0.7, 0.7, 0.7)).width(Length::FillPortion(1)),
+                ].spacing(10),
+                compare_row("Title", file.title.clone(), result.title.clone(), mask.title, move |v| Message::CompareMaskChanged(FieldMask { title: v, ..mask })),
+                compare_row("Artist", file.artist.clone(), result.artist.clone(), mask.artist, move |v| Message::CompareMaskChanged(FieldMask { artist: v, ..mask })),
+                compare_row("Album", file.album.clone(), result.album.clone(), mask.album, move |v| Message::CompareMaskChanged(FieldMask { album: v, ..mask })),
+                compare_row("Year", optional(file.year), optional(result.year), mask.year, move |v| Message::CompareMaskChanged(FieldMask { year: v, ..mask })),
+                compare_row("Track", optional(file.track), optional(result.track), mask.track, move |v| Message::CompareMaskChanged(FieldMask { track: v, ..mask })),
+                compare_row(
+                    "Cover",
+                    if file.picture_data.is_some() { "Embedded".to_string() } else { "None".to_string() },
+                    if result.cover_urls.is_empty() { "None".to_string() } else { "Available".to_string() },
+                    mask.cover,
+                    move |v| Message::CompareMaskChanged(FieldMask { cover: v, ..mask })
+                ),
+            ]
+            .spacing(10);
 
-        if self.show_settings {
-             let settings_modal = Element::from(container(
-                 column![
-                     text("Settings").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
-                     
-                     text("Apple Music").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
-                     checkbox("Enable Apple Music Search", self.settings.enable_apple_music)
-                         .on_toggle(|v| Message::SettingsChanged(settings::UserSettings { enable_apple_music: v, ..self.settings.clone() })),
-                     
-                     text("Spotify").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
-                     checkbox("Enable Spotify Search", self.settings.enable_spotify)
-                         .on_toggle(|v| Message::SettingsChanged(settings::UserSettings { enable_spotify: v, ..self.settings.clone() })),
-                     
-                     text("Client ID").size(12),
-                     text_input("Client ID", &self.settings.spotify_id)
-                         .on_input(|v| Message::SettingsChanged(settings::UserSettings { spotify_id: v, ..self.settings.clone() })),
-                     text("Client Secret").size(12),
-                     text_input("Client Secret", &self.settings.spotify_secret)
-                         .on_input(|v| Message::SettingsChanged(settings::UserSettings { spotify_secret: v, ..self.settings.clone() })),
-                    
-                     text("Genius").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
-                     checkbox("Enable Genius Search", self.settings.enable_genius)
-                         .on_toggle(|v| Message::SettingsChanged(settings::UserSettings { enable_genius: v, ..self.settings.clone() })),
-                     text("Access Token").size(12),
-                     text_input("Genius Access Token", &self.settings.genius_token)
-                         .on_input(|v| Message::SettingsChanged(settings::UserSettings { genius_token: v, ..self.settings.clone() }))
-                         .secure(true),
+            let overlay = modal(
+                column![
+                    text("Compare with Result").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text("Differences are highlighted. Tick the fields to copy into the editor; nothing is saved yet.").size(12),
+                    rows,
+                    row![
+                        primary_button("Apply Selected").on_press_maybe(any_selected.then(|| Message::ApplyFields(result.clone(), mask))),
+                        secondary_button("Cancel").on_press(Message::CloseCompare),
+                    ].spacing(20)
+                ]
+                .spacing(20)
+                .padding(30)
+                .max_width(700)
+                .align_x(iced::Alignment::Center)
+            );
+             layers.push(overlay);
+        }
 
-                     text("Last.fm").size(16).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
-                     checkbox("Enable Last.fm Search", self.settings.enable_lastfm)
-                         .on_toggle(|v| Message::SettingsChanged(settings::UserSettings { enable_lastfm: v, ..self.settings.clone() })),
-                     text("API Key").size(12),
-                     text_input("Last.fm API Key", &self.settings.lastfm_api_key)
-                         .on_input(|v| Message::SettingsChanged(settings::UserSettings { lastfm_api_key: v, ..self.settings.clone() }))
-                         .secure(true),
+        if let Some((path, result)) = &self.swap_suggestion {
+            let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let current = self.files.iter().find(|f| &f.path == path);
 
-                     row![
-                         button("Save & Close").on_press(Message::SaveSettings).padding(10),
-                         button("Cancel").on_press(Message::ToggleSettings).padding(10)
-                     ].spacing(10)
-                 ]
-                 .spacing(10)
-                 .padding(20)
-             )
-             .style(|_theme: &Theme| container::Style {
-                 background: Some(_theme.palette().background.into()),
-                 border: iced::border::Border { color: _theme.palette().text, width: 1.0, radius: 10.0.into() },
-                 shadow: iced::Shadow { color: iced::Color::BLACK, offset: iced::Vector::new(0.0, 5.0), blur_radius: 20.0 },
-                 ..Default::default()
-             })
-             .width(Length::Fill)
-             .height(Length::Fill)
-             .center_x(Length::Fill)
-             .center_y(Length::Fill)
-             .style(|_theme: &Theme| container::Style {
-                 background: Some(iced::Color::from_rgba(0.0, 0.0, 0.0, 0.5).into()),
-                 ..Default::default()
-             }));
-            
-            layers.push(settings_modal);
+            let overlay = modal(
+                column![
+                    text("Artist and Title Swapped?").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text(format!(
+                        "{} is tagged as \"{} - {}\", but {} knows it as \"{} - {}\".",
+                        file_name,
+                        current.map(|f| f.artist.as_str()).unwrap_or_default(),
+                        current.map(|f| f.title.as_str()).unwrap_or_default(),
+                        result.source,
+                        result.artist,
+                        result.title
+                    )).size(16),
+                    text("Swapping only exchanges the two fields; nothing is saved until you save.").size(12),
+                    row![
+                        primary_button("Swap").on_press(Message::SwapArtistTitle),
+                        secondary_button("Keep As Is").on_press(Message::DismissSwapSuggestion),
+                    ].spacing(20)
+                ]
+                .spacing(20)
+                .padding(30)
+                .max_width(500)
+                .align_x(iced::Alignment::Center)
+            );
+             layers.push(overlay);
+        }
+
+        if !self.read_only_pending.is_empty() {
+            let names = self.read_only_pending.iter().fold(column![].spacing(4), |col, path| {
+                col.push(text(path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()).size(14))
+            });
+
+            let overlay = modal(
+                column![
+                    text("Read-only Files").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text("These files are marked read-only, so their tags can't be written:").size(16),
+                    scrollable(names).height(Length::Shrink),
+                    text("NaviTag can try to make them writable and save again.").size(14),
+                    row![
+                        primary_button("Make Writable & Save").on_press(Message::MakeWritableAndSave),
+                        secondary_button("Cancel").on_press(Message::DismissReadOnly),
+                    ].spacing(20)
+                ]
+                .spacing(20)
+                .padding(30)
+                .max_width(500)
+                .align_x(iced::Alignment::Center)
+            );
+             layers.push(overlay);
         }
 
-        if self.show_exit_confirmation {
-            let overlay = Element::from(container(
+        if let Some(path) = &self.file_menu {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let overlay = modal(
                 column![
-                    text("Unsaved Changes").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
-                    text("You have unsaved changes. Do you want to save before quitting?").size(16),
+                    text(name).size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text(path.parent().map(|p| p.display().to_string()).unwrap_or_default()).size(12).color(iced::Color::from_rgb(0.6, 0.6, 0.6)),
                     row![
-                        button("Save & Quit").on_press(Message::ConfirmExit(true)).padding(10).style(|_theme, _status| button::Style {
-                            background: Some(iced::Color::from_rgb(0.2, 0.6, 0.2).into()),
-                            text_color: iced::Color::WHITE,
-                            border: iced::border::Border { radius: 5.0.into(), ..Default::default() },
-                            ..Default::default()
-                        }),
-                        button("Quit without Saving").on_press(Message::ConfirmExit(false)).padding(10).style(|_theme, _status| button::Style {
-                            background: Some(iced::Color::from_rgb(0.8, 0.2, 0.2).into()),
-                            text_color: iced::Color::WHITE,
-                            border: iced::border::Border { radius: 5.0.into(), ..Default::default() },
-                            ..Default::default()
-                        }),
-                        button("Cancel").on_press(Message::CancelExit).padding(10).style(|_theme, _status| button::Style {
-                            background: Some(iced::Color::from_rgb(0.4, 0.4, 0.4).into()),
-                            text_color: iced::Color::WHITE,
-                            border: iced::border::Border { radius: 5.0.into(), ..Default::default() },
-                            ..Default::default()
-                        }),
+                        primary_button("Show in File Manager").on_press(Message::RevealFile(path.clone())),
+                        secondary_button("Open Containing Folder").on_press(Message::OpenContainingFolder(path.clone())),
+                        secondary_button("Cancel").on_press(Message::CloseFileMenu),
                     ].spacing(20)
                 ]
                 .spacing(20)
                 .padding(30)
+                .max_width(500)
                 .align_x(iced::Alignment::Center)
-            )
-            .style(|_theme: &Theme| container::Style {
-                 background: Some(_theme.palette().background.into()),
-                 border: iced::border::Border { color: _theme.palette().text, width: 1.0, radius: 10.0.into() },
-                 shadow: iced::Shadow { color: iced::Color::BLACK, offset: iced::Vector::new(0.0, 5.0), blur_radius: 20.0 },
-                 ..Default::default()
-             })
-             .width(Length::Fill)
-             .height(Length::Fill)
-             .center_x(Length::Fill)
-             .center_y(Length::Fill)
-             .style(|_theme: &Theme| container::Style {
-                 background: Some(iced::Color::from_rgba(0.0, 0.0, 0.0, 0.8).into()),
-                 ..Default::default()
-             }));
+            );
+             layers.push(overlay);
+        }
+
+        if !self.save_failures.is_empty() {
+            let failures = self.save_failures.iter().fold(column![].spacing(8), |col, (path, e)| {
+                col.push(row![
+                    column![
+                        text(path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()).size(14).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                        text(e).size(12).color(iced::Color::from_rgb(0.8, 0.3, 0.3)),
+                    ].spacing(2).width(Length::Fill),
+                    button(text("Show").size(12)).on_press(Message::RevealFile(path.clone())).padding(5),
+                ].spacing(10).align_y(iced::Alignment::Center))
+            });
+
+            let overlay = modal(
+                column![
+                    text("Some Files Weren't Saved").size(24).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
+                    text(format!("{} files could not be written. Their changes are kept until you save again or revert them.", self.save_failures.len())).size(16),
+                    scrollable(failures).height(Length::Fixed(240.0)),
+                    row![
+                        primary_button("Retry Failed").on_press(Message::RetryFailedSaves),
+                        secondary_button("Close").on_press(Message::DismissSaveFailures),
+                    ].spacing(20)
+                ]
+                .spacing(20)
+                .padding(30)
+                .max_width(500)
+                .align_x(iced::Alignment::Center)
+            );
              layers.push(overlay);
         }
 
         if self.is_loading {
-             let overlay = Element::from(container(
-                 column![
-                     text("Loading...").size(24).style(|_theme: &Theme| text::Style { color: Some(iced::Color::WHITE) }),
-                     text(&self.loading_message).size(16).style(|_theme: &Theme| text::Style { color: Some(iced::Color::WHITE) })
-                 ]
-                 .spacing(10)
-                 .align_x(iced::Alignment::Center)
-             )
+             let mut content = column![
+                 text("Loading...").size(24).style(|_theme: &Theme| text::Style { color: Some(iced::Color::WHITE) }),
+                 text(&self.loading_message).size(16).style(|_theme: &Theme| text::Style { color: Some(iced::Color::WHITE) })
+             ]
+             .spacing(10)
+             .align_x(iced::Alignment::Center);
+
+             if let Some(job) = &self.batch_job {
+                 if job.total > 1 {
+                     content = content.push(
+                         text(format!("{} of {}", job.done(), job.total)).size(14).style(|_theme: &Theme| text::Style { color: Some(iced::Color::WHITE) })
+                     );
+                 }
+                 let cancel = secondary_button("Cancel");
+                 content = content.push(if job.is_cancelled() { cancel } else { cancel.on_press(Message::CancelBatch) });
+             }
+
+             let overlay = Element::from(container(content)
              .width(Length::Fill)
              .height(Length::Fill)
              .center_x(Length::Fill)
@@ -841,55 +4266,487 @@ impl App {
         
         stack(vec![
             stack(layers).into(),
-            self.toast_manager.view()
+            self.toast_manager.view(Message::DismissToast)
         ]).into()
     }
 
     fn theme(&self) -> Theme {
-        Theme::Dark
+        match self.settings.theme {
+            settings::ThemePref::Light => Theme::Light,
+            settings::ThemePref::Dark => Theme::Dark,
+            settings::ThemePref::System => {
+                if self.system_prefers_light { Theme::Light } else { Theme::Dark }
+            }
+        }
+    }
+}
+
+/// Cache key for a search: case and extra whitespace don't change the results.
+fn normalize_query(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Copies the provider IDs and explicit flag from a result, as far as Settings allow.
+fn apply_optional_fields(file: &mut audio::AudioFile, result: &api::MetadataResult, settings: &settings::UserSettings) {
+    if settings.write_provider_ids {
+        apply_provider_ids(file, result);
+    }
+    if settings.write_explicit_flag {
+        match result.explicit {
+            Some(true) => file.explicit = Some(true),
+            // "Not explicit" isn't the same as a clean edit, so only drop a stale explicit flag
+            Some(false) if file.explicit == Some(true) => file.explicit = None,
+            _ => {}
+        }
+    }
+}
+
+/// Copies the IDs a result carries onto the file. IDs the provider didn't return are kept,
+/// so applying a Deezer match doesn't drop an MBID written by Picard.
+fn apply_provider_ids(file: &mut audio::AudioFile, result: &api::MetadataResult) {
+    if let Some(id) = &result.musicbrainz_id {
+        file.musicbrainz_id = Some(id.clone());
+    }
+    if let Some(id) = &result.spotify_id {
+        file.spotify_id = Some(id.clone());
+    }
+    if let Some(isrc) = &result.isrc {
+        file.isrc = Some(isrc.clone());
+    }
+}
+
+/// One-line list of the provider IDs stored in a file, or None when it has none.
+fn provider_ids_summary(file: &audio::AudioFile) -> Option<String> {
+    let ids: Vec<String> = [("MBID", &file.musicbrainz_id), ("Spotify", &file.spotify_id), ("ISRC", &file.isrc)]
+        .into_iter()
+        .filter_map(|(label, id)| id.as_ref().map(|id| format!("{}: {}", label, id)))
+        .collect();
+    if ids.is_empty() { None } else { Some(ids.join(" · ")) }
+}
+
+/// Default online search query for a file, built from the template's {artist}, {title}
+/// and {album} tokens. Unknown or empty fields are left out rather than searched for.
+fn search_query_for(file: &audio::AudioFile, template: &str) -> String {
+    let known = |value: &str| {
+        if audio::is_placeholder(value) { String::new() } else { value.to_string() }
+    };
+    let query = template
+        .replace("{artist}", &known(&file.artist))
+        .replace("{title}", &known(&file.title))
+        .replace("{album}", &known(&file.album));
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if query.is_empty() { known(&file.title) } else { query }
+}
+
+/// Centers `content` in a bordered card over a dimmed backdrop. Every dialog in `view` is
+/// built with this.
+fn modal<'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    let card = container(content).style(|theme: &Theme| container::Style {
+        background: Some(theme.palette().background.into()),
+        border: iced::border::Border { color: theme.palette().text, width: 1.0, radius: 10.0.into() },
+        shadow: iced::Shadow { color: iced::Color::BLACK, offset: iced::Vector::new(0.0, 5.0), blur_radius: 20.0 },
+        ..Default::default()
+    });
+
+    container(card)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .style(|_theme: &Theme| container::Style {
+            background: Some(iced::Color::from_rgba(0.0, 0.0, 0.0, 0.7).into()),
+            ..Default::default()
+        })
+        .into()
+}
+
+/// A dialog button filled with `color`, faded while it has nothing to do.
+fn dialog_button<'a>(label: impl Into<Element<'a, Message>>, color: iced::Color) -> iced::widget::Button<'a, Message> {
+    button(label).padding(10).style(move |_theme, status| button::Style {
+        background: Some(if status == button::Status::Disabled { color.scale_alpha(0.5) } else { color }.into()),
+        text_color: iced::Color::WHITE,
+        border: iced::border::Border { radius: 5.0.into(), ..Default::default() },
+        ..Default::default()
+    })
+}
+
+/// The dialog's main action.
+fn primary_button<'a>(label: impl Into<Element<'a, Message>>) -> iced::widget::Button<'a, Message> {
+    dialog_button(label, iced::Color::from_rgb(0.2, 0.6, 0.2))
+}
+
+/// Cancel, Close and other ways out of a dialog.
+fn secondary_button<'a>(label: impl Into<Element<'a, Message>>) -> iced::widget::Button<'a, Message> {
+    dialog_button(label, iced::Color::from_rgb(0.4, 0.4, 0.4))
+}
+
+/// An action that deletes or overwrites something.
+fn danger_button<'a>(label: impl Into<Element<'a, Message>>) -> iced::widget::Button<'a, Message> {
+    dialog_button(label, iced::Color::from_rgb(0.8, 0.2, 0.2))
+}
+
+/// One line of the compare dialog: a checkbox, the file's value and the result's value,
+/// the latter highlighted when they differ.
+fn compare_row<'a>(label: &'a str, current: String, proposed: String, checked: bool, on_toggle: impl Fn(bool) -> Message + 'a) -> Element<'a, Message> {
+    let differs = current != proposed;
+    row![
+        checkbox(label, checked).on_toggle(on_toggle).width(Length::Fixed(110.0)),
+        text(current).size(14).width(Length::FillPortion(1)),
+        text(proposed).size(14).width(Length::FillPortion(1)).color(if differs {
+            iced::Color::from_rgb(0.9, 0.7, 0.2)
+        } else {
+            iced::Color::from_rgb(0.7, 0.7, 0.7)
+        }),
+    ]
+    .spacing(10)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+/// The thumbnail image, or a placeholder: "..." while it downloads, "?" when there is none
+/// and "!" when the download kept failing.
+fn thumbnail_view<'a>(thumbnail: &Thumbnail) -> Element<'a, Message> {
+    let placeholder = match thumbnail {
+        Thumbnail::Loaded(data) => {
+            return image_widget(image_widget::Handle::from_bytes(data.clone())).width(Length::Fixed(50.0)).height(Length::Fixed(50.0)).into();
+        }
+        Thumbnail::Loading => text("...").size(14).color(iced::Color::from_rgb(0.6, 0.6, 0.6)),
+        Thumbnail::Missing => text("?").size(20),
+        Thumbnail::Failed => text("!").size(20).color(iced::Color::from_rgb(0.9, 0.6, 0.1)),
+    };
+
+    container(placeholder)
+        .center_x(Length::Fixed(50.0))
+        .center_y(Length::Fixed(50.0))
+        .style(|theme: &Theme| container::Style {
+            background: Some(theme.extended_palette().background.strong.color.into()),
+            ..Default::default()
+        })
+        .into()
+}
+
+/// Read-only summary such as "FLAC · 44.1kHz · Stereo · 1011 kbps · 3:45".
+fn format_properties(file: &audio::AudioFile) -> String {
+    let mut parts = vec![file.format.clone()];
+
+    if let Some(rate) = file.sample_rate {
+        if rate % 1000 == 0 {
+            parts.push(format!("{}kHz", rate / 1000));
+        } else {
+            parts.push(format!("{:.1}kHz", rate as f32 / 1000.0));
+        }
+    }
+    match file.channels {
+        Some(1) => parts.push("Mono".to_string()),
+        Some(2) => parts.push("Stereo".to_string()),
+        Some(n) => parts.push(format!("{} ch", n)),
+        None => {}
+    }
+    if let Some(bitrate) = file.bitrate {
+        parts.push(format!("{} kbps", bitrate));
+    }
+    parts.push(audio_player::format_duration(file.duration));
+
+    parts.join(" · ")
+}
+
+fn handle_key_press(key: iced::keyboard::Key, _modifiers: iced::keyboard::Modifiers) -> Option<Message> {
+    use iced::keyboard::{key::Named, Key};
+
+    match key {
+        Key::Named(Named::ArrowUp) => Some(Message::SelectPrev),
+        Key::Named(Named::ArrowDown) => Some(Message::SelectNext),
+        _ => None,
+    }
+}
+
+/// Ctrl (Cmd on macOS) shortcuts. Unlike the arrow keys these also fire while a text
+/// field has focus, so Ctrl+S works in the middle of an edit.
+fn handle_shortcut(event: iced::Event, _status: iced::event::Status, _window: iced::window::Id) -> Option<Message> {
+    use iced::keyboard::{self, Key};
+
+    let iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) = event else {
+        return None;
+    };
+    if !modifiers.command() {
+        return None;
+    }
+
+    match key.as_ref() {
+        Key::Character(c) if c.eq_ignore_ascii_case("s") && modifiers.shift() => Some(Message::SaveAll),
+        Key::Character(c) if c.eq_ignore_ascii_case("s") => Some(Message::SavePressed),
+        Key::Character(c) if c.eq_ignore_ascii_case("f") => Some(Message::FocusSearch),
+        _ => None,
     }
 }
 
+/// Shows a button's keyboard shortcut when hovering it.
+fn with_shortcut<'a>(content: impl Into<Element<'a, Message>>, shortcut: &'a str) -> Element<'a, Message> {
+    tooltip(
+        content,
+        container(text(shortcut).size(12)).padding(5).style(container::rounded_box),
+        tooltip::Position::Bottom,
+    )
+    .into()
+}
+
+async fn detect_bpm(path: PathBuf) -> Result<u32, String> {
+    tokio::task::spawn_blocking(move || bpm::detect_bpm(&path))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Moves each file into `target`, numbering the name instead of replacing a file already there.
+/// Returns the files moved and a message for each one that failed.
+async fn move_duplicates(paths: HashSet<PathBuf>, target: PathBuf) -> (HashSet<PathBuf>, Vec<String>) {
+    tokio::task::spawn_blocking(move || {
+        let mut moved = HashSet::new();
+        let mut failed = Vec::new();
+        for path in paths {
+            // Each move lands before the next name is picked, so the disk check alone is enough
+            let to = organizer::free_destination(&target, &path, &HashSet::new());
+            match organizer::execute(&organizer::PlannedMove { from: path.clone(), to }) {
+                Ok(()) => {
+                    moved.insert(path);
+                }
+                Err(e) => failed.push(format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), e)),
+            }
+        }
+        (moved, failed)
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Carries out the organizer's moves in order, returning each one with its outcome.
+async fn organize_files(moves: Vec<organizer::PlannedMove>) -> Vec<(organizer::PlannedMove, Result<(), String>)> {
+    tokio::task::spawn_blocking(move || {
+        moves.into_iter().map(|planned| {
+            let result = organizer::execute(&planned);
+            (planned, result)
+        }).collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
 async fn pick_folder() -> Option<PathBuf> {
     rfd::AsyncFileDialog::new().pick_folder().await.map(|h| h.path().to_path_buf())
 }
 
-async fn load_files(path: PathBuf) -> Vec<audio::AudioFile> {
-    tokio::task::spawn_blocking(move || audio::scan_folder(&path))
+/// Save dialog for a tagged copy, opening in a `tagged/` subfolder when there is one.
+async fn pick_copy_path(source: PathBuf) -> Option<PathBuf> {
+    let folder = source.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+    let tagged = folder.join("tagged");
+    let start = if tagged.is_dir() { tagged } else { folder };
+
+    rfd::AsyncFileDialog::new()
+        .set_directory(start)
+        .set_file_name(source.file_name().unwrap_or_default().to_string_lossy())
+        .save_file()
+        .await
+        .map(|h| h.path().to_path_buf())
+}
+
+async fn pick_export_path() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("CSV", &["csv"])
+        .add_filter("JSON", &["json"])
+        .set_file_name("metadata.csv")
+        .save_file()
+        .await
+        .map(|h| h.path().to_path_buf())
+}
+
+async fn pick_import_path() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("Metadata", &["csv", "json"])
+        .pick_file()
+        .await
+        .map(|h| h.path().to_path_buf())
+}
+
+async fn load_files(path: PathBuf, separator: char, extra: Vec<String>) -> (Vec<audio::AudioFile>, Vec<(PathBuf, String)>) {
+    tokio::task::spawn_blocking(move || audio::scan_folder(&path, separator, &extra))
         .await
         .unwrap_or_default()
 }
 
-async fn perform_search(query: String) -> Result<Vec<api::MetadataResult>, String> {
-    api::apple_music::search(&query).await
+/// Applies the embedded cover size limit off the UI thread.
+/// Falls back to the original bytes if the resize task fails.
+async fn prepare_cover(bytes: Vec<u8>, max_dimension: u32, quality: u8) -> Vec<u8> {
+    let original = bytes.clone();
+    tokio::task::spawn_blocking(move || audio::shrink_cover(bytes, max_dimension, quality))
+        .await
+        .unwrap_or(original)
+}
+
+async fn decode_thumbnail(data: Vec<u8>, fit: settings::ThumbnailFit) -> Option<Vec<u8>> {
+    let _permit = THUMBNAIL_DECODES.acquire().await.ok()?;
+    tokio::task::spawn_blocking(move || audio::make_thumbnail(&data, audio::LIST_THUMBNAIL_SIZE, fit))
+        .await
+        .ok()?
+        .ok()
+}
+
+/// Searches with each file's query and downloads the first cover found, a few files at a time.
+/// Files not started before the job is cancelled are left out of the results.
+async fn fetch_covers(jobs: Vec<(PathBuf, String)>, settings: settings::UserSettings, job: BatchJob) -> Vec<(PathBuf, Option<Vec<u8>>)> {
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_COVER_FETCHES));
+    let mut set = tokio::task::JoinSet::new();
+
+    for (path, query) in jobs {
+        let semaphore = semaphore.clone();
+        let settings = settings.clone();
+        let job = job.clone();
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok();
+            if job.is_cancelled() {
+                return None;
+            }
+            let (max_cover, quality) = (settings.max_embedded_cover, settings.cover_jpeg_quality());
+            let cover_url = api::search_all(query, settings).await
+                .results
+                .into_iter()
+                .find_map(|r| r.cover_urls.into_iter().next());
+            let cover = match cover_url {
+                Some(url) => match download_image(Some(url)).await {
+                    Ok(bytes) => Some(prepare_cover(bytes, max_cover, quality).await),
+                    Err(_) => None,
+                },
+                None => None,
+            };
+            job.advance();
+            Some((path, cover))
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = set.join_next().await {
+        if let Ok(Some(result)) = joined {
+            results.push(result);
+        }
+    }
+    results
+}
+
+/// Writes each file in turn, stopping at the next file once the job is cancelled.
+async fn save_files(files: Vec<audio::AudioFile>, options: audio::SaveOptions, job: BatchJob) -> Vec<(PathBuf, Result<(), String>)> {
+    tokio::task::spawn_blocking(move || {
+        let mut results = Vec::new();
+        for file in files {
+            if job.is_cancelled() {
+                break;
+            }
+            let result = file.save(options);
+            job.advance();
+            results.push((file.path, result));
+        }
+        results
+    })
+    .await
+    .unwrap_or_default()
+}
+
+async fn fetch_cover_bytes(url: &str) -> Result<Vec<u8>, String> {
+    if let Some(bytes) = cover_cache::get(url) {
+        return Ok(bytes);
+    }
+    if api::is_offline() {
+        return Err(api::OFFLINE_ERROR.to_string());
+    }
+
+    let bytes = api::http_client().get(url).send().await.map_err(|e| e.to_string())?
+        .error_for_status().map_err(|e| e.to_string())?
+        .bytes().await.map_err(|e| e.to_string())?
+        .to_vec();
+    // Writing the entry and evicting old ones touches the disk, so keep it off the executor
+    let (url, cached) = (url.to_string(), bytes.clone());
+    tokio::task::spawn_blocking(move || cover_cache::put(&url, &cached));
+    Ok(bytes)
 }
 
 async fn download_image(url: Option<String>) -> Result<Vec<u8>, String> {
     if let Some(url) = url {
-        let bytes = reqwest::get(&url).await.map_err(|e| e.to_string())?
-            .bytes().await.map_err(|e| e.to_string())?;
-        Ok(bytes.to_vec())
+        fetch_cover_bytes(&url).await
     } else {
         Err("No URL provided".to_string())
     }
 }
 
-async fn download_thumbnail(url: Option<String>) -> Result<Vec<u8>, String> {
+/// Shows the file selected in the system file manager. Where the file manager can't
+/// highlight a file (some Linux desktops), falls back to opening its folder and returns false.
+async fn reveal_in_file_manager(path: PathBuf) -> Result<bool, String> {
+    tokio::task::spawn_blocking(move || match opener::reveal(&path) {
+        Ok(()) => Ok(true),
+        Err(reveal_error) => match path.parent() {
+            Some(folder) => opener::open(folder).map(|()| false).map_err(|_| reveal_error.to_string()),
+            None => Err(reveal_error.to_string()),
+        },
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+async fn open_containing_folder(path: PathBuf) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let folder = path.parent().ok_or_else(|| format!("{} has no containing folder", path.display()))?;
+        opener::open(folder).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Thumbnails are small and shown in bulk, so a dropped connection is retried a couple
+/// of times before the slot is marked as failed.
+const THUMBNAIL_ATTEMPTS: u32 = 3;
+
+/// Side of the cover thumbnails next to search results and cover candidates.
+const RESULT_THUMBNAIL_SIZE: u32 = 50;
+
+/// Shared by every thumbnail download so a search with many results doesn't open dozens
+/// of connections at once. Replaced when the setting changes; downloads already waiting
+/// finish under the old limit.
+static DOWNLOAD_PERMITS: RwLock<Option<Arc<tokio::sync::Semaphore>>> = RwLock::new(None);
+
+fn set_download_limit(limit: usize) {
+    if let Ok(mut permits) = DOWNLOAD_PERMITS.write() {
+        *permits = Some(Arc::new(tokio::sync::Semaphore::new(limit.max(1))));
+    }
+}
+
+fn download_permits() -> Arc<tokio::sync::Semaphore> {
+    if let Some(permits) = DOWNLOAD_PERMITS.read().ok().and_then(|permits| permits.clone()) {
+        return permits;
+    }
+    set_download_limit(settings::DEFAULT_CONCURRENT_DOWNLOADS);
+    DOWNLOAD_PERMITS.read().ok().and_then(|permits| permits.clone())
+        .unwrap_or_else(|| Arc::new(tokio::sync::Semaphore::new(settings::DEFAULT_CONCURRENT_DOWNLOADS)))
+}
+
+async fn download_thumbnail(url: Option<String>, fit: settings::ThumbnailFit) -> Result<Vec<u8>, String> {
      if let Some(url) = url {
-        let bytes = reqwest::get(&url).await.map_err(|e| e.to_string())?
-            .bytes().await.map_err(|e| e.to_string())?
-            .to_vec();
+        let mut attempt = 0;
+        let permits = download_permits();
+        let bytes = loop {
+            // Held for one attempt only, so the backoff wait doesn't block other downloads
+            let permit = permits.acquire().await.map_err(|e| e.to_string())?;
+            let fetched = fetch_cover_bytes(&url).await;
+            drop(permit);
+            match fetched {
+                Ok(bytes) => break bytes,
+                Err(e) if attempt + 1 >= THUMBNAIL_ATTEMPTS => return Err(e),
+                Err(_) => {
+                    tokio::time::sleep(api::backoff_delay(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        };
 
-        tokio::task::spawn_blocking(move || {
-            let img = image::load_from_memory(&bytes).map_err(|e: image::ImageError| e.to_string())?;
-            let thumbnail = img.resize_to_fill(50, 50, image::imageops::FilterType::Triangle);
-            
-            let mut buf = std::io::Cursor::new(Vec::new());
-            thumbnail.write_to(&mut buf, image::ImageOutputFormat::Png)
-                .map_err(|e: image::ImageError| e.to_string())?;
-            
-            Ok::<Vec<u8>, String>(buf.into_inner())
-        }).await.map_err(|e| format!("Task join error: {}", e))?
+        tokio::task::spawn_blocking(move || audio::make_thumbnail(&bytes, RESULT_THUMBNAIL_SIZE, fit))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
     } else {
         Err("No URL provided".to_string())
     }
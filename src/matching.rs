@@ -0,0 +1,250 @@
+use crate::api::MetadataResult;
+use crate::audio::AudioFile;
+
+/// Minimum score for a result to be applied without the user picking it.
+pub const CONFIDENT_MATCH: f32 = 0.75;
+
+/// How batch results are assigned to the files in the folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatchMapping {
+    /// First result to the first file, and so on
+    InOrder,
+    /// Each result to the file whose name resembles it most
+    Filename,
+    #[default]
+    TrackNumber,
+}
+
+impl BatchMapping {
+    pub const ALL: [BatchMapping; 3] = [BatchMapping::InOrder, BatchMapping::Filename, BatchMapping::TrackNumber];
+}
+
+impl std::fmt::Display for BatchMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            BatchMapping::InOrder => "Apply in order",
+            BatchMapping::Filename => "Match by filename",
+            BatchMapping::TrackNumber => "Match by track number",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+fn normalize(value: &str) -> String {
+    value
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn bigrams(value: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = value.chars().collect();
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// Dice coefficient over character bigrams of the normalized strings, from 0.0 to 1.0.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let a = normalize(a);
+    let b = normalize(b);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+
+    let a_grams = bigrams(&a);
+    let mut b_grams = bigrams(&b);
+    if a_grams.is_empty() || b_grams.is_empty() {
+        return 0.0;
+    }
+
+    let total = a_grams.len() + b_grams.len();
+    let mut shared = 0;
+    for gram in a_grams {
+        if let Some(pos) = b_grams.iter().position(|g| *g == gram) {
+            b_grams.swap_remove(pos);
+            shared += 1;
+        }
+    }
+
+    (2 * shared) as f32 / total as f32
+}
+
+fn is_known(value: &str) -> bool {
+    !crate::audio::is_placeholder(value)
+}
+
+/// How well a search result matches a file's current tags, from 0.0 to 1.0.
+/// Fields the file doesn't know yet are left out of the weighting.
+pub fn score(file: &AudioFile, result: &MetadataResult) -> f32 {
+    let fields = [
+        (file.title.as_str(), result.title.as_str(), 0.5),
+        (file.artist.as_str(), result.artist.as_str(), 0.35),
+        (file.album.as_str(), result.album.as_str(), 0.15),
+    ];
+
+    let mut total_weight = 0.0;
+    let mut total = 0.0;
+    for (current, proposed, weight) in fields {
+        if is_known(current) {
+            total_weight += weight;
+            total += weight * similarity(current, proposed);
+        }
+    }
+
+    if total_weight == 0.0 { 0.0 } else { total / total_weight }
+}
+
+/// How much better the swapped reading has to score before artist and title are reported
+/// as reversed, so a title that happens to resemble the artist doesn't trigger it.
+const SWAP_MARGIN: f32 = 0.25;
+
+/// A result suggesting the file's artist and title are reversed: its artist matches the
+/// file's title and its title matches the file's artist, clearly better than the other way round.
+pub fn swapped_match<'a>(file: &AudioFile, results: &'a [MetadataResult]) -> Option<&'a MetadataResult> {
+    if !is_known(&file.artist) || !is_known(&file.title) {
+        return None;
+    }
+    results
+        .iter()
+        .map(|r| {
+            let straight = (similarity(&file.title, &r.title) + similarity(&file.artist, &r.artist)) / 2.0;
+            let swapped = (similarity(&file.title, &r.artist) + similarity(&file.artist, &r.title)) / 2.0;
+            (r, swapped, straight)
+        })
+        .filter(|(_, swapped, straight)| *swapped >= CONFIDENT_MATCH && *swapped >= straight + SWAP_MARGIN)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(r, _, _)| r)
+}
+
+/// The highest-scoring result and its score. On a tie the earlier result wins, so the
+/// provider priority that orders search results also breaks ties here.
+pub fn best_match<'a>(file: &AudioFile, results: &'a [MetadataResult]) -> Option<(&'a MetadataResult, f32)> {
+    results
+        .iter()
+        .map(|r| (r, score(file, r)))
+        .fold(None, |best, candidate| match best {
+            Some(best) if best.1 >= candidate.1 => Some(best),
+            _ => Some(candidate),
+        })
+}
+
+/// Pairs batch results with files using the chosen mapping. Returns (file index, result)
+/// pairs in file order.
+pub fn pair(mapping: BatchMapping, files: &[&AudioFile], results: Vec<MetadataResult>) -> Vec<(usize, MetadataResult)> {
+    match mapping {
+        BatchMapping::InOrder => results.into_iter().take(files.len()).enumerate().collect(),
+        BatchMapping::Filename => pair_by_filename(files, results),
+        BatchMapping::TrackNumber => pair_by_track(files, results),
+    }
+}
+
+/// How much a file name looks like a result, trying both "artist title" and the bare title.
+fn filename_score(file: &AudioFile, result: &MetadataResult) -> f32 {
+    let stem = file.path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    similarity(&stem, &format!("{} {}", result.artist, result.title)).max(similarity(&stem, &result.title))
+}
+
+/// Pairs files and results greedily, best-matching file name first, so each file and
+/// each result is used at most once. Pairs scoring below `CONFIDENT_MATCH` are not made;
+/// those files are left unpaired rather than given a result that only looks a bit alike.
+pub fn pair_by_filename(files: &[&AudioFile], results: Vec<MetadataResult>) -> Vec<(usize, MetadataResult)> {
+    let mut candidates = Vec::new();
+    for (f, file) in files.iter().enumerate() {
+        for (r, result) in results.iter().enumerate() {
+            candidates.push((filename_score(file, result), f, r));
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut remaining: Vec<Option<MetadataResult>> = results.into_iter().map(Some).collect();
+    let mut file_used = vec![false; files.len()];
+    let mut pairs = Vec::new();
+    for (score, f, r) in candidates {
+        if score < CONFIDENT_MATCH {
+            break;
+        }
+        if file_used[f] || remaining[r].is_none() {
+            continue;
+        }
+        if let Some(result) = remaining[r].take() {
+            file_used[f] = true;
+            pairs.push((f, result));
+        }
+    }
+
+    pairs.sort_by_key(|(idx, _)| *idx);
+    pairs
+}
+
+/// Pairs batch results with files by track number, so file 1 gets track 1 whatever order
+/// the provider returned. Files without a usable track number take the leftover results
+/// in list order. Returns (file index, result) pairs in file order.
+pub fn pair_by_track(files: &[&AudioFile], results: Vec<MetadataResult>) -> Vec<(usize, MetadataResult)> {
+    let mut remaining: Vec<Option<MetadataResult>> = results.into_iter().map(Some).collect();
+    let mut pairs = Vec::new();
+    let mut unpaired = Vec::new();
+
+    for (idx, file) in files.iter().enumerate() {
+        let found = file.track.and_then(|track| {
+            remaining
+                .iter()
+                .position(|r| r.as_ref().is_some_and(|r| r.track == Some(track)))
+        });
+        match found.and_then(|pos| remaining[pos].take()) {
+            Some(result) => pairs.push((idx, result)),
+            None => unpaired.push(idx),
+        }
+    }
+
+    let mut leftovers = remaining.into_iter().flatten();
+    for idx in unpaired {
+        if let Some(result) = leftovers.next() {
+            pairs.push((idx, result));
+        }
+    }
+
+    pairs.sort_by_key(|(idx, _)| *idx);
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str) -> AudioFile {
+        AudioFile { path: path.into(), ..AudioFile::default() }
+    }
+
+    fn result(artist: &str, title: &str) -> MetadataResult {
+        MetadataResult {
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: String::new(),
+            year: None,
+            track: None,
+            cover_urls: Vec::new(),
+            source: String::new(),
+            musicbrainz_id: None,
+            spotify_id: None,
+            isrc: None,
+            explicit: None,
+        }
+    }
+
+    #[test]
+    fn filename_pairing_leaves_poor_matches_unpaired() {
+        let files = [file("/album/Daft Punk - One More Time.mp3"), file("/album/track07.mp3")];
+        let results = vec![result("Daft Punk", "Aerodynamic"), result("Daft Punk", "One More Time")];
+        let pairs = pair_by_filename(&files.iter().collect::<Vec<_>>(), results);
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].0, 0);
+        assert_eq!(pairs[0].1.title, "One More Time");
+    }
+}
@@ -0,0 +1,113 @@
+use crate::audio::AudioFile;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// One exported file's tags. Cover art is deliberately left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataRow {
+    pub path: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub year: Option<u32>,
+    pub track: Option<u32>,
+    pub genre: String,
+}
+
+impl MetadataRow {
+    fn from_file(file: &AudioFile, base_dir: Option<&Path>) -> Self {
+        let path = base_dir
+            .and_then(|base| file.path.strip_prefix(base).ok())
+            .unwrap_or(&file.path)
+            .to_string_lossy()
+            .to_string();
+
+        Self {
+            path,
+            title: file.title.clone(),
+            artist: file.artist.clone(),
+            album: file.album.clone(),
+            year: file.year,
+            track: file.track,
+            genre: file.genre.clone(),
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("json"))
+        .unwrap_or(false)
+}
+
+/// Writes every file's tags to `target` as JSON or CSV depending on its extension.
+/// Paths are written relative to `base_dir` when possible.
+pub fn export(files: &[AudioFile], base_dir: Option<&Path>, target: &Path) -> Result<usize, String> {
+    let rows: Vec<MetadataRow> = files.iter().map(|f| MetadataRow::from_file(f, base_dir)).collect();
+
+    if is_json(target) {
+        let content = serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())?;
+        fs::write(target, content).map_err(|e| e.to_string())?;
+    } else {
+        let mut writer = csv::Writer::from_path(target).map_err(|e| e.to_string())?;
+        for row in &rows {
+            writer.serialize(row).map_err(|e| e.to_string())?;
+        }
+        writer.flush().map_err(|e| e.to_string())?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Reads rows previously written by `export` (or edited in a spreadsheet).
+pub fn import(source: &Path) -> Result<Vec<MetadataRow>, String> {
+    if is_json(source) {
+        let content = fs::read_to_string(source).map_err(|e| e.to_string())?;
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    } else {
+        let mut reader = csv::Reader::from_path(source).map_err(|e| e.to_string())?;
+        reader
+            .deserialize()
+            .collect::<Result<Vec<MetadataRow>, _>>()
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Applies rows to loaded files, matching on relative path first and then on file name.
+/// Returns the indices of updated files and the paths of rows that matched nothing.
+pub fn apply_rows(files: &mut [AudioFile], base_dir: Option<&Path>, rows: Vec<MetadataRow>) -> (Vec<usize>, Vec<String>) {
+    let mut updated = Vec::new();
+    let mut unmatched = Vec::new();
+
+    for row in rows {
+        let row_path = Path::new(&row.path);
+        let position = files
+            .iter()
+            .position(|f| base_dir.and_then(|base| f.path.strip_prefix(base).ok()) == Some(row_path))
+            .or_else(|| {
+                let row_name = row_path.file_name()?;
+                files.iter().position(|f| f.path.file_name() == Some(row_name))
+            });
+
+        match position {
+            Some(idx) => {
+                let file = &mut files[idx];
+                file.title = row.title;
+                file.artist = row.artist;
+                file.album = row.album;
+                file.year = row.year;
+                file.track = row.track;
+                file.genre = row.genre;
+                file.dirty = true;
+                if !updated.contains(&idx) {
+                    updated.push(idx);
+                }
+            }
+            None => unmatched.push(row.path),
+        }
+    }
+
+    (updated, unmatched)
+}
@@ -0,0 +1,167 @@
+use crate::audio::{self, AudioFile};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Characters Windows, macOS or Linux refuse in a file or folder name.
+const RESERVED: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// One file the organizer will move.
+#[derive(Debug, Clone)]
+pub struct PlannedMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+fn known_or(value: &str, fallback: &str) -> String {
+    if audio::is_placeholder(value) {
+        fallback.to_string()
+    } else {
+        value.trim().to_string()
+    }
+}
+
+/// Makes a tag value safe as one path component: reserved and control characters become
+/// "_", and trailing dots and spaces, which Windows drops, are trimmed.
+fn sanitize(component: &str) -> String {
+    let cleaned: String = component
+        .chars()
+        .map(|c| if RESERVED.contains(&c) || c.is_control() { '_' } else { c })
+        .collect();
+    let cleaned = cleaned.trim().trim_end_matches(['.', ' ']).to_string();
+    if cleaned.is_empty() { "_".to_string() } else { cleaned }
+}
+
+/// The folder a file belongs in, relative to the library root. The template's segments are
+/// separated by "/" and may use {albumartist}, {artist}, {album}, {year} and {genre}.
+/// {albumartist} falls back to "Various Artists" for compilations and to the track artist
+/// otherwise.
+pub fn folder_for(file: &AudioFile, template: &str) -> PathBuf {
+    let album_artist = if !file.album_artist.trim().is_empty() {
+        file.album_artist.trim().to_string()
+    } else if file.compilation {
+        "Various Artists".to_string()
+    } else {
+        known_or(&file.artist, audio::UNKNOWN_ARTIST)
+    };
+    let year = file.year.map(|y| y.to_string()).unwrap_or_else(|| "Unknown Year".to_string());
+
+    template
+        .split(['/', '\\'])
+        .map(|segment| {
+            segment
+                .replace("{albumartist}", &album_artist)
+                .replace("{artist}", &known_or(&file.artist, audio::UNKNOWN_ARTIST))
+                .replace("{album}", &known_or(&file.album, audio::UNKNOWN_ALBUM))
+                .replace("{year}", &year)
+                .replace("{genre}", &known_or(&file.genre, "Unknown Genre"))
+        })
+        .filter(|segment| !segment.trim().is_empty())
+        .map(|segment| sanitize(&segment))
+        .collect()
+}
+
+/// Plans where each file goes under `root`. Files already in place are left out. When the
+/// destination is taken, on disk or by an earlier move in the plan, " (2)", " (3)" and so
+/// on is added to the file name.
+pub fn plan(files: &[AudioFile], root: &Path, template: &str) -> Vec<PlannedMove> {
+    let mut claimed: HashSet<PathBuf> = HashSet::new();
+    let mut moves = Vec::new();
+
+    for file in files {
+        let Some(name) = file.path.file_name() else {
+            continue;
+        };
+        let folder = root.join(folder_for(file, template));
+        if folder.join(name) == file.path {
+            continue;
+        }
+
+        let to = free_destination(&folder, &file.path, &claimed);
+        claimed.insert(to.clone());
+        moves.push(PlannedMove { from: file.path.clone(), to });
+    }
+    moves
+}
+
+/// Where `from` can go in `folder` without replacing anything: its own file name, or the
+/// name with " (2)", " (3)" and so on added when that is taken on disk or in `claimed`.
+pub fn free_destination(folder: &Path, from: &Path, claimed: &HashSet<PathBuf>) -> PathBuf {
+    let mut to = folder.join(from.file_name().unwrap_or_default());
+    let stem = from.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let extension = from.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    let mut n = 2;
+    while to.exists() || claimed.contains(&to) {
+        to = folder.join(format!("{} ({}){}", stem, n, extension));
+        n += 1;
+    }
+    to
+}
+
+/// Carries out one planned move, creating the folders it needs. Never overwrites a file
+/// that appeared at the destination after planning.
+pub fn execute(planned: &PlannedMove) -> Result<(), String> {
+    if planned.to.exists() {
+        return Err(format!("{} already exists", planned.to.display()));
+    }
+    if let Some(folder) = planned.to.parent() {
+        std::fs::create_dir_all(folder).map_err(|e| format!("Could not create {}: {}", folder.display(), e))?;
+    }
+    // rename fails across drives, so fall back to copy and delete
+    std::fs::rename(&planned.from, &planned.to)
+        .or_else(|_| std::fs::copy(&planned.from, &planned.to).and_then(|_| std::fs::remove_file(&planned.from)))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, artist: &str, album: &str) -> AudioFile {
+        AudioFile {
+            path: PathBuf::from(path),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            ..AudioFile::default()
+        }
+    }
+
+    #[test]
+    fn sanitize_replaces_reserved_characters_and_trailing_dots() {
+        assert_eq!(sanitize("AC/DC"), "AC_DC");
+        assert_eq!(sanitize("What? Now: Live"), "What_ Now_ Live");
+        assert_eq!(sanitize("Vol. 1..."), "Vol. 1");
+        assert_eq!(sanitize(".."), "_");
+        assert_eq!(sanitize("  "), "_");
+    }
+
+    #[test]
+    fn folder_for_fills_template_with_fallbacks() {
+        let mut track = file("/in/a.mp3", "AC/DC", "Back in Black");
+        track.year = Some(1980);
+        assert_eq!(folder_for(&track, "{albumartist}/{year} - {album}"), PathBuf::from("AC_DC").join("1980 - Back in Black"));
+
+        track.compilation = true;
+        assert_eq!(folder_for(&track, "{albumartist}/{album}"), PathBuf::from("Various Artists").join("Back in Black"));
+
+        let unknown = file("/in/b.mp3", audio::UNKNOWN_ARTIST, "");
+        assert_eq!(folder_for(&unknown, "{artist}//{album} ({genre})"), PathBuf::from("Unknown Artist").join("Unknown Album (Unknown Genre)"));
+    }
+
+    #[test]
+    fn plan_numbers_colliding_names() {
+        let root = std::env::temp_dir().join(format!("navitag-organizer-{}", std::process::id()));
+        let album = root.join("Artist").join("Album");
+        std::fs::create_dir_all(&album).unwrap();
+        std::fs::write(album.join("song.mp3"), b"").unwrap();
+
+        let files = [
+            file("/in/one/song.mp3", "Artist", "Album"),
+            file("/in/two/song.mp3", "Artist", "Album"),
+            file("/in/three/other.mp3", "Artist", "Album"),
+        ];
+        let targets: Vec<PathBuf> = plan(&files, &root, "{artist}/{album}").into_iter().map(|m| m.to).collect();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(targets, vec![album.join("song (2).mp3"), album.join("song (3).mp3"), album.join("other.mp3")]);
+    }
+}
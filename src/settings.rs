@@ -1,17 +1,336 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
 
+/// Region codes offered in the settings selector.
+pub const COUNTRIES: [&str; 24] = [
+    "US", "GB", "CA", "AU", "NZ", "IE", "DE", "FR", "ES", "IT", "NL", "BE",
+    "SE", "NO", "DK", "FI", "PL", "BR", "MX", "AR", "JP", "KR", "IN", "ZA",
+];
+
+const KEYRING_SERVICE: &str = "navitag";
+
+pub const DEFAULT_RESULT_LIMIT: u32 = 10;
+pub const MAX_RESULT_LIMIT: u32 = 50;
+pub const DEFAULT_MAX_EMBEDDED_COVER: u32 = 1200;
+pub const DEFAULT_COVER_JPEG_QUALITY: u8 = 90;
+pub const DEFAULT_PROVIDER_TIMEOUT_SECS: u64 = 10;
+pub const MAX_PROVIDER_TIMEOUT_SECS: u64 = 60;
+pub const DEFAULT_CONCURRENT_DOWNLOADS: usize = 4;
+pub const MAX_CONCURRENT_DOWNLOADS: usize = 16;
+pub const DEFAULT_VALUE_SEPARATOR: char = ';';
+pub const MAX_RECENT_FOLDERS: usize = 10;
+pub const DEFAULT_SEARCH_QUERY_TEMPLATE: &str = "{artist} {title}";
+/// Folder layout used by "Organize into Folders", relative to the chosen library root
+pub const DEFAULT_ORGANIZE_TEMPLATE: &str = "{albumartist}/{album}";
+/// Search providers by the name results carry as their source, in the default listing order.
+pub const DEFAULT_PROVIDER_PRIORITY: [&str; 5] = ["Apple Music", "Spotify", "Genius", "Last.fm", "Deezer"];
+pub const DEFAULT_TOAST_DURATION_SECS: u64 = 5;
+pub const MAX_TOAST_DURATION_SECS: u64 = 60;
+/// Profile created from the existing settings the first time profiles are used.
+pub const DEFAULT_PROFILE_NAME: &str = "Default";
+
+/// The warning for a config.json that couldn't be parsed, kept until settings are saved
+/// over it so the file is backed up only once.
+static DAMAGED_CONFIG: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ThemePref {
+    System,
+    Light,
+    #[default]
+    Dark,
+}
+
+impl ThemePref {
+    pub const ALL: [ThemePref; 3] = [ThemePref::System, ThemePref::Light, ThemePref::Dark];
+}
+
+impl std::fmt::Display for ThemePref {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ThemePref::System => "System",
+            ThemePref::Light => "Light",
+            ThemePref::Dark => "Dark",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Code page assumed when re-reading legacy tags that came out garbled. Text that is
+/// valid UTF-8 is always read as UTF-8 first.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum LegacyEncoding {
+    /// Cyrillic, the most common source of mis-encoded MP3 tags
+    #[default]
+    Windows1251,
+    Windows1252,
+    Windows1250,
+    Windows1253,
+    ShiftJis,
+    Gbk,
+    Big5,
+    EucKr,
+}
+
+impl LegacyEncoding {
+    pub const ALL: [LegacyEncoding; 8] = [
+        LegacyEncoding::Windows1251,
+        LegacyEncoding::Windows1252,
+        LegacyEncoding::Windows1250,
+        LegacyEncoding::Windows1253,
+        LegacyEncoding::ShiftJis,
+        LegacyEncoding::Gbk,
+        LegacyEncoding::Big5,
+        LegacyEncoding::EucKr,
+    ];
+
+    pub fn encoding(self) -> &'static encoding_rs::Encoding {
+        match self {
+            LegacyEncoding::Windows1251 => encoding_rs::WINDOWS_1251,
+            LegacyEncoding::Windows1252 => encoding_rs::WINDOWS_1252,
+            LegacyEncoding::Windows1250 => encoding_rs::WINDOWS_1250,
+            LegacyEncoding::Windows1253 => encoding_rs::WINDOWS_1253,
+            LegacyEncoding::ShiftJis => encoding_rs::SHIFT_JIS,
+            LegacyEncoding::Gbk => encoding_rs::GBK,
+            LegacyEncoding::Big5 => encoding_rs::BIG5,
+            LegacyEncoding::EucKr => encoding_rs::EUC_KR,
+        }
+    }
+}
+
+impl std::fmt::Display for LegacyEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            LegacyEncoding::Windows1251 => "Cyrillic (Windows-1251)",
+            LegacyEncoding::Windows1252 => "Western (Windows-1252)",
+            LegacyEncoding::Windows1250 => "Central European (Windows-1250)",
+            LegacyEncoding::Windows1253 => "Greek (Windows-1253)",
+            LegacyEncoding::ShiftJis => "Japanese (Shift_JIS)",
+            LegacyEncoding::Gbk => "Chinese Simplified (GBK)",
+            LegacyEncoding::Big5 => "Chinese Traditional (Big5)",
+            LegacyEncoding::EucKr => "Korean (EUC-KR)",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// ID3v2 revision written to MP3s (and WAV/AIFF files carrying ID3v2 chunks).
+/// Other tag formats (Vorbis comments, MP4 atoms, APE) are unaffected.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum Id3Version {
+    /// Read by practically every player, including older hardware
+    #[default]
+    V23,
+    V24,
+}
+
+impl Id3Version {
+    pub const ALL: [Id3Version; 2] = [Id3Version::V23, Id3Version::V24];
+}
+
+impl std::fmt::Display for Id3Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Id3Version::V23 => "ID3v2.3",
+            Id3Version::V24 => "ID3v2.4",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Where downloaded cover art goes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ArtworkMode {
+    /// Inside each audio file
+    #[default]
+    Embed,
+    /// As cover.jpg next to the files, which are left without embedded art
+    Folder,
+    Both,
+}
+
+impl ArtworkMode {
+    pub const ALL: [ArtworkMode; 3] = [ArtworkMode::Embed, ArtworkMode::Folder, ArtworkMode::Both];
+
+    pub fn embeds(&self) -> bool {
+        matches!(self, ArtworkMode::Embed | ArtworkMode::Both)
+    }
+
+    pub fn writes_folder_file(&self) -> bool {
+        matches!(self, ArtworkMode::Folder | ArtworkMode::Both)
+    }
+}
+
+impl std::fmt::Display for ArtworkMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ArtworkMode::Embed => "Embed in files",
+            ArtworkMode::Folder => "cover.jpg in folder",
+            ArtworkMode::Both => "Both",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// How cover art that isn't square is made into a square thumbnail.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ThumbnailFit {
+    /// Fill the square and cut off the overhanging edges
+    #[default]
+    Crop,
+    /// Show the whole image, padded with transparent bars
+    Letterbox,
+}
+
+impl ThumbnailFit {
+    pub const ALL: [ThumbnailFit; 2] = [ThumbnailFit::Crop, ThumbnailFit::Letterbox];
+}
+
+impl std::fmt::Display for ThumbnailFit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ThumbnailFit::Crop => "Crop to square",
+            ThumbnailFit::Letterbox => "Fit inside square",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Which optional fields the editor shows; hidden fields keep their values and are still saved.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EditorFields {
+    pub album_artist: bool,
+    pub composer: bool,
+    pub comment: bool,
+    pub lyrics: bool,
+    pub bpm: bool,
+}
+
+impl Default for EditorFields {
+    fn default() -> Self {
+        Self {
+            album_artist: true,
+            composer: true,
+            comment: true,
+            lyrics: true,
+            bpm: true,
+        }
+    }
+}
+
+/// A named set of the settings that differ between kinds of music: which providers to ask,
+/// the search query pattern and the editor fields.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TaggingProfile {
+    pub name: String,
+    pub enable_apple_music: bool,
+    pub enable_spotify: bool,
+    pub enable_genius: bool,
+    pub enable_lastfm: bool,
+    pub enable_deezer: bool,
+    pub search_query_template: String,
+    pub editor_fields: EditorFields,
+}
+
+impl Default for TaggingProfile {
+    fn default() -> Self {
+        UserSettings::default().snapshot_profile(DEFAULT_PROFILE_NAME)
+    }
+}
+
+/// Last known window size and position, in logical pixels.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct WindowGeometry {
+    pub width: f32,
+    pub height: f32,
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        Self {
+            width: 1024.0,
+            height: 768.0,
+            x: None,
+            y: None,
+        }
+    }
+}
+
+// Missing fields fall back to their defaults so older config files keep the keys they already have
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct UserSettings {
     pub spotify_id: String,
     pub spotify_secret: String,
     pub genius_token: String,
     pub lastfm_api_key: String,
+    pub acoustid_key: String,
     pub enable_apple_music: bool,
     pub enable_spotify: bool,
     pub enable_genius: bool,
     pub enable_lastfm: bool,
+    pub enable_deezer: bool,
+    pub theme: ThemePref,
+    pub result_limit: u32,
+    pub auto_save: bool,
+    pub country: String,
+    pub provider_timeout_secs: u64,
+    pub window: Option<WindowGeometry>,
+    pub id3_version: Id3Version,
+    /// When set, the secret fields live in the OS keychain and are blank in config.json
+    pub secrets_in_keyring: bool,
+    /// Largest width/height for downloaded covers before they are embedded; 0 keeps the original
+    pub max_embedded_cover: u32,
+    pub cover_jpeg_quality: u8,
+    /// How many search result thumbnails download at the same time
+    pub concurrent_downloads: usize,
+    pub artwork_mode: ArtworkMode,
+    pub thumbnail_fit: ThumbnailFit,
+    /// Saving erases everything except title, artist, album, year, track, genre and cover
+    pub minimal_tags: bool,
+    /// Applying a result also stores its MusicBrainz recording ID, Spotify ID and ISRC
+    pub write_provider_ids: bool,
+    /// Applying a result from Spotify, Apple Music or Deezer also sets the explicit flag
+    pub write_explicit_flag: bool,
+    /// Batch tagging leaves out files whose title, artist and album are already filled in
+    pub only_incomplete: bool,
+    /// Splits the artist and genre fields into multiple tag values; newlines always split too
+    pub value_separator: char,
+    /// Used by "Fix Encoding" for tags that aren't valid UTF-8
+    pub legacy_encoding: LegacyEncoding,
+    /// Most recently opened first
+    pub recent_folders: Vec<PathBuf>,
+    /// Builds the default search query from {artist}, {title} and {album}
+    pub search_query_template: String,
+    /// Provider names, most trusted first; search results are listed in this order
+    pub provider_priority: Vec<String>,
+    /// Folders files are moved into by the organizer, from {albumartist}, {artist}, {album}, {year} and {genre}
+    pub organize_template: String,
+    pub toast_duration_secs: u64,
+    /// Comma-separated file extensions scanned in addition to the built-in ones, as typed
+    pub extra_extensions: String,
+    /// Error notifications stay up until dismissed instead of timing out
+    pub sticky_error_toasts: bool,
+    /// Proxy URLs for provider requests and cover downloads; empty uses the environment
+    pub http_proxy: String,
+    pub https_proxy: String,
+    /// No searches, credential tests or cover downloads; for purely local tag editing
+    pub offline: bool,
+    pub editor_fields: EditorFields,
+    /// Saved profiles. The provider toggles, query template and editor fields above always
+    /// hold the active profile's values; they are copied back into it on save.
+    pub profiles: Vec<TaggingProfile>,
+    pub active_profile: String,
+    /// Profile last used in each recent folder
+    pub folder_profiles: BTreeMap<PathBuf, String>,
 }
 
 impl Default for UserSettings {
@@ -21,32 +340,368 @@ impl Default for UserSettings {
             spotify_secret: String::new(),
             genius_token: String::new(),
             lastfm_api_key: String::new(),
+            acoustid_key: String::new(),
             enable_apple_music: true,
             enable_spotify: false,
             enable_genius: false,
             enable_lastfm: false,
+            enable_deezer: true,
+            theme: ThemePref::default(),
+            result_limit: DEFAULT_RESULT_LIMIT,
+            auto_save: false,
+            country: system_country(),
+            provider_timeout_secs: DEFAULT_PROVIDER_TIMEOUT_SECS,
+            window: None,
+            id3_version: Id3Version::default(),
+            secrets_in_keyring: false,
+            max_embedded_cover: DEFAULT_MAX_EMBEDDED_COVER,
+            cover_jpeg_quality: DEFAULT_COVER_JPEG_QUALITY,
+            concurrent_downloads: DEFAULT_CONCURRENT_DOWNLOADS,
+            artwork_mode: ArtworkMode::default(),
+            thumbnail_fit: ThumbnailFit::default(),
+            minimal_tags: false,
+            write_provider_ids: true,
+            write_explicit_flag: false,
+            only_incomplete: false,
+            value_separator: DEFAULT_VALUE_SEPARATOR,
+            legacy_encoding: LegacyEncoding::default(),
+            recent_folders: Vec::new(),
+            search_query_template: DEFAULT_SEARCH_QUERY_TEMPLATE.to_string(),
+            provider_priority: DEFAULT_PROVIDER_PRIORITY.iter().map(|p| p.to_string()).collect(),
+            organize_template: DEFAULT_ORGANIZE_TEMPLATE.to_string(),
+            toast_duration_secs: DEFAULT_TOAST_DURATION_SECS,
+            extra_extensions: String::new(),
+            sticky_error_toasts: false,
+            http_proxy: String::new(),
+            https_proxy: String::new(),
+            offline: false,
+            editor_fields: EditorFields::default(),
+            profiles: Vec::new(),
+            active_profile: DEFAULT_PROFILE_NAME.to_string(),
+            folder_profiles: BTreeMap::new(),
         }
     }
 }
 
+/// Region from the OS locale (e.g. "en-GB" -> "GB"), falling back to US.
+fn system_country() -> String {
+    sys_locale::get_locale()
+        .and_then(|locale| {
+            locale
+                .split(['-', '_', '.'])
+                .skip(1)
+                .find(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic()))
+                .map(|part| part.to_ascii_uppercase())
+        })
+        .unwrap_or_else(|| "US".to_string())
+}
+
 impl UserSettings {
+    /// The configured region as an uppercase ISO code, if it looks valid.
+    pub fn country_code(&self) -> Option<String> {
+        let code = self.country.trim();
+        if code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+            Some(code.to_ascii_uppercase())
+        } else {
+            None
+        }
+    }
+
+    /// Number of results to request from each provider, kept within the range the APIs accept.
+    pub fn result_limit(&self) -> u32 {
+        self.result_limit.clamp(1, MAX_RESULT_LIMIT)
+    }
+
+    /// JPEG quality for resized covers, within the 1-100 range the encoder accepts.
+    pub fn cover_jpeg_quality(&self) -> u8 {
+        self.cover_jpeg_quality.clamp(1, 100)
+    }
+
+    /// Thumbnail downloads allowed at once, at least one.
+    pub fn concurrent_downloads(&self) -> usize {
+        self.concurrent_downloads.clamp(1, MAX_CONCURRENT_DOWNLOADS)
+    }
+
+    /// How long a single provider may take before its results are given up on.
+    pub fn provider_timeout(&self) -> Duration {
+        Duration::from_secs(self.provider_timeout_secs.clamp(1, MAX_PROVIDER_TIMEOUT_SECS))
+    }
+
+    /// Moves `folder` to the top of the recent list, dropping folders that no longer exist.
+    pub fn remember_folder(&mut self, folder: &Path) {
+        self.recent_folders.retain(|f| f != folder && f.is_dir());
+        self.recent_folders.insert(0, folder.to_path_buf());
+        self.recent_folders.truncate(MAX_RECENT_FOLDERS);
+        let recent = &self.recent_folders;
+        self.folder_profiles.retain(|f, _| recent.contains(f));
+    }
+
+    /// The current provider toggles, query template and editor fields under `name`.
+    fn snapshot_profile(&self, name: &str) -> TaggingProfile {
+        TaggingProfile {
+            name: name.to_string(),
+            enable_apple_music: self.enable_apple_music,
+            enable_spotify: self.enable_spotify,
+            enable_genius: self.enable_genius,
+            enable_lastfm: self.enable_lastfm,
+            enable_deezer: self.enable_deezer,
+            search_query_template: self.search_query_template.clone(),
+            editor_fields: self.editor_fields,
+        }
+    }
+
+    /// Turns a config from before profiles existed into one with a single default profile.
+    fn ensure_profiles(&mut self) {
+        if self.profiles.is_empty() {
+            let name = if self.active_profile.trim().is_empty() { DEFAULT_PROFILE_NAME.to_string() } else { self.active_profile.clone() };
+            self.profiles.push(self.snapshot_profile(&name));
+            self.active_profile = name;
+        }
+    }
+
+    /// Drops unknown and repeated names from `provider_priority` and appends any provider
+    /// missing from it, so every provider has exactly one place.
+    fn ensure_provider_priority(&mut self) {
+        let mut priority: Vec<String> = Vec::new();
+        for name in &self.provider_priority {
+            if DEFAULT_PROVIDER_PRIORITY.contains(&name.as_str()) && !priority.contains(name) {
+                priority.push(name.clone());
+            }
+        }
+        for name in DEFAULT_PROVIDER_PRIORITY {
+            if !priority.iter().any(|p| p == name) {
+                priority.push(name.to_string());
+            }
+        }
+        self.provider_priority = priority;
+    }
+
+    /// Position of a result's source in `provider_priority`; unknown sources come last.
+    pub fn provider_rank(&self, source: &str) -> usize {
+        self.provider_priority.iter().position(|p| p == source).unwrap_or(self.provider_priority.len())
+    }
+
+    /// Moves the provider at `index` one place towards the front, or towards the back
+    /// when `up` is false. Does nothing at either end.
+    pub fn move_provider(&mut self, index: usize, up: bool) {
+        let other = if up { index.checked_sub(1) } else { Some(index + 1) };
+        if let Some(other) = other.filter(|&o| o < self.provider_priority.len() && index < self.provider_priority.len()) {
+            self.provider_priority.swap(index, other);
+        }
+    }
+
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.iter().map(|p| p.name.clone()).collect()
+    }
+
+    /// Copies the current settings into the active profile.
+    pub fn update_active_profile(&mut self) {
+        let name = self.active_profile.clone();
+        self.save_profile_as(&name);
+    }
+
+    /// Stores the current settings as profile `name`, replacing one with the same name,
+    /// and makes it the active profile.
+    pub fn save_profile_as(&mut self, name: &str) {
+        let profile = self.snapshot_profile(name);
+        match self.profiles.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+        self.active_profile = name.to_string();
+    }
+
+    /// Loads profile `name` into the live settings. Returns false if there is no such profile.
+    pub fn activate_profile(&mut self, name: &str) -> bool {
+        let Some(profile) = self.profiles.iter().find(|p| p.name == name).cloned() else {
+            return false;
+        };
+        self.enable_apple_music = profile.enable_apple_music;
+        self.enable_spotify = profile.enable_spotify;
+        self.enable_genius = profile.enable_genius;
+        self.enable_lastfm = profile.enable_lastfm;
+        self.enable_deezer = profile.enable_deezer;
+        self.search_query_template = profile.search_query_template;
+        self.editor_fields = profile.editor_fields;
+        self.active_profile = profile.name;
+        true
+    }
+
+    /// Removes profile `name`, switching to the first remaining one if it was active.
+    /// The last profile can't be deleted.
+    pub fn delete_profile(&mut self, name: &str) -> Result<(), String> {
+        if self.profiles.len() <= 1 {
+            return Err("At least one profile has to remain.".to_string());
+        }
+        self.profiles.retain(|p| p.name != name);
+        self.folder_profiles.retain(|_, profile| profile != name);
+        if self.active_profile == name {
+            let first = self.profiles[0].name.clone();
+            self.activate_profile(&first);
+        }
+        Ok(())
+    }
+
+    /// Records the active profile as the one to use next time `folder` is opened.
+    pub fn remember_folder_profile(&mut self, folder: &Path) {
+        self.folder_profiles.insert(folder.to_path_buf(), self.active_profile.clone());
+    }
+
+    /// The profile last used in `folder`, if it still exists.
+    pub fn profile_for_folder(&self, folder: &Path) -> Option<&str> {
+        self.folder_profiles
+            .get(folder)
+            .filter(|name| self.profiles.iter().any(|p| &p.name == *name))
+            .map(String::as_str)
+    }
+
+    /// Recent folders that still exist on disk.
+    pub fn recent_folders(&self) -> impl Iterator<Item = &PathBuf> {
+        self.recent_folders.iter().filter(|f| f.is_dir())
+    }
+
+    /// How long a notification stays on screen.
+    pub fn toast_duration(&self) -> Duration {
+        Duration::from_secs(self.toast_duration_secs.clamp(1, MAX_TOAST_DURATION_SECS))
+    }
+
+    /// The valid entries of `extra_extensions`, lowercase and without dots.
+    pub fn extra_extensions(&self) -> Vec<String> {
+        crate::audio::parse_extensions(&self.extra_extensions).0
+    }
+
+    /// Built-in and extra extensions together, for messages listing what can be opened.
+    pub fn supported_extensions(&self) -> Vec<String> {
+        crate::audio::SUPPORTED_EXTENSIONS.iter().map(|e| e.to_string()).chain(self.extra_extensions()).collect()
+    }
+
     pub fn load() -> Self {
+        Self::load_reporting().0
+    }
+
+    /// Whether config.json couldn't be parsed and hasn't been saved over since. Settings
+    /// written on the side (window size, recent folders) should skip saving while this is
+    /// true, so only an explicit save replaces the damaged file.
+    pub fn config_damaged() -> bool {
+        DAMAGED_CONFIG.lock().is_ok_and(|warning| warning.is_some())
+    }
+
+    /// Like `load`, but also returns a warning when config.json exists and can't be parsed.
+    /// The first time that happens the unreadable file is copied to a timestamped .bak, so
+    /// the keys in it can be recovered after the defaults are saved over it.
+    pub fn load_reporting() -> (Self, Option<String>) {
         let config_path = Self::get_config_path();
+        let mut warning = None;
         if config_path.exists() {
-            if let Ok(content) = fs::read_to_string(config_path) {
-                if let Ok(settings) = serde_json::from_str(&content) {
-                    return settings;
+            match fs::read_to_string(&config_path) {
+                Ok(content) => match serde_json::from_str::<Self>(&content) {
+                    Ok(mut settings) => {
+                        if settings.secrets_in_keyring {
+                            settings.load_secrets();
+                        }
+                        settings.ensure_profiles();
+                        settings.ensure_provider_priority();
+                        return (settings, None);
+                    }
+                    Err(e) if !content.trim().is_empty() => {
+                        if let Ok(mut damaged) = DAMAGED_CONFIG.lock() {
+                            warning = Some(damaged.get_or_insert_with(|| Self::back_up_corrupt(&config_path, &e.to_string())).clone());
+                        }
+                    }
+                    Err(_) => {}
+                },
+                Err(e) => warning = Some(format!("Could not read {}: {}. Using default settings.", config_path.display(), e)),
+            }
+        }
+
+        let mut settings = Self::default();
+        if warning.is_some() {
+            // Secrets may still be in the keychain; saving the defaults without them would delete them
+            settings.load_secrets();
+        }
+        settings.ensure_profiles();
+        (settings, warning)
+    }
+
+    /// Copies an unparseable config next to itself as config.json.<unix time>.bak, so an
+    /// earlier backup is never overwritten, and describes what happened.
+    fn back_up_corrupt(config_path: &Path, error: &str) -> String {
+        let stamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let backup = config_path.with_extension(format!("json.{}.bak", stamp));
+        match fs::copy(config_path, &backup) {
+            Ok(_) => format!(
+                "{} is damaged ({}). Default settings are in use; the old file was kept as {} so you can recover your API keys.",
+                config_path.display(),
+                error,
+                backup.display()
+            ),
+            Err(e) => format!(
+                "{} is damaged ({}) and could not be backed up ({}). Default settings are in use; copy the file somewhere safe before saving settings.",
+                config_path.display(),
+                error,
+                e
+            ),
+        }
+    }
+
+    /// Writes config.json, moving the API secrets into the OS keychain when one is available.
+    /// Returns a warning if the secrets had to be written to the file in plaintext instead.
+    pub fn save(&self) -> Option<String> {
+        let mut on_disk = self.clone();
+        let warning = match self.store_secrets() {
+            Ok(()) => {
+                on_disk.secrets_in_keyring = true;
+                for (_, value) in on_disk.secrets_mut() {
+                    value.clear();
+                }
+                None
+            }
+            Err(e) => {
+                on_disk.secrets_in_keyring = false;
+                Some(format!("No system keychain available ({}); API secrets were saved in plaintext.", e))
+            }
+        };
+
+        let config_path = Self::get_config_path();
+        if let Ok(content) = serde_json::to_string_pretty(&on_disk) {
+            if fs::write(config_path, content).is_ok() {
+                if let Ok(mut damaged) = DAMAGED_CONFIG.lock() {
+                    *damaged = None;
                 }
             }
         }
-        Self::default()
+        warning
+    }
+
+    fn secrets_mut(&mut self) -> [(&'static str, &mut String); 4] {
+        [
+            ("spotify_secret", &mut self.spotify_secret),
+            ("genius_token", &mut self.genius_token),
+            ("lastfm_api_key", &mut self.lastfm_api_key),
+            ("acoustid_key", &mut self.acoustid_key),
+        ]
+    }
+
+    fn load_secrets(&mut self) {
+        for (name, value) in self.secrets_mut() {
+            if let Ok(secret) = keyring::Entry::new(KEYRING_SERVICE, name).and_then(|e| e.get_password()) {
+                *value = secret;
+            }
+        }
     }
 
-    pub fn save(&self) {
-         let config_path = Self::get_config_path();
-         if let Ok(content) = serde_json::to_string_pretty(self) {
-             let _ = fs::write(config_path, content);
-         }
+    fn store_secrets(&self) -> Result<(), String> {
+        let mut secrets = self.clone();
+        for (name, value) in secrets.secrets_mut() {
+            let entry = keyring::Entry::new(KEYRING_SERVICE, name).map_err(|e| e.to_string())?;
+            if value.is_empty() {
+                // Nothing to keep; a missing entry is fine
+                let _ = entry.delete_credential();
+            } else {
+                entry.set_password(value).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
     }
 
     fn get_config_path() -> PathBuf {
@@ -54,3 +709,15 @@ impl UserSettings {
         PathBuf::from("config.json")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_take_their_defaults() {
+        let settings: UserSettings = serde_json::from_str(r#"{"spotify_id":"x"}"#).unwrap();
+        let expected = UserSettings { spotify_id: "x".to_string(), ..UserSettings::default() };
+        assert_eq!(settings, expected);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::audio::AudioFile;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::Duration;
+
+/// Overview of the loaded folder, recomputed from the file list on every render.
+#[derive(Debug, Default)]
+pub struct FolderStats {
+    pub total: usize,
+    pub missing_cover: usize,
+    pub unknown_artist: usize,
+    pub unknown_album: usize,
+    pub missing_year: usize,
+    pub unsaved: usize,
+    pub duration: Duration,
+    /// File count per format name, e.g. "FLAC" -> 12
+    pub formats: BTreeMap<String, usize>,
+}
+
+fn is_unknown(value: &str) -> bool {
+    crate::audio::is_placeholder(value)
+}
+
+pub fn compute(files: &[AudioFile]) -> FolderStats {
+    let mut stats = FolderStats { total: files.len(), ..Default::default() };
+    for file in files {
+        if file.picture_data.is_none() {
+            stats.missing_cover += 1;
+        }
+        if is_unknown(&file.artist) {
+            stats.unknown_artist += 1;
+        }
+        if is_unknown(&file.album) {
+            stats.unknown_album += 1;
+        }
+        if file.year.is_none() {
+            stats.missing_year += 1;
+        }
+        if file.dirty {
+            stats.unsaved += 1;
+        }
+        stats.duration += file.duration;
+        *stats.formats.entry(file.format.clone()).or_default() += 1;
+    }
+    stats
+}
+
+/// Total playing time such as "3h 07m", or "42m 10s" under an hour.
+pub fn format_total_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// Problems that make players split or misorder an album: files in one folder with
+/// different album or album artist names, and missing or repeated track numbers.
+/// Folders holding a single file are skipped.
+pub fn album_warnings(files: &[AudioFile]) -> Vec<String> {
+    let mut folders: BTreeMap<&Path, Vec<&AudioFile>> = BTreeMap::new();
+    for file in files {
+        if let Some(folder) = file.path.parent() {
+            folders.entry(folder).or_default().push(file);
+        }
+    }
+    let name_folders = folders.len() > 1;
+
+    let mut warnings = Vec::new();
+    for (folder, files) in folders.into_iter().filter(|(_, files)| files.len() > 1) {
+        let prefix = if name_folders {
+            format!("{}: ", folder.file_name().unwrap_or_default().to_string_lossy())
+        } else {
+            String::new()
+        };
+
+        let albums: BTreeSet<&str> = files.iter().map(|f| f.album.trim()).filter(|a| !is_unknown(a)).collect();
+        if albums.len() > 1 {
+            warnings.push(format!("{}{} different album names ({})", prefix, albums.len(), quoted_list(&albums)));
+        }
+
+        let album_artists: BTreeSet<&str> = files.iter().map(|f| f.album_artist.trim()).collect();
+        if album_artists.len() > 1 {
+            let named: BTreeSet<&str> = album_artists.iter().copied().filter(|a| !a.is_empty()).collect();
+            if named.len() > 1 {
+                warnings.push(format!("{}{} different album artists ({})", prefix, named.len(), quoted_list(&named)));
+            } else {
+                let missing = files.iter().filter(|f| f.album_artist.trim().is_empty()).count();
+                warnings.push(format!("{}{} files have no album artist while the others do", prefix, missing));
+            }
+        }
+
+        let missing_track = files.iter().filter(|f| f.track.is_none()).count();
+        if missing_track > 0 {
+            warnings.push(format!("{}{} files have no track number", prefix, missing_track));
+        }
+
+        let mut track_counts: BTreeMap<u32, usize> = BTreeMap::new();
+        for track in files.iter().filter_map(|f| f.track) {
+            *track_counts.entry(track).or_default() += 1;
+        }
+        let repeated: Vec<String> = track_counts.iter().filter(|(_, count)| **count > 1).map(|(track, _)| track.to_string()).collect();
+        if !repeated.is_empty() {
+            warnings.push(format!("{}Track numbers used more than once: {}", prefix, repeated.join(", ")));
+        }
+    }
+    warnings
+}
+
+/// Up to three names in quotes, followed by "and N more" when there are others.
+fn quoted_list(names: &BTreeSet<&str>) -> String {
+    let shown: Vec<String> = names.iter().take(3).map(|name| format!("\"{}\"", name)).collect();
+    match names.len().saturating_sub(shown.len()) {
+        0 => shown.join(", "),
+        more => format!("{} and {} more", shown.join(", "), more),
+    }
+}
+
+/// Formats by file count, most common first, e.g. "FLAC 12 · MP3 3".
+pub fn format_breakdown(formats: &BTreeMap<String, usize>) -> String {
+    let mut formats: Vec<(&String, &usize)> = formats.iter().collect();
+    formats.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    formats
+        .iter()
+        .map(|(format, count)| format!("{} {}", format, count))
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
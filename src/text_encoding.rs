@@ -0,0 +1,37 @@
+use encoding_rs::Encoding;
+
+/// The bytes a string was decoded from, if it could have come from a Latin-1 frame
+/// (ID3v2 text encoding 0). Latin-1 maps every byte to exactly one char, so the raw
+/// tag bytes can be recovered from the decoded text without rereading the file.
+fn latin1_bytes(value: &str) -> Option<Vec<u8>> {
+    value.chars().map(|c| u8::try_from(u32::from(c)).ok()).collect()
+}
+
+/// Whether the text looks like bytes in another encoding that were read as Latin-1:
+/// valid UTF-8 sequences, C1 control characters, or mostly accented letters (how
+/// Cyrillic and Greek code pages come out).
+pub fn looks_misdecoded(value: &str) -> bool {
+    let Some(bytes) = latin1_bytes(value) else {
+        return false;
+    };
+    let high = bytes.iter().filter(|b| **b >= 0x80).count();
+    if high == 0 {
+        return false;
+    }
+    if std::str::from_utf8(&bytes).is_ok() || bytes.iter().any(|b| (0x80..0xA0).contains(b)) {
+        return true;
+    }
+
+    let letters = value.chars().filter(|c| c.is_alphabetic()).count();
+    high * 2 > letters
+}
+
+/// Re-reads the text's original bytes: as UTF-8 when they are valid UTF-8, otherwise
+/// in `fallback`. Returns None when the text can't have come from a Latin-1 frame.
+pub fn repair(value: &str, fallback: &'static Encoding) -> Option<String> {
+    let bytes = latin1_bytes(value)?;
+    match String::from_utf8(bytes) {
+        Ok(utf8) => Some(utf8),
+        Err(e) => Some(fallback.decode_without_bom_handling(e.as_bytes()).0.into_owned()),
+    }
+}
@@ -1,46 +1,84 @@
-use iced::widget::{container, row, text};
+use iced::widget::{button, container, row, text};
 use iced::{Element, Length, Theme};
 use std::time::{Duration, Instant};
 
+pub const DEFAULT_DURATION: Duration = Duration::from_secs(5);
+/// Older toasts beyond this are summarized as "+N more".
+pub const MAX_VISIBLE: usize = 4;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Status {
     Success,
     Error,
+    Warning,
     Info,
 }
 
 #[derive(Debug, Clone)]
 pub struct Toast {
+    /// Assigned by `Manager::add`, used to dismiss the toast
+    pub id: u64,
     pub title: String,
     pub body: String,
     pub status: Status,
     pub created_at: Instant,
-    pub duration: Duration,
+    /// None keeps the toast up until it is dismissed
+    pub duration: Option<Duration>,
 }
 
 impl Toast {
     pub fn new(status: Status, title: impl Into<String>, body: impl Into<String>) -> Self {
         Self {
+            id: 0,
             title: title.into(),
             body: body.into(),
             status,
             created_at: Instant::now(),
-            duration: Duration::from_secs(5),
+            duration: Some(DEFAULT_DURATION),
         }
     }
 
-    pub fn expired(&self) -> bool {
-        self.created_at.elapsed() >= self.duration
+    /// Whether the toast's time is up at `now`.
+    pub fn expired_at(&self, now: Instant) -> bool {
+        self.duration.is_some_and(|duration| now.saturating_duration_since(self.created_at) >= duration)
     }
 }
 
+/// WCAG relative luminance of an sRGB color, from 0.0 (black) to 1.0 (white).
+fn luminance(color: iced::Color) -> f32 {
+    let channel = |c: f32| {
+        if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * channel(color.r) + 0.7152 * channel(color.g) + 0.0722 * channel(color.b)
+}
+
+/// Black or white, whichever has the higher contrast ratio against `background`.
+fn readable_on(background: iced::Color) -> iced::Color {
+    let l = luminance(background);
+    let against_white = 1.05 / (l + 0.05);
+    let against_black = (l + 0.05) / 0.05;
+    if against_black > against_white { iced::Color::BLACK } else { iced::Color::WHITE }
+}
+
+fn readable_text(theme: &Theme) -> text::Style {
+    text::Style { color: Some(readable_on(theme.palette().background)) }
+}
+
 pub struct Manager {
     toasts: Vec<Toast>,
+    next_id: u64,
+    duration: Duration,
+    sticky_errors: bool,
 }
 
 impl Default for Manager {
     fn default() -> Self {
-        Self { toasts: Vec::new() }
+        Self {
+            toasts: Vec::new(),
+            next_id: 0,
+            duration: DEFAULT_DURATION,
+            sticky_errors: false,
+        }
     }
 }
 
@@ -49,29 +87,67 @@ impl Manager {
         Self::default()
     }
 
-    pub fn add(&mut self, toast: Toast) {
+    /// Sets how long new toasts stay up, and whether errors stay until dismissed.
+    pub fn configure(&mut self, duration: Duration, sticky_errors: bool) {
+        self.duration = duration;
+        self.sticky_errors = sticky_errors;
+    }
+
+    pub fn add(&mut self, mut toast: Toast) {
+        toast.duration = if self.sticky_errors && toast.status == Status::Error {
+            None
+        } else {
+            Some(self.duration)
+        };
+        toast.id = self.next_id;
+        self.next_id += 1;
         self.toasts.push(toast);
     }
 
-    pub fn update(&mut self) {
-        self.toasts.retain(|t| !t.expired());
+    /// Adds a toast that stays up until dismissed, whatever the configured duration.
+    pub fn add_persistent(&mut self, toast: Toast) {
+        self.add(toast);
+        if let Some(added) = self.toasts.last_mut() {
+            added.duration = None;
+        }
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.toasts.retain(|t| t.id != id);
+    }
+
+    /// Drops the toasts expired at `now`. Call it periodically while `is_empty` is false.
+    pub fn update(&mut self, now: Instant) {
+        self.toasts.retain(|t| !t.expired_at(now));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
     }
 
-    pub fn view<'a, Message: 'a>(&'a self) -> Element<'a, Message> {
-        let content = iced::widget::column(
-            self.toasts
+    /// Renders the toasts; each one's close button sends `on_dismiss(toast.id)`.
+    pub fn view<'a, Message: Clone + 'a>(&'a self, on_dismiss: impl Fn(u64) -> Message + 'a) -> Element<'a, Message> {
+        let now = Instant::now();
+        let visible: Vec<&Toast> = self.toasts.iter().filter(|t| !t.expired_at(now)).collect();
+        let hidden = visible.len().saturating_sub(MAX_VISIBLE);
+
+        let mut content = iced::widget::column(
+            visible
                 .iter()
                 .rev()
+                .take(MAX_VISIBLE)
                 .map(|toast| {
                     let icon = match toast.status {
                         Status::Success => "[OK]",
                         Status::Error => "[!]",
+                        Status::Warning => "/!\\",
                         Status::Info => "(i)",
                     };
 
                     let color = match toast.status {
                         Status::Success => iced::Color::from_rgb(0.1, 0.8, 0.1),
                         Status::Error => iced::Color::from_rgb(0.8, 0.1, 0.1),
+                        Status::Warning => iced::Color::from_rgb(0.9, 0.6, 0.1),
                         Status::Info => iced::Color::from_rgb(0.1, 0.1, 0.8),
                     };
 
@@ -79,9 +155,13 @@ impl Manager {
                         row![
                             text(icon).size(20).color(color),
                             iced::widget::column![
-                                text(&toast.title).size(14).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }),
-                                text(&toast.body).size(12)
-                            ].spacing(2)
+                                text(&toast.title).size(14).font(iced::Font { weight: iced::font::Weight::Bold, ..Default::default() }).style(readable_text),
+                                text(&toast.body).size(12).style(readable_text)
+                            ].spacing(2).width(Length::Fill),
+                            button(text("×").size(14))
+                                .on_press(on_dismiss(toast.id))
+                                .padding(2)
+                                .style(button::text),
                         ]
                         .spacing(10)
                         .align_y(iced::Alignment::Center)
@@ -108,6 +188,24 @@ impl Manager {
         )
         .spacing(10);
 
+        if hidden > 0 {
+            content = content.push(
+                container(text(format!("+{} more", hidden)).size(12).style(readable_text))
+                    .padding(5)
+                    .width(300)
+                    .align_x(iced::alignment::Horizontal::Center)
+                    .style(|theme: &Theme| container::Style {
+                        background: Some(theme.palette().background.into()),
+                        border: iced::border::Border {
+                            color: theme.palette().text,
+                            width: 1.0,
+                            radius: 5.0.into(),
+                        },
+                        ..Default::default()
+                    }),
+            );
+        }
+
         container(content)
             .width(Length::Fill)
             .height(Length::Fill)
@@ -117,3 +215,24 @@ impl Manager {
             .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_prunes_expired_toasts_but_keeps_sticky_errors() {
+        let mut manager = Manager::new();
+        manager.configure(Duration::from_secs(5), true);
+        manager.add(Toast::new(Status::Success, "Saved", ""));
+        manager.add(Toast::new(Status::Error, "Failed", ""));
+        let start = manager.toasts[0].created_at;
+
+        manager.update(start + Duration::from_secs(4));
+        assert_eq!(manager.toasts.len(), 2);
+
+        manager.update(start + Duration::from_secs(6));
+        let titles: Vec<&str> = manager.toasts.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Failed"]);
+    }
+}